@@ -1,17 +1,39 @@
 mod tcc;
 
-#[cfg(test)]
 use clap::CommandFactory;
-#[cfg(test)]
+use clap::builder::PossibleValuesParser;
 use clap::error::ErrorKind;
-use clap::{Parser, Subcommand};
-use colored::Colorize;
+use clap::{ArgGroup, Args, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use colored::{Color, Colorize};
+use regex::Regex;
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::{env, process};
 
-use tcc::{DbTarget, SERVICE_MAP, TccDb, TccEntry, TccError, auth_value_display, compact_client};
+use tcc::{
+    AccessRow, AuthStatus, CheckStatus, ColumnChange, DbTarget, DoctorCheck, DoctorFix,
+    DuplicateGroup, EffectiveAuth, HistoryBucket, InfoReport, ListFilter, ListResult, MeasuredList,
+    Op, RawRow, Redactor, ResetOutcome, RestoreOutcome, SERVICE_MAP, SchemaDigest, SchemaPolicy,
+    SelfTestCheck, Service, ServiceAccess, ServiceFamily, SortKey, SourceCount, SourceFailure,
+    SourceLabel, TccDb, TccEntry, TccError, auth_reason_display, auth_value_display, base64,
+    bucket_history, client_type_display, compact_client, dedupe_entries, diff_access_rows,
+    entries_checksum, retain_newer_than,
+};
 
 #[derive(Parser, Debug)]
-#[command(name = "tccutil-rs", about = "Manage macOS TCC permissions", version)]
+#[command(
+    name = "tccutil-rs",
+    about = "Manage macOS TCC permissions",
+    version,
+    arg_required_else_help = true
+)]
 struct Cli {
     /// Operate on user DB instead of system DB
     #[arg(short, long, global = true)]
@@ -21,76 +43,850 @@ struct Cli {
     #[arg(short = 'j', long, global = true)]
     json: bool,
 
+    /// Emit CSV: a header and one row per entry for `list`, one summary row otherwise
+    #[arg(long, global = true, conflicts_with = "json")]
+    csv: bool,
+
+    /// Use this home directory to locate the user DB
+    #[arg(long, global = true, value_name = "DIR")]
+    home: Option<PathBuf>,
+
+    /// Read and write this one DB file instead of the user and system DBs
+    #[arg(long, global = true, value_name = "PATH", conflicts_with_all = ["user", "home"])]
+    db: Option<PathBuf>,
+
+    /// With --home or --db, list nothing instead of failing when that DB does not exist
+    #[arg(long, global = true)]
+    allow_missing_db: bool,
+
+    /// Canonicalize DB paths, following symlinks to the real files
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Draw tables with ASCII only (no ditto marks, box rules, or bars)
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Parse every JSON document before printing it, failing instead of emitting invalid JSON
+    #[arg(long, global = true)]
+    strict_json: bool,
+
+    /// Retry grant/revoke/enable/disable up to N times while tccd holds the user DB locked
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retry: u32,
+
+    /// Schema digest policy for writes (default: warn on unknown and proceed)
+    #[arg(long, global = true, value_enum, value_name = "POLICY")]
+    assume_schema: Option<AssumeSchema>,
+
+    /// How statuses are colored in tables
+    #[arg(long, global = true, value_enum, value_name = "SCHEME", default_value_t = ColorScheme::Default)]
+    color_scheme: ColorScheme,
+
+    /// When to color output (JSON and CSV are never colored)
+    #[arg(long, global = true, value_enum, value_name = "WHEN", default_value_t = ColorWhen::Auto)]
+    color: ColorWhen,
+
+    /// Never color output; same as --color never
+    #[arg(long, global = true, conflicts_with = "color")]
+    no_color: bool,
+
+    /// Print the access schema digest of each targeted DB and exit
+    #[arg(long, global = true)]
+    hash_schema: bool,
+
+    /// Exit 1 before running the command unless every targeted DB has this schema digest;
+    /// without a command, print the expected and actual digests and exit 0 or 1
+    #[arg(long, global = true, value_name = "DIGEST")]
+    verify_schema: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum AssumeSchema {
+    /// Skip digest validation and proceed as if the schema is known
+    Known,
+    /// Refuse to write when the schema digest is unknown
+    Strict,
+}
+
+impl From<AssumeSchema> for SchemaPolicy {
+    fn from(value: AssumeSchema) -> Self {
+        match value {
+            AssumeSchema::Known => SchemaPolicy::AssumeKnown,
+            AssumeSchema::Strict => SchemaPolicy::Strict,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ColorScheme {
+    /// Green granted, red denied, yellow limited
+    Default,
+    /// Blue granted, orange denied, magenta limited (safe for red-green color blindness)
+    Colorblind,
+    /// No color; a mark before each status instead
+    Mono,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ColorWhen {
+    /// Color when writing to a terminal, unless NO_COLOR is set
+    Auto,
+    /// Color even through pipes and redirects
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Forced coloring for `colored`, or `None` to keep its own terminal detection.
+/// Machine-readable output always turns it off; an explicit flag beats `NO_COLOR`.
+fn color_override(
+    when: ColorWhen,
+    no_color: bool,
+    no_color_env: Option<&std::ffi::OsStr>,
+    machine_output: bool,
+) -> Option<bool> {
+    if machine_output || no_color {
+        return Some(false);
+    }
+    match when {
+        ColorWhen::Always => Some(true),
+        ColorWhen::Never => Some(false),
+        // no-color.org: set and not empty
+        ColorWhen::Auto if no_color_env.is_some_and(|v| !v.is_empty()) => Some(false),
+        ColorWhen::Auto => None,
+    }
+}
+
+impl ColorScheme {
+    fn status_color(self, auth_value: i32) -> Option<Color> {
+        match (self, auth_value) {
+            (ColorScheme::Default, 0) => Some(Color::Red),
+            (ColorScheme::Default, 2) => Some(Color::Green),
+            (ColorScheme::Default, 3) => Some(Color::Yellow),
+            (ColorScheme::Colorblind, 0) => Some(Color::TrueColor {
+                r: 230,
+                g: 159,
+                b: 0,
+            }),
+            (ColorScheme::Colorblind, 2) => Some(Color::Blue),
+            (ColorScheme::Colorblind, 3) => Some(Color::Magenta),
+            _ => None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Source {
+    User,
+    System,
+    Both,
+}
+
+impl Source {
+    /// The one DB to keep rows of, or `None` for both
+    fn label(self) -> Option<SourceLabel> {
+        match self {
+            Source::User => Some(SourceLabel::User),
+            Source::System => Some(SourceLabel::System),
+            Source::Both => None,
+        }
+    }
+}
+
+/// `--csv` has no form for list's other output modes. Checked here rather
+/// than by clap, which misses the conflict when the global `--csv` comes
+/// before the subcommand.
+fn check_csv_list(csv: bool, modes: &[(&str, bool)]) -> Result<(), clap::Error> {
+    match modes.iter().find(|(_, set)| *set) {
+        Some((flag, _)) if csv => Err(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            format!("the argument '{}' cannot be used with '--csv'", flag),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// `--csv` prints list rows, or the message of a command that changes
+/// the DB; other commands, and `schema_only` runs that print digests
+/// instead of running the command, have no row to print, so they are refused
+fn check_csv_command(csv: bool, schema_only: bool, command: &Commands) -> Result<(), clap::Error> {
+    let summarized = matches!(
+        command,
+        Commands::List { .. }
+            | Commands::Grant { .. }
+            | Commands::Revoke { .. }
+            | Commands::Enable { .. }
+            | Commands::Disable { .. }
+            | Commands::Toggle { .. }
+            | Commands::Set { .. }
+            | Commands::Reset { .. }
+            | Commands::Restore { .. }
+    );
+    if !csv || (summarized && !schema_only) {
+        return Ok(());
+    }
+    Err(Cli::command().error(
+        ErrorKind::ArgumentConflict,
+        "'--csv' only applies to list and to grant, revoke, enable, disable, toggle, set, reset and restore",
+    ))
+}
+
+/// Highest auth_value macOS is known to write; `set` needs `--force` above it
+const MAX_AUTH_VALUE: i32 = 3;
+
+/// Keep a typo from writing an auth_value tccd never uses
+fn check_auth_value(value: i32, force: bool) -> Result<(), clap::Error> {
+    if !force && !(0..=MAX_AUTH_VALUE).contains(&value) {
+        return Err(Cli::command().error(
+            ErrorKind::ValueValidation,
+            format!(
+                "auth_value {} is outside 0-{}; pass --force to write it anyway",
+                value, MAX_AUTH_VALUE
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// An empty window is almost certainly swapped bounds
+fn check_time_window(since: Option<i64>, until: Option<i64>) -> Result<(), clap::Error> {
+    match (since, until) {
+        (Some(since), Some(until)) if since > until => Err(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            format!(
+                "--since ({}) is later than --until ({}), so nothing could match",
+                since, until
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Only Automation entries are scoped to a target app
+fn check_target_app(service: &str, target_app: Option<&str>) -> Result<(), clap::Error> {
+    match Service::try_from(service) {
+        Ok(svc) if target_app.is_some() && !svc.needs_indirect_object() => Err(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            format!(
+                "the TARGET argument only applies to Automation (kTCCServiceAppleEvents), not {}",
+                svc.display_name()
+            ),
+        )),
+        // An unknown service is reported when it is resolved for the write
+        _ => Ok(()),
+    }
+}
+
+/// Whether `command` may write to the DB it opens
+fn writes_db(command: &Commands) -> bool {
+    match command {
+        Commands::Grant { .. }
+        | Commands::Revoke { .. }
+        | Commands::Enable { .. }
+        | Commands::Disable { .. }
+        | Commands::Toggle { .. }
+        | Commands::Set { .. }
+        | Commands::Reset { .. } => true,
+        Commands::Restore { dry_run, .. } => !dry_run,
+        Commands::Repair { fix_duplicates } => *fix_duplicates,
+        Commands::Doctor { fix, .. } => *fix,
+        _ => false,
+    }
+}
+
+/// `--db -` is a read-only copy of stdin, so commands that write are refused
+fn check_stdin_db(db: Option<&Path>, command: &Commands) -> Result<(), clap::Error> {
+    if db != Some(Path::new("-")) || !writes_db(command) {
+        return Ok(());
+    }
+    Err(Cli::command().error(
+        ErrorKind::ArgumentConflict,
+        "'--db -' reads the DB from stdin read-only; write to a file with '--db <PATH>' instead",
+    ))
+}
+
+/// `--source system` asks for rows `--user` and `--db` never read
+fn check_source_target(source: Source, target: DbTarget) -> Result<(), clap::Error> {
+    let reason = match target {
+        DbTarget::User => "'--user' (--user reads only the user DB)",
+        DbTarget::File => "'--db' (rows of the --db file are labelled user)",
+        DbTarget::Default => return Ok(()),
+    };
+    if source == Source::System {
+        return Err(Cli::command().error(
+            ErrorKind::ArgumentConflict,
+            format!(
+                "the argument '--source system' cannot be used with {}",
+                reason
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Auth {
+    Denied,
+    Granted,
+    Limited,
+    /// Any auth_value other than denied (0), granted (2) and limited (3)
+    Unknown,
+}
+
+impl From<Auth> for AuthStatus {
+    fn from(value: Auth) -> Self {
+        match value {
+            Auth::Denied => AuthStatus::Denied,
+            Auth::Granted => AuthStatus::Granted,
+            Auth::Limited => AuthStatus::Limited,
+            Auth::Unknown => AuthStatus::Unknown,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Sort {
+    Service,
+    Client,
+    /// By auth_value: denied, then granted, then limited
+    Status,
+    /// By modification time, entries without one first
+    Modified,
+    /// User DB rows, then system DB rows
+    Source,
+}
+
+impl From<Sort> for SortKey {
+    fn from(value: Sort) -> Self {
+        match value {
+            Sort::Service => SortKey::Service,
+            Sort::Client => SortKey::Client,
+            Sort::Status => SortKey::Status,
+            Sort::Modified => SortKey::Modified,
+            Sort::Source => SortKey::Source,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum JsonStream {
+    /// `[`, comma-separated entry objects, then `]`
+    Array,
+    /// One entry object per line
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum CsreqEncoding {
+    Hex,
+    Base64,
+}
+
+impl CsreqEncoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            CsreqEncoding::Hex => hex(bytes),
+            CsreqEncoding::Base64 => base64(bytes),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Family {
+    Devices,
+    Files,
+    Automation,
+    PersonalData,
+}
+
+impl From<Family> for ServiceFamily {
+    fn from(value: Family) -> Self {
+        match value {
+            Family::Devices => ServiceFamily::Devices,
+            Family::Files => ServiceFamily::Files,
+            Family::Automation => ServiceFamily::Automation,
+            Family::PersonalData => ServiceFamily::PersonalData,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Sqlite,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Bucket {
+    Day,
+    Month,
+}
+
+impl From<Bucket> for HistoryBucket {
+    fn from(value: Bucket) -> Self {
+        match value {
+            Bucket::Day => HistoryBucket::Day,
+            Bucket::Month => HistoryBucket::Month,
+        }
+    }
+}
+
+/// Options of `list`, boxed in `Commands::List` since they outweigh every
+/// other command's
+#[derive(Args, Debug)]
+struct ListArgs {
+    /// Filter by client name (partial match)
+    #[arg(long)]
+    client: Option<String>,
+    /// Filter by service name (partial match)
+    #[arg(long)]
+    service: Option<String>,
+    /// Filter by exact internal service key (e.g. kTCCServicePhotos)
+    #[arg(long, value_name = "KEY")]
+    service_key: Option<String>,
+    /// Filter by a regex searched in the client (e.g. '^com\.(apple|google)\.')
+    #[arg(long, value_name = "PATTERN", value_parser = parse_regex)]
+    client_regex: Option<Regex>,
+    /// Filter by a regex searched in the service name or internal key
+    #[arg(long, value_name = "PATTERN", value_parser = parse_regex)]
+    service_regex: Option<Regex>,
+    /// Only these services (comma-separated or repeated; names resolved like other commands)
+    #[arg(long, value_name = "SERVICES", value_delimiter = ',')]
+    only: Vec<String>,
+    /// Only show entries this session could modify right now (root, FDA, file permissions)
+    #[arg(long)]
+    only_modifiable: bool,
+    /// Compact mode: show only binary name instead of full path
+    #[arg(short, long)]
+    compact: bool,
+    /// In compact mode, keep the last N path components instead of just the binary name
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "compact",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    compact_depth: u64,
+    /// Keep the order rows were read in (user DB, then system DB) instead of sorting
+    #[arg(long)]
+    no_sort: bool,
+    /// Sort by this column; ties fall back to service, then client
+    #[arg(
+        long,
+        value_enum,
+        default_value = "service",
+        conflicts_with = "no_sort"
+    )]
+    sort: Sort,
+    /// Sort descending
+    #[arg(long, conflicts_with = "no_sort")]
+    reverse: bool,
+    /// Only entries modified later than the same service/client row in this other TCC.db (or missing there)
+    #[arg(long, value_name = "OTHER_DB", conflicts_with_all = ["json_stream", "template"])]
+    newer_than: Option<PathBuf>,
+    /// Cap clients at N characters with an ellipsis; JSON keeps the original in `client_full`
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    truncate_client: Option<u64>,
+    /// Add PID and LAST REMINDED columns where the schema has them
+    #[arg(long)]
+    wide: bool,
+    /// Collapse service/client pairs present in both DBs, keeping the system row
+    #[arg(long, overrides_with = "no_dedupe")]
+    dedupe: bool,
+    /// Show rows from both DBs even when they share a service/client pair (default)
+    #[arg(long, overrides_with = "dedupe")]
+    no_dedupe: bool,
+    /// Show rows for Apple's own TCC components such as tccd and System Settings (default)
+    #[arg(long, overrides_with = "no_tcc_internal")]
+    include_tcc_internal: bool,
+    /// Hide rows for Apple's own TCC components
+    #[arg(long, overrides_with = "include_tcc_internal")]
+    no_tcc_internal: bool,
+    /// Replace client identifiers with salted hashes (stable within one run)
+    #[arg(long)]
+    redact: bool,
+    /// Leave out the "N entries total" line under the table
+    #[arg(long)]
+    no_footer: bool,
+    /// Line under the table, e.g. '{count} entries, {granted} granted' (fields: count, granted, denied, limited, unknown, user, system)
+    #[arg(long, value_name = "FORMAT", value_parser = parse_footer, conflicts_with = "no_footer")]
+    footer_format: Option<FooterTemplate>,
+    /// Print each entry as this template instead of a table, e.g. '{service} {client} {status}'
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_template)]
+    template: Option<OutputTemplate>,
+    /// Only entries modified at or after this time: Unix seconds, an ISO-8601 date or date-time, or an age like 7d
+    #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+    since: Option<i64>,
+    /// Only entries modified at or before this time (same forms as --since)
+    #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+    until: Option<i64>,
+    /// Only entries with a modification time (hides N/A rows, usually system-seeded defaults)
+    #[arg(long)]
+    only_modified: bool,
+    /// Only rows from this DB, after reading every targeted DB (unlike --user, which also changes where writes go)
+    #[arg(
+        long,
+        visible_alias = "filter-source",
+        value_enum,
+        value_name = "SOURCE",
+        default_value = "both"
+    )]
+    source: Source,
+    /// Only entries with this status
+    #[arg(
+        long,
+        visible_alias = "status",
+        value_enum,
+        ignore_case = true,
+        value_name = "STATUS"
+    )]
+    auth: Option<Auth>,
+    /// Only entries written since the machine last booted (by boot_uuid, where the schema has it)
+    #[arg(long)]
+    since_boot: bool,
+    /// Report how many rows each DB held before filtering (stderr, or `measure` in JSON)
+    #[arg(long)]
+    measure: bool,
+    /// Write entries one at a time as a bare JSON array or NDJSON (no envelope)
+    #[arg(long, value_enum, value_name = "FRAMING")]
+    json_stream: Option<JsonStream>,
+    /// One block per client (and DB) listing each service's status, instead of one row per entry
+    #[arg(long, conflicts_with_all = ["json_stream", "template", "newer_than", "tabular_json", "distinct_clients"])]
+    app_centric: bool,
+    /// Print each client that has any entry once, sorted, instead of the entries
+    #[arg(long, conflicts_with_all = ["json_stream", "template", "newer_than", "tabular_json"])]
+    distinct_clients: bool,
+    /// JSON with one `columns` list and a `rows` array of arrays instead of entry objects
+    #[arg(long, conflicts_with_all = ["json_stream", "template", "newer_than"])]
+    tabular_json: bool,
+    /// Also print a SHA1 of the listed entries that does not depend on their order (`checksum` in JSON)
+    #[arg(long, conflicts_with_all = ["json_stream", "distinct_clients"])]
+    checksum: bool,
+    /// Show each client's code requirement (csreq) as hex or base64; JSON always uses base64.
+    /// Not with --redact: the blob names the client
+    #[arg(long, value_enum, value_name = "ENCODING", num_args = 0..=1,
+          default_missing_value = "hex", conflicts_with_all = ["distinct_clients", "app_centric", "redact"])]
+    show_csreq: Option<CsreqEncoding>,
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// List all TCC permissions
-    List {
-        /// Filter by client name (partial match)
-        #[arg(long)]
-        client: Option<String>,
-        /// Filter by service name (partial match)
-        #[arg(long)]
-        service: Option<String>,
-        /// Compact mode: show only binary name instead of full path
-        #[arg(short, long)]
-        compact: bool,
-    },
-    /// Grant a TCC permission (inserts new entry)
+    List(Box<ListArgs>),
+    /// Grant a TCC permission (inserts an entry, or sets an existing one to allowed)
     Grant {
         /// Service name (e.g. Accessibility, Camera)
         service: String,
         /// Client bundle ID or path
-        client_path: String,
+        #[arg(required_unless_present_any = ["pid", "client_file"])]
+        client_path: Option<String>,
+        /// For Automation (Apple Events): the bundle ID or path of the app the client may control
+        #[arg(value_name = "TARGET", requires = "client_path")]
+        target_app: Option<String>,
+        /// Use the running process with this PID as the client
+        #[arg(long, conflicts_with_all = ["client_path", "client_file"])]
+        pid: Option<i32>,
+        /// Read the client from this file (trimmed) instead of the argument
+        #[arg(long, value_name = "PATH", conflicts_with = "client_path")]
+        client_file: Option<PathBuf>,
+        /// Do nothing if an entry for this service/client already exists
+        #[arg(long, conflicts_with = "force")]
+        if_missing: bool,
+        /// Grant over an existing denied or limited entry instead of failing
+        #[arg(long)]
+        force: bool,
+        /// Copy a csreq this client already has stored for another service into the grant
+        #[arg(long)]
+        merge_csreq_from_db: bool,
+        /// Print the columns the grant changed, as before → after (`diff` in JSON)
+        #[arg(long)]
+        verbose_diff: bool,
     },
     /// Revoke a TCC permission (deletes entry)
     Revoke {
         /// Service name (e.g. Accessibility, Camera)
         service: String,
         /// Client bundle ID or path
-        client_path: String,
+        #[arg(required_unless_present_any = ["pid", "client_file"])]
+        client_path: Option<String>,
+        /// For Automation (Apple Events): the bundle ID or path of the app the client may control
+        #[arg(value_name = "TARGET", requires = "client_path")]
+        target_app: Option<String>,
+        /// Use the running process with this PID as the client
+        #[arg(long, conflicts_with_all = ["client_path", "client_file"])]
+        pid: Option<i32>,
+        /// Read the client from this file (trimmed) instead of the argument
+        #[arg(long, value_name = "PATH", conflicts_with = "client_path")]
+        client_file: Option<PathBuf>,
+        /// Print only the final counts, e.g. `Deleted 1 entry across 1 DB` (ignored with --json)
+        #[arg(long)]
+        summary_only: bool,
+        /// Print every column of the deleted rows before the result (JSON always has them)
+        #[arg(long, conflicts_with = "summary_only")]
+        show_deleted: bool,
+        /// Print the columns the revoke changed, as before → after (`diff` in JSON)
+        #[arg(long, conflicts_with = "summary_only")]
+        verbose_diff: bool,
     },
     /// Enable a TCC permission (set auth_value=2 for existing entry)
     Enable {
         /// Service name (e.g. Accessibility, Camera)
         service: String,
         /// Client bundle ID or path
-        client_path: String,
+        #[arg(required_unless_present_any = ["all_clients", "client_file"])]
+        client_path: Option<String>,
+        /// Read the client from this file (trimmed) instead of the argument
+        #[arg(long, value_name = "PATH", conflicts_with = "client_path")]
+        client_file: Option<PathBuf>,
+        /// Enable every existing entry of the service in one transaction
+        #[arg(long, conflicts_with_all = ["client_path", "client_file"], requires = "yes")]
+        all_clients: bool,
+        /// Confirm --all-clients
+        #[arg(short, long, requires = "all_clients")]
+        yes: bool,
     },
     /// Disable a TCC permission (set auth_value=0 for existing entry)
     Disable {
+        /// Service name (e.g. Accessibility, Camera)
+        service: String,
+        /// Client bundle ID or path
+        #[arg(required_unless_present_any = ["all_clients", "client_file"])]
+        client_path: Option<String>,
+        /// Read the client from this file (trimmed) instead of the argument
+        #[arg(long, value_name = "PATH", conflicts_with = "client_path")]
+        client_file: Option<PathBuf>,
+        /// Disable every existing entry of the service in one transaction
+        #[arg(long, conflicts_with_all = ["client_path", "client_file"], requires = "yes")]
+        all_clients: bool,
+        /// Confirm --all-clients
+        #[arg(short, long, requires = "all_clients")]
+        yes: bool,
+    },
+    /// Flip a TCC permission (granted becomes denied; denied or limited becomes granted)
+    Toggle {
+        /// Service name (e.g. Accessibility, Camera)
+        service: String,
+        /// Client bundle ID or path
+        #[arg(required_unless_present = "client_file")]
+        client_path: Option<String>,
+        /// Read the client from this file (trimmed) instead of the argument
+        #[arg(long, value_name = "PATH", conflicts_with = "client_path")]
+        client_file: Option<PathBuf>,
+    },
+    /// Set an existing entry's auth_value (0 denied, 1 unknown, 2 granted, 3 limited)
+    Set {
         /// Service name (e.g. Accessibility, Camera)
         service: String,
         /// Client bundle ID or path
         client_path: String,
+        /// The auth_value to write
+        #[arg(allow_negative_numbers = true)]
+        value: i32,
+        /// Write a value outside 0-3
+        #[arg(long)]
+        force: bool,
     },
     /// Reset (delete) TCC entries for a service
+    #[command(group = ArgGroup::new("confirmable").args(["interactive", "family"]).multiple(true))]
     Reset {
         /// Service name (e.g. Accessibility, Camera)
-        service: String,
+        #[arg(required_unless_present = "family")]
+        service: Option<String>,
         /// Optional: specific client to reset (if omitted, resets all entries for the service)
         client_path: Option<String>,
+        /// Read the specific client from this file (trimmed) instead of the argument
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["client_path", "keep", "confirm_each"])]
+        client_file: Option<PathBuf>,
+        /// Keep entries for this client while resetting the rest (repeatable)
+        #[arg(long, value_name = "CLIENT", conflicts_with = "client_path")]
+        keep: Vec<String>,
+        /// Ask before deleting each entry (y/N/a/q); needs an interactive terminal
+        #[arg(long, conflicts_with_all = ["client_path", "keep"])]
+        confirm_each: bool,
+        /// Show the entries that would be deleted, then ask once before deleting them
+        #[arg(long, conflicts_with = "confirm_each")]
+        interactive: bool,
+        /// Answer yes to the --interactive prompt, or confirm --family
+        #[arg(short, long, requires = "confirmable")]
+        yes: bool,
+        /// Print only the final counts, e.g. `Deleted 42 entries across 2 DBs` (ignored with --json)
+        #[arg(long, conflicts_with_all = ["confirm_each", "interactive"])]
+        summary_only: bool,
+        /// Print every column of the deleted rows before the result (JSON always has them)
+        #[arg(long, conflicts_with = "summary_only")]
+        show_deleted: bool,
+        /// Reset every service in a family instead of one service
+        #[arg(long, value_enum, value_name = "FAMILY", requires = "yes",
+              conflicts_with_all = ["service", "client_path", "client_file", "keep", "confirm_each", "interactive"])]
+        family: Option<Family>,
+    },
+    /// Find rows sharing the access table's primary key (report only unless --fix-duplicates)
+    Repair {
+        /// Keep the most recently modified row of each duplicate group and delete the rest
+        #[arg(long)]
+        fix_duplicates: bool,
+    },
+    /// Check one service/client pair (exit 0 granted, 2 not granted, 3 no entry)
+    Check {
+        /// Service name (e.g. Accessibility, Camera)
+        service: String,
+        /// Client bundle ID or path
+        client_path: String,
+        /// Print a single `Service client: status` line for status bars (ignored with --json)
+        #[arg(long)]
+        oneline: bool,
+        /// Also ask macOS what it enforces, where a probe exists, and flag mismatches
+        #[arg(long, conflicts_with = "oneline")]
+        effective: bool,
+    },
+    /// Print the entry for an exact service/client pair (error if there is none)
+    Get {
+        /// Service name (e.g. Accessibility, Camera)
+        service: String,
+        /// Client bundle ID or path, matched exactly
+        client_path: String,
+    },
+    /// Show how many entries were modified per day or month
+    History {
+        /// Period to group modification times by
+        #[arg(long, value_enum, default_value = "day")]
+        bucket: Bucket,
     },
     /// List all known TCC service names
-    Services,
+    Services {
+        /// Print only the total and per-category counts instead of the list
+        #[arg(long)]
+        count: bool,
+    },
     /// Show TCC database info, macOS version, and SIP status
-    Info,
+    Info {
+        /// Also show, per service, which DB writes go to and whether it is writable
+        #[arg(long)]
+        check_permissions: bool,
+        /// Also show how many entries each service family (devices, files, ...) has
+        #[arg(long)]
+        group_services: bool,
+        /// Date the report in UTC instead of local time
+        #[arg(long)]
+        utc: bool,
+    },
+    /// Print the DB paths the current flags resolve to, without opening them
+    Paths,
+    /// Print every access row with all its columns as stored, as a JSON array (always JSON)
+    Dump,
+    /// Re-insert the rows of a `dump` file (INSERT OR REPLACE)
+    Restore {
+        /// JSON array written by `dump`
+        file: PathBuf,
+        /// Parse and check every row against the target DBs without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write the access rows to a new standalone file for archiving
+    Export {
+        /// File to create (never overwritten)
+        path: PathBuf,
+        /// Output format: a fresh single-table SQLite DB with a `source` column
+        #[arg(long, value_enum, default_value = "sqlite")]
+        format: ExportFormat,
+        /// Also print a SHA1 of the exported entries, as `list --checksum` computes it
+        #[arg(long)]
+        checksum: bool,
+        /// Replace clients and Automation targets with salted hashes and drop the csreq blobs
+        #[arg(long)]
+        redact: bool,
+    },
+    /// Exercise grant/enable/disable/revoke/reset against a throwaway DB
+    SelfTest,
+    /// Diagnose root, SIP, Full Disk Access, schema, and write access (exit 1 on failure)
+    Doctor {
+        /// First apply safe fixes: back up and checkpoint a lingering WAL
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Print a completion script for the given shell to stdout
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// Characters the table renderers draw with
+struct Glyphs {
+    /// Stands in for a client repeated from the row above
+    ditto: &'static str,
+    rule: &'static str,
+    bar: &'static str,
+    pass: &'static str,
+    fail: &'static str,
+    arrow: &'static str,
+    /// Status marks for the mono color scheme
+    granted: &'static str,
+    denied: &'static str,
+    limited: &'static str,
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    ditto: "\u{2033}",
+    rule: "─",
+    bar: "█",
+    pass: "[✓]",
+    fail: "[✗]",
+    arrow: "→",
+    granted: "✓",
+    denied: "✗",
+    limited: "~",
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    ditto: "\"",
+    rule: "-",
+    bar: "#",
+    pass: "[ok]",
+    fail: "[FAIL]",
+    arrow: "->",
+    granted: "+",
+    denied: "x",
+    limited: "~",
+};
+
+impl Glyphs {
+    fn for_mode(ascii: bool) -> &'static Glyphs {
+        if ascii {
+            &ASCII_GLYPHS
+        } else {
+            &UNICODE_GLYPHS
+        }
+    }
 }
 
-fn print_entries(entries: &[TccEntry], compact: bool) {
+fn print_entries(
+    entries: &[TccEntry],
+    view: ClientView,
+    glyphs: &Glyphs,
+    scheme: ColorScheme,
+    wide: bool,
+    footer: Option<&FooterTemplate>,
+) {
     if entries.is_empty() {
         println!("{}", "No entries found.".dimmed());
         return;
     }
 
-    let display_clients: Vec<String> = if compact {
-        entries.iter().map(|e| compact_client(&e.client)).collect()
-    } else {
-        entries.iter().map(|e| e.client.clone()).collect()
-    };
+    // Automation entries name the app they may control after the client
+    let display_clients: Vec<String> = entries
+        .iter()
+        .map(|e| match &e.indirect_object {
+            Some(target) => format!(
+                "{} {} {}",
+                display_client(&e.client, view),
+                glyphs.arrow,
+                target
+            ),
+            None => display_client(&e.client, view),
+        })
+        .collect();
 
     let hdr_svc = "SERVICE";
     let hdr_client = "CLIENT";
@@ -106,13 +902,13 @@ fn print_entries(entries: &[TccEntry], compact: bool) {
         .max(hdr_svc.len());
     let client_w = display_clients
         .iter()
-        .map(|c| c.len())
+        .map(|c| c.chars().count())
         .max()
         .unwrap_or(0)
         .max(hdr_client.len());
     let status_w = entries
         .iter()
-        .map(|e| auth_value_display(e.auth_value).len())
+        .map(|e| status_label(e.auth_value, scheme, glyphs).chars().count())
         .max()
         .unwrap_or(0)
         .max(hdr_status.len());
@@ -124,13 +920,83 @@ fn print_entries(entries: &[TccEntry], compact: bool) {
         .unwrap_or(0)
         .max(hdr_modified.len());
 
+    // --wide adds TYPE and REASON, and PID and LAST REMINDED unless no row's
+    // schema has them
+    let extended = wide
+        && entries
+            .iter()
+            .any(|e| e.pid.is_some() || e.last_reminded.is_some());
+    let type_cells: Vec<String> = entries
+        .iter()
+        .map(|e| client_type_display(e.client_type))
+        .collect();
+    let type_w = type_cells
+        .iter()
+        .map(|c| c.len())
+        .max()
+        .unwrap_or(0)
+        .max("TYPE".len());
+    let reason_cells: Vec<String> = entries
+        .iter()
+        .map(|e| auth_reason_display(e.auth_reason))
+        .collect();
+    let reason_w = reason_cells
+        .iter()
+        .map(|c| c.len())
+        .max()
+        .unwrap_or(0)
+        .max("REASON".len());
+    let pid_cells: Vec<String> = entries
+        .iter()
+        .map(|e| e.pid.map_or("-".to_string(), |p| p.to_string()))
+        .collect();
+    let pid_w = pid_cells
+        .iter()
+        .map(|c| c.len())
+        .max()
+        .unwrap_or(0)
+        .max("PID".len());
+    let reminded_w = entries
+        .iter()
+        .map(|e| e.last_reminded.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0)
+        .max("LAST REMINDED".len());
+    let tail = |modified: &str, client_type: &str, reason: &str, pid: &str, reminded: &str| {
+        if extended {
+            format!(
+                "{:<mw$}  {:<tw$}  {:<rw$}  {:<pw$}  {}",
+                modified,
+                client_type,
+                reason,
+                pid,
+                reminded,
+                mw = modified_w,
+                tw = type_w,
+                rw = reason_w,
+                pw = pid_w
+            )
+        } else if wide {
+            format!(
+                "{:<mw$}  {:<tw$}  {}",
+                modified,
+                client_type,
+                reason,
+                mw = modified_w,
+                tw = type_w
+            )
+        } else {
+            modified.to_string()
+        }
+    };
+
     println!(
         "{:<sw$}  {:<cw$}  {:<stw$}  {:<srw$}  {}",
         hdr_svc,
         hdr_client,
         hdr_status,
         hdr_source,
-        hdr_modified,
+        tail(hdr_modified, "TYPE", "REASON", "PID", "LAST REMINDED"),
         sw = svc_w,
         cw = client_w,
         stw = status_w,
@@ -138,27 +1004,34 @@ fn print_entries(entries: &[TccEntry], compact: bool) {
     );
     println!(
         "{}  {}  {}  {}  {}",
-        "─".repeat(svc_w),
-        "─".repeat(client_w),
-        "─".repeat(status_w),
-        "─".repeat(source_w),
-        "─".repeat(modified_w),
+        glyphs.rule.repeat(svc_w),
+        glyphs.rule.repeat(client_w),
+        glyphs.rule.repeat(status_w),
+        glyphs.rule.repeat(source_w),
+        tail(
+            &glyphs.rule.repeat(modified_w),
+            &glyphs.rule.repeat(type_w),
+            &glyphs.rule.repeat(reason_w),
+            &glyphs.rule.repeat(pid_w),
+            &glyphs.rule.repeat(reminded_w)
+        ),
     );
 
     let mut prev_client: Option<&str> = None;
-    for (entry, display_client) in entries.iter().zip(display_clients.iter()) {
-        let status_plain = auth_value_display(entry.auth_value);
-        let status_colored = match entry.auth_value {
-            0 => status_plain.red().to_string(),
-            2 => status_plain.green().to_string(),
-            3 => status_plain.yellow().to_string(),
-            _ => status_plain.clone(),
-        };
-        let status_pad = status_w.saturating_sub(status_plain.len());
+    for ((((entry, display_client), client_type), reason), pid) in entries
+        .iter()
+        .zip(display_clients.iter())
+        .zip(type_cells.iter())
+        .zip(reason_cells.iter())
+        .zip(pid_cells.iter())
+    {
+        let status_plain = status_label(entry.auth_value, scheme, glyphs);
+        let status_colored = colored_status(entry.auth_value, scheme, glyphs);
+        let status_pad = status_w.saturating_sub(status_plain.chars().count());
         let status_cell = format!("{}{}", status_colored, " ".repeat(status_pad));
 
         let client_cell = if prev_client == Some(display_client.as_str()) {
-            "\u{2033}".to_string()
+            glyphs.ditto.to_string()
         } else {
             display_client.clone()
         };
@@ -172,14 +1045,34 @@ fn print_entries(entries: &[TccEntry], compact: bool) {
             client_cell,
             status_cell,
             source,
-            entry.last_modified,
+            tail(
+                &entry.last_modified,
+                client_type,
+                reason,
+                pid,
+                entry.last_reminded.as_deref().unwrap_or("-")
+            ),
             sw = svc_w,
             cw = client_w,
             srw = source_w,
         );
+        if let Some(encoding) = view.csreq {
+            let csreq = entry
+                .csreq
+                .as_deref()
+                .map_or("-".to_string(), |b| encoding.encode(b));
+            println!(
+                "{:<sw$}  {}",
+                "",
+                format!("csreq: {}", csreq).dimmed(),
+                sw = svc_w
+            );
+        }
     }
 
-    println!("\n{} entries total", entries.len());
+    if let Some(footer) = footer {
+        println!("\n{}", footer.render(entries));
+    }
 }
 
 #[cfg(test)]
@@ -190,10 +1083,18 @@ mod tests {
         Cli::try_parse_from(args)
     }
 
+    /// `s` without its ANSI styling, whether or not colors are on
+    fn plain(s: impl AsRef<str>) -> String {
+        Regex::new("\x1b\\[[0-9;]*m")
+            .unwrap()
+            .replace_all(s.as_ref(), "")
+            .into_owned()
+    }
+
     #[test]
     fn parse_list_no_flags() {
         let cli = parse(&["tcc", "list"]).unwrap();
-        assert!(matches!(cli.command, Commands::List { .. }));
+        assert!(matches!(cli.command, Commands::List(_)));
         assert!(!cli.user);
         assert!(!cli.json);
     }
@@ -202,14 +1103,10 @@ mod tests {
     fn parse_list_with_client_and_service_filter() {
         let cli = parse(&["tcc", "list", "--client", "apple", "--service", "Camera"]).unwrap();
         match cli.command {
-            Commands::List {
-                client,
-                service,
-                compact,
-            } => {
-                assert_eq!(client.as_deref(), Some("apple"));
-                assert_eq!(service.as_deref(), Some("Camera"));
-                assert!(!compact);
+            Commands::List(args) => {
+                assert_eq!(args.client.as_deref(), Some("apple"));
+                assert_eq!(args.service.as_deref(), Some("Camera"));
+                assert!(!args.compact);
             }
             _ => panic!("expected List"),
         }
@@ -219,266 +1116,2923 @@ mod tests {
     fn parse_list_compact() {
         let cli = parse(&["tcc", "list", "-c"]).unwrap();
         match cli.command {
-            Commands::List { compact, .. } => assert!(compact),
+            Commands::List(args) => assert!(args.compact),
             _ => panic!("expected List"),
         }
     }
 
     #[test]
-    fn parse_services() {
-        let cli = parse(&["tcc", "services"]).unwrap();
-        assert!(matches!(cli.command, Commands::Services));
+    fn parse_list_compact_depth() {
+        let cli = parse(&["tcc", "list", "-c", "--compact-depth", "3"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert_eq!(args.compact_depth, 3),
+            _ => panic!("expected List"),
+        }
+        let cli = parse(&["tcc", "list", "-c"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert_eq!(args.compact_depth, 1),
+            _ => panic!("expected List"),
+        }
+        let err = parse(&["tcc", "list", "--compact-depth", "2"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        let err = parse(&["tcc", "list", "-c", "--compact-depth", "0"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
     }
 
     #[test]
-    fn parse_info() {
-        let cli = parse(&["tcc", "info"]).unwrap();
-        assert!(matches!(cli.command, Commands::Info));
+    fn parse_list_service_key() {
+        let cli = parse(&["tcc", "list", "--service-key", "kTCCServicePhotos"]).unwrap();
+        match cli.command {
+            Commands::List(args) => {
+                assert_eq!(args.service_key.as_deref(), Some("kTCCServicePhotos"))
+            }
+            _ => panic!("expected List"),
+        }
     }
 
     #[test]
-    fn parse_grant() {
-        let cli = parse(&["tcc", "grant", "Camera", "com.app.test"]).unwrap();
+    fn parse_list_only_comma_separated_and_repeated() {
+        let cli = parse(&[
+            "tcc",
+            "list",
+            "--only",
+            "Camera,Microphone",
+            "--only",
+            "Photos",
+        ])
+        .unwrap();
         match cli.command {
-            Commands::Grant {
-                service,
-                client_path,
-            } => {
-                assert_eq!(service, "Camera");
-                assert_eq!(client_path, "com.app.test");
-            }
-            _ => panic!("expected Grant"),
+            Commands::List(args) => assert_eq!(args.only, vec!["Camera", "Microphone", "Photos"]),
+            _ => panic!("expected List"),
         }
     }
 
     #[test]
-    fn parse_revoke() {
-        let cli = parse(&["tcc", "revoke", "Camera", "com.app.test"]).unwrap();
+    fn parse_list_tcc_internal_last_flag_wins() {
+        let cli = parse(&["tcc", "list", "--no-tcc-internal"]).unwrap();
         match cli.command {
-            Commands::Revoke {
-                service,
-                client_path,
-            } => {
-                assert_eq!(service, "Camera");
-                assert_eq!(client_path, "com.app.test");
-            }
-            _ => panic!("expected Revoke"),
+            Commands::List(args) => assert!(args.no_tcc_internal),
+            _ => panic!("expected List"),
+        }
+        let cli = parse(&["tcc", "list", "--no-tcc-internal", "--include-tcc-internal"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert!(!args.no_tcc_internal),
+            _ => panic!("expected List"),
         }
     }
 
     #[test]
-    fn parse_enable() {
-        let cli = parse(&["tcc", "enable", "Accessibility", "/usr/bin/foo"]).unwrap();
+    fn parse_list_dedupe_last_flag_wins() {
+        let cli = parse(&["tcc", "list", "--no-dedupe", "--dedupe"]).unwrap();
         match cli.command {
-            Commands::Enable {
-                service,
-                client_path,
-            } => {
-                assert_eq!(service, "Accessibility");
-                assert_eq!(client_path, "/usr/bin/foo");
-            }
-            _ => panic!("expected Enable"),
+            Commands::List(args) => assert!(args.dedupe),
+            _ => panic!("expected List"),
+        }
+        let cli = parse(&["tcc", "list", "--dedupe", "--no-dedupe"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert!(!args.dedupe),
+            _ => panic!("expected List"),
         }
     }
 
     #[test]
-    fn parse_disable() {
-        let cli = parse(&["tcc", "disable", "Microphone", "com.app.x"]).unwrap();
+    fn parse_list_only_modifiable() {
+        let cli = parse(&["tcc", "list", "--only-modifiable"]).unwrap();
         match cli.command {
-            Commands::Disable {
-                service,
-                client_path,
-            } => {
-                assert_eq!(service, "Microphone");
-                assert_eq!(client_path, "com.app.x");
+            Commands::List(args) => assert!(args.only_modifiable),
+            _ => panic!("expected List"),
+        }
+    }
+
+    #[test]
+    fn parse_list_redact() {
+        let cli = parse(&["tcc", "list", "--redact"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert!(args.redact),
+            _ => panic!("expected List"),
+        }
+    }
+
+    #[test]
+    fn redact_conflicts_with_show_csreq() {
+        // The csreq blob spells out the client the redaction hides
+        let err = parse(&["tcc", "list", "--redact", "--show-csreq"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_list_json_stream() {
+        let cli = parse(&["tcc", "list", "--json-stream", "ndjson"]).unwrap();
+        match cli.command {
+            Commands::List(args) => {
+                assert_eq!(args.json_stream, Some(JsonStream::Ndjson))
             }
-            _ => panic!("expected Disable"),
+            _ => panic!("expected List"),
         }
     }
 
     #[test]
-    fn parse_reset_with_client() {
-        let cli = parse(&["tcc", "reset", "Camera", "com.app.test"]).unwrap();
+    fn parse_list_sort_defaults_to_service_and_conflicts_with_no_sort() {
+        let cli = parse(&["tcc", "list"]).unwrap();
         match cli.command {
-            Commands::Reset {
-                service,
-                client_path,
-            } => {
-                assert_eq!(service, "Camera");
-                assert_eq!(client_path.as_deref(), Some("com.app.test"));
+            Commands::List(args) => {
+                assert_eq!(args.sort, Sort::Service);
+                assert!(!args.reverse);
             }
-            _ => panic!("expected Reset"),
+            _ => panic!("expected List"),
+        }
+        let cli = parse(&["tcc", "list", "--sort", "modified", "--reverse"]).unwrap();
+        match cli.command {
+            Commands::List(args) => {
+                assert_eq!(args.sort, Sort::Modified);
+                assert!(args.reverse);
+            }
+            _ => panic!("expected List"),
+        }
+        for args in [
+            &["tcc", "list", "--sort", "client", "--no-sort"][..],
+            &["tcc", "list", "--reverse", "--no-sort"],
+        ] {
+            assert_eq!(parse(args).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+        }
+    }
+
+    fn stream_entry(service_raw: &str, client: &str) -> TccEntry {
+        TccEntry {
+            service_raw: service_raw.to_string(),
+            service_display: service_raw.to_string(),
+            client: client.to_string(),
+            client_type: 1,
+            auth_value: 2,
+            auth_reason: 0,
+            last_modified: "N/A".to_string(),
+            last_modified_ts: 0,
+            is_system: false,
+            boot_uuid: None,
+            pid: None,
+            last_reminded: None,
+            csreq: None,
+            indirect_object: None,
         }
     }
 
     #[test]
-    fn parse_reset_without_client() {
-        let cli = parse(&["tcc", "reset", "Camera"]).unwrap();
+    fn app_centric_groups_services_per_client_and_db() {
+        let mut camera = stream_entry("kTCCServiceCamera", "com.foo.app");
+        camera.service_display = "Camera".to_string();
+        let mut mic = stream_entry("kTCCServiceMicrophone", "com.foo.app");
+        mic.service_display = "Microphone".to_string();
+        mic.auth_value = 0;
+        let mut other = stream_entry("kTCCServiceCamera", "com.bar.app");
+        other.service_display = "Camera".to_string();
+        let mut system = stream_entry("kTCCServiceAccessibility", "com.foo.app");
+        system.service_display = "Accessibility".to_string();
+        system.is_system = true;
+
+        let apps = pivot_by_app(&[mic, other, system, camera], ClientView::default());
+        assert_eq!(
+            apps,
+            vec![
+                AppPermissions {
+                    client: "com.bar.app".to_string(),
+                    source: SourceLabel::User,
+                    permissions: vec![("Camera".to_string(), vec![2])],
+                },
+                AppPermissions {
+                    client: "com.foo.app".to_string(),
+                    source: SourceLabel::User,
+                    permissions: vec![
+                        ("Camera".to_string(), vec![2]),
+                        ("Microphone".to_string(), vec![0])
+                    ],
+                },
+                AppPermissions {
+                    client: "com.foo.app".to_string(),
+                    source: SourceLabel::System,
+                    permissions: vec![("Accessibility".to_string(), vec![2])],
+                },
+            ]
+        );
+        assert_eq!(
+            json_app_centric(&apps[1..2]),
+            "{\"count\":1,\"apps\":[{\"client\":\"com.foo.app\",\"source\":\"user\",\"permissions\":{\"Camera\":\"granted\",\"Microphone\":\"denied\"}}]}"
+        );
+    }
+
+    #[test]
+    fn app_centric_keeps_every_value_and_the_full_client() {
+        let mut finder = stream_entry("kTCCServiceAppleEvents", "/Applications/Tool.app");
+        finder.service_display = "Automation".to_string();
+        finder.indirect_object = Some("com.apple.finder".to_string());
+        let mut mail = stream_entry("kTCCServiceAppleEvents", "/Applications/Tool.app");
+        mail.service_display = "Automation".to_string();
+        mail.indirect_object = Some("com.apple.mail".to_string());
+        mail.auth_value = 0;
+        let mut other = stream_entry("kTCCServiceAppleEvents", "/opt/Tool.app");
+        other.service_display = "Automation".to_string();
+
+        let view = ClientView {
+            compact: Some(1),
+            ..ClientView::default()
+        };
+        let apps = pivot_by_app(&[finder, mail, other], view);
+        // Both clients compact to the same name but stay apart
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0].client, apps[1].client);
+        assert_eq!(
+            apps[0].permissions,
+            vec![("Automation".to_string(), vec![0, 2])]
+        );
+        assert!(
+            json_app_centric(&apps[..1]).contains("\"Automation\":[\"denied\",\"granted\"]"),
+            "{}",
+            json_app_centric(&apps[..1])
+        );
+    }
+
+    fn render_stream(entries: &[TccEntry], framing: JsonStream) -> String {
+        let mut out = Vec::new();
+        write_json_stream(
+            &mut out,
+            entries,
+            ClientView::default(),
+            framing,
+            JsonOutput::default(),
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json_stream_array_empty_is_valid() {
+        assert_eq!(render_stream(&[], JsonStream::Array), "[]\n");
+    }
+
+    #[test]
+    fn json_stream_ndjson_empty_is_empty() {
+        assert_eq!(render_stream(&[], JsonStream::Ndjson), "");
+    }
+
+    #[test]
+    fn json_stream_array_multiple_entries() {
+        let entries = [stream_entry("a", "x"), stream_entry("b", "y")];
+        let out = render_stream(&entries, JsonStream::Array);
+        assert!(out.starts_with("[{\"service\":\"a\""));
+        assert!(out.contains("},{\"service\":\"b\""));
+        assert!(out.ends_with("}]\n"));
+        assert_eq!(out.lines().count(), 1);
+    }
+
+    #[test]
+    fn json_stream_ndjson_multiple_entries() {
+        let entries = [stream_entry("a", "x"), stream_entry("b", "y")];
+        let out = render_stream(&entries, JsonStream::Ndjson);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with('{') && lines[0].ends_with('}'));
+        assert!(lines[1].contains("\"client\":\"y\""));
+    }
+
+    #[test]
+    fn json_stream_writes_each_entry_before_reading_the_next() {
+        struct Shared(std::rc::Rc<RefCell<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let written = std::rc::Rc::new(RefCell::new(Vec::<u8>::new()));
+        let mut seen = Vec::new();
+        let entries = ["a", "b", "c"].into_iter().map(|client| {
+            seen.push(RefCell::borrow(&written).len());
+            stream_entry("kTCCServiceCamera", client)
+        });
+        let mut out = Shared(written.clone());
+        write_json_stream(
+            &mut out,
+            entries,
+            ClientView::default(),
+            JsonStream::Ndjson,
+            JsonOutput::default(),
+        )
+        .unwrap();
+        assert_eq!(seen.len(), 3);
+        assert!(seen.windows(2).all(|w| w[0] < w[1]), "{:?}", seen);
+    }
+
+    #[test]
+    fn redact_covers_automation_targets() {
+        let mut entries = vec![stream_entry("kTCCServiceAppleEvents", "com.secret.app")];
+        entries[0].indirect_object = Some("com.secret.target".to_string());
+        let redactor = Redactor::with_salt("salt");
+        redactor.redact_entries(&mut entries);
+
+        let json = json_entry(&entries[0], ClientView::default());
+        assert!(!json.contains("com.secret"), "got: {}", json);
+        assert!(json.contains(&format!(
+            "\"indirect_object\":\"{}\"",
+            redactor.redact("com.secret.target")
+        )));
+    }
+
+    #[test]
+    fn truncate_client_caps_client_and_keeps_client_full() {
+        let entry = stream_entry("kTCCServiceCamera", "com.example.verylongname");
+        let view = ClientView {
+            truncate: Some(8),
+            ..ClientView::default()
+        };
+        let json = json_entry(&entry, view);
+        assert!(json.contains("\"client\":\"com.exa…\""), "got: {}", json);
+        assert!(json.contains("\"client_full\":\"com.example.verylongname\""));
+
+        // Short clients are left alone, and the field is absent without the flag
+        assert_eq!(display_client("com.a", view), "com.a");
+        assert!(!json_entry(&entry, ClientView::default()).contains("client_full"));
+    }
+
+    #[test]
+    fn parse_check_oneline() {
+        let cli = parse(&["tcc", "check", "Camera", "com.app.x", "--oneline"]).unwrap();
         match cli.command {
-            Commands::Reset {
+            Commands::Check {
                 service,
                 client_path,
+                oneline,
+                ..
             } => {
                 assert_eq!(service, "Camera");
-                assert!(client_path.is_none());
+                assert_eq!(client_path, "com.app.x");
+                assert!(oneline);
             }
-            _ => panic!("expected Reset"),
+            _ => panic!("expected Check"),
         }
     }
 
     #[test]
-    fn parse_user_flag_global() {
-        let cli = parse(&["tcc", "--user", "list"]).unwrap();
-        assert!(cli.user);
+    fn check_exit_codes_by_status() {
+        let mut entry = stream_entry("kTCCServiceCamera", "x");
+        assert_eq!(check_exit_code(Some(&entry)), CHECK_EXIT_GRANTED);
+        entry.auth_value = 0;
+        assert_eq!(check_exit_code(Some(&entry)), CHECK_EXIT_NOT_GRANTED);
+        assert_eq!(check_exit_code(None), CHECK_EXIT_NO_ENTRY);
     }
 
     #[test]
-    fn parse_user_flag_after_subcommand() {
-        let cli = parse(&["tcc", "list", "--user"]).unwrap();
-        assert!(cli.user);
+    fn check_effective_flags_mismatches() {
+        let entry = stream_entry("kTCCServiceAccessibility", "/usr/local/bin/tcc");
+        let data = json_check_data(
+            "kTCCServiceAccessibility",
+            "/usr/local/bin/tcc",
+            Some(&entry),
+            Some(EffectiveAuth::Probed(false)),
+        );
+        assert!(
+            data.ends_with(
+                ",\"effective\":{\"source\":\"probe\",\"scope\":\"this process\",\"granted\":false,\"mismatch\":true}}"
+            ),
+            "{}",
+            data
+        );
+        // No row means not granted, which agrees with a denied probe
+        assert!(!effective_mismatch(None, false));
+        assert!(effective_mismatch(None, true));
+
+        let data = json_check_data(
+            "kTCCServiceCamera",
+            "com.foo",
+            None,
+            Some(EffectiveAuth::DbOnly("no runtime probe for this service")),
+        );
+        assert!(data.contains("\"effective\":{\"source\":\"db-only\",\"reason\":"));
+        assert!(!json_check_data("kTCCServiceCamera", "com.foo", None, None).contains("effective"));
+
+        assert_eq!(
+            plain(effective_line(Some(&entry), EffectiveAuth::Probed(true))),
+            "Effective for this process: granted (matches DB)"
+        );
     }
 
     #[test]
-    fn parse_json_flag_global() {
-        let cli = parse(&["tcc", "--json", "services"]).unwrap();
-        assert!(cli.json);
+    fn check_oneline_format() {
+        let entry = stream_entry("kTCCServiceCamera", "com.foo");
+        assert_eq!(
+            plain(check_oneline(
+                "Camera",
+                "com.foo",
+                Some(&entry),
+                ColorScheme::Default,
+                &UNICODE_GLYPHS
+            )),
+            "Camera com.foo: granted"
+        );
+        assert_eq!(
+            plain(check_oneline(
+                "Camera",
+                "com.foo",
+                None,
+                ColorScheme::Default,
+                &UNICODE_GLYPHS
+            )),
+            "Camera com.foo: not set"
+        );
     }
 
     #[test]
-    fn parse_json_flag_after_subcommand() {
-        let cli = parse(&["tcc", "services", "--json"]).unwrap();
-        assert!(cli.json);
+    fn color_override_honors_flags_then_no_color_env() {
+        let set = Some(std::ffi::OsStr::new("1"));
+        let empty = Some(std::ffi::OsStr::new(""));
+        assert_eq!(color_override(ColorWhen::Auto, false, None, false), None);
+        assert_eq!(color_override(ColorWhen::Auto, false, empty, false), None);
+        assert_eq!(
+            color_override(ColorWhen::Auto, false, set, false),
+            Some(false)
+        );
+        assert_eq!(
+            color_override(ColorWhen::Auto, true, None, false),
+            Some(false)
+        );
+        assert_eq!(
+            color_override(ColorWhen::Never, false, None, false),
+            Some(false)
+        );
+        // An explicit request wins over NO_COLOR, but never over JSON/CSV
+        assert_eq!(
+            color_override(ColorWhen::Always, false, set, false),
+            Some(true)
+        );
+        assert_eq!(
+            color_override(ColorWhen::Always, false, None, true),
+            Some(false)
+        );
     }
 
     #[test]
-    fn parse_json_short_flag() {
-        let cli = parse(&["tcc", "-j", "info"]).unwrap();
-        assert!(cli.json);
+    fn parse_color_when_and_no_color() {
+        let cli = parse(&["tcc", "list"]).unwrap();
+        assert_eq!(cli.color, ColorWhen::Auto);
+        assert!(!cli.no_color);
+        let cli = parse(&["tcc", "list", "--color=always"]).unwrap();
+        assert_eq!(cli.color, ColorWhen::Always);
+        let cli = parse(&["tcc", "--no-color", "list"]).unwrap();
+        assert!(cli.no_color);
+        assert!(parse(&["tcc", "list", "--no-color", "--color", "always"]).is_err());
     }
 
     #[test]
-    fn parse_no_subcommand_is_error() {
-        let err = parse(&["tcc"]).unwrap_err();
+    fn colorblind_scheme_avoids_red_and_green() {
+        for auth_value in [0, 2] {
+            let default = ColorScheme::Default.status_color(auth_value).unwrap();
+            let colorblind = ColorScheme::Colorblind.status_color(auth_value).unwrap();
+            assert_ne!(default.to_fg_str(), colorblind.to_fg_str());
+        }
+        assert_ne!(
+            ColorScheme::Colorblind.status_color(0),
+            ColorScheme::Colorblind.status_color(2)
+        );
+        assert_eq!(ColorScheme::Mono.status_color(2), None);
+    }
+
+    #[test]
+    fn mono_scheme_marks_statuses() {
         assert_eq!(
-            err.kind(),
-            ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+            plain(colored_status(2, ColorScheme::Mono, &UNICODE_GLYPHS)),
+            "✓ granted"
+        );
+        assert_eq!(
+            plain(colored_status(0, ColorScheme::Mono, &ASCII_GLYPHS)),
+            "x denied"
+        );
+        assert_eq!(
+            plain(colored_status(0, ColorScheme::Colorblind, &UNICODE_GLYPHS)),
+            "denied"
         );
     }
 
     #[test]
-    fn parse_unknown_subcommand_is_error() {
-        let err = parse(&["tcc", "foobar"]).unwrap_err();
-        assert_eq!(err.kind(), ErrorKind::InvalidSubcommand);
+    fn footer_format_counts_statuses() {
+        let mut denied = stream_entry("kTCCServiceCamera", "com.b");
+        denied.auth_value = 0;
+        denied.is_system = true;
+        let entries = [stream_entry("kTCCServiceCamera", "com.a"), denied];
+        assert_eq!(
+            FooterTemplate::default().render(&entries),
+            "2 entries total"
+        );
+        let footer = parse_footer("{granted}/{count} granted, {system} system, {{x}}").unwrap();
+        assert_eq!(footer.render(&entries), "1/2 granted, 1 system, {x}");
+        let err = parse_footer("{client}").unwrap_err();
+        assert!(err.contains("count, granted"), "{}", err);
     }
 
     #[test]
-    fn parse_grant_missing_args_is_error() {
-        let err = parse(&["tcc", "grant"]).unwrap_err();
-        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    fn template_substitutes_fields() {
+        let template = parse_template("{service} {client} {status}").unwrap();
+        let entry = stream_entry("Camera", "/Applications/Foo.app/Contents/MacOS/Foo");
+        assert_eq!(
+            template.render(&entry, ClientView::default()),
+            "Camera /Applications/Foo.app/Contents/MacOS/Foo granted"
+        );
+        assert_eq!(
+            template.render(
+                &entry,
+                ClientView {
+                    compact: Some(1),
+                    ..ClientView::default()
+                }
+            ),
+            "Camera Foo granted"
+        );
+
+        let template =
+            parse_template("{service_raw}\t{auth_value}\t{source}\t{last_modified}").unwrap();
+        assert_eq!(
+            template.render(&entry, ClientView::default()),
+            "Camera\t2\tuser\tN/A"
+        );
     }
 
     #[test]
-    fn cli_has_version() {
-        let cmd = Cli::command();
-        assert!(cmd.get_version().is_some());
+    fn template_literal_braces_and_plain_text() {
+        let entry = stream_entry("Camera", "com.foo");
+        let template = parse_template("{{{client}}} ok").unwrap();
+        assert_eq!(
+            template.render(&entry, ClientView::default()),
+            "{com.foo} ok"
+        );
+        assert_eq!(parse_template("").unwrap(), OutputTemplate(vec![]));
     }
-}
 
-fn error_kind(error: &TccError) -> &'static str {
-    match error {
-        TccError::DbOpen { .. } => "DbOpen",
-        TccError::NotFound { .. } => "NotFound",
-        TccError::NeedsRoot { .. } => "NeedsRoot",
-        TccError::UnknownService(_) => "UnknownService",
-        TccError::AmbiguousService { .. } => "AmbiguousService",
-        TccError::QueryFailed(_) => "QueryFailed",
-        TccError::SchemaInvalid(_) => "SchemaInvalid",
-        TccError::HomeDirNotFound => "HomeDirNotFound",
-        TccError::WriteFailed(_) => "WriteFailed",
+    #[test]
+    fn template_rejects_unknown_and_malformed_fields() {
+        let err = parse_template("{service} {bundle}").unwrap_err();
+        assert!(err.contains("unknown template field '{bundle}'"), "{}", err);
+        assert!(err.contains("service_raw"), "{}", err);
+        assert!(parse_template("{client").unwrap_err().contains("unclosed"));
+        assert!(parse_template("client}").unwrap_err().contains("unmatched"));
     }
-}
 
-fn json_escape(input: &str) -> String {
-    let mut escaped = String::with_capacity(input.len());
-    for c in input.chars() {
-        match c {
-            '\\' => escaped.push_str("\\\\"),
-            '"' => escaped.push_str("\\\""),
-            '\n' => escaped.push_str("\\n"),
-            '\r' => escaped.push_str("\\r"),
-            '\t' => escaped.push_str("\\t"),
-            '\u{08}' => escaped.push_str("\\b"),
-            '\u{0C}' => escaped.push_str("\\f"),
-            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
-            c => escaped.push(c),
+    #[test]
+    fn parse_list_regex_validates_at_parse_time() {
+        let cli = parse(&["tcc", "list", "--client-regex", r"^com\.(apple|google)\."]).unwrap();
+        match cli.command {
+            Commands::List(args) => {
+                let re = args.client_regex.unwrap();
+                assert!(re.is_match("com.google.Chrome"));
+                assert!(!re.is_match("org.google.x"));
+            }
+            _ => panic!("expected List"),
         }
+
+        let err = parse(&["tcc", "list", "--service-regex", "(Camera"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        assert!(err.to_string().contains("unclosed group"), "{}", err);
     }
-    escaped
-}
 
-fn json_string(value: &str) -> String {
-    format!("\"{}\"", json_escape(value))
-}
+    #[test]
+    fn parse_list_template_validates_at_parse_time() {
+        let cli = parse(&["tcc", "list", "--template", "{client}"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert!(args.template.is_some()),
+            _ => panic!("expected List"),
+        }
+        let err = parse(&["tcc", "list", "--template", "{nope}"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+    }
 
-fn emit_json(raw_json: String) {
-    println!("{}", raw_json);
-}
+    #[test]
+    fn parse_history_bucket() {
+        let cli = parse(&["tcc", "history"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::History {
+                bucket: Bucket::Day
+            }
+        ));
+        let cli = parse(&["tcc", "history", "--bucket", "month"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::History {
+                bucket: Bucket::Month
+            }
+        ));
+    }
 
-fn emit_json_success(command: &'static str, data_json: String) {
-    emit_json(format!(
-        "{{\"ok\":true,\"command\":{},\"data\":{},\"error\":null}}",
-        json_string(command),
-        data_json
-    ));
-}
+    #[test]
+    fn csv_field_quotes_only_what_rfc_4180_requires() {
+        assert_eq!(csv_field("com.foo.app"), "com.foo.app");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines\r"), "\"two\nlines\r\"");
+        assert_eq!(csv_row(&["x", "", "y,z"]), "x,,\"y,z\"\r\n");
 
-fn emit_json_error(command: &'static str, kind: &'static str, message: String) {
-    emit_json(format!(
-        "{{\"ok\":false,\"command\":{},\"data\":null,\"error\":{{\"kind\":{},\"message\":{}}}}}",
-        json_string(command),
-        json_string(kind),
-        json_string(&message),
-    ));
-}
+        let summary = csv_summary("grant", false, "No, entry", 1);
+        assert_eq!(
+            summary,
+            "command,ok,message,warnings\r\ngrant,false,\"No, entry\",1\r\n"
+        );
+    }
 
-fn json_message_data(message: &str) -> String {
-    format!("{{\"message\":{}}}", json_string(message))
-}
+    #[test]
+    fn csv_and_json_are_mutually_exclusive() {
+        let err = parse(&["tcc", "--csv", "--json", "list"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        let err = check_csv_list(true, &[("--template", false), ("--checksum", true)]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        assert!(check_csv_list(false, &[("--checksum", true)]).is_ok());
 
-fn json_list_data(entries: &[TccEntry], compact: bool) -> String {
-    let mut entry_json = Vec::with_capacity(entries.len());
-    for entry in entries {
-        let client = if compact {
-            compact_client(&entry.client)
-        } else {
-            entry.client.clone()
+        // Commands with no summary message have no CSV form
+        for args in [
+            &["tcc", "--csv", "check", "Camera", "x"][..],
+            &["tcc", "--csv", "info"],
+        ] {
+            let cli = parse(args).unwrap();
+            let err = check_csv_command(true, false, &cli.command).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        }
+        let cli = parse(&["tcc", "--csv", "grant", "Camera", "x"]).unwrap();
+        assert!(check_csv_command(true, false, &cli.command).is_ok());
+        assert!(check_csv_command(true, true, &cli.command).is_err());
+    }
+
+    #[test]
+    fn csreq_is_base64_in_json_and_null_when_absent() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(bytes), encoded);
+        }
+        assert_eq!(CsreqEncoding::Hex.encode(&[0xfa, 0xde]), "fade");
+        assert_eq!(CsreqEncoding::Base64.encode(&[0xfa, 0xde]), "+t4=");
+
+        let view = ClientView {
+            csreq: Some(CsreqEncoding::Hex),
+            ..ClientView::default()
         };
-        let source = if entry.is_system { "system" } else { "user" };
-        entry_json.push(format!(
-            "{{\"service\":{},\"service_raw\":{},\"client\":{},\"status\":{},\"auth_value\":{},\"source\":{},\"last_modified\":{}}}",
-            json_string(&entry.service_display),
-            json_string(&entry.service_raw),
-            json_string(&client),
-            json_string(&auth_value_display(entry.auth_value)),
-            entry.auth_value,
-            json_string(source),
-            json_string(&entry.last_modified),
-        ));
+        let mut entry = stream_entry("kTCCServiceCamera", "com.foo");
+        assert!(!json_entry(&entry, ClientView::default()).contains("csreq"));
+        assert!(json_entry(&entry, view).ends_with(",\"csreq\":null}"));
+        entry.csreq = Some(vec![0xfa, 0xde, 0x0c, 0x00]);
+        assert!(json_entry(&entry, view).ends_with(",\"csreq\":\"+t4MAA==\"}"));
+        let tabular: serde_json::Value = serde_json::from_str(&format!(
+            "{{{}}}",
+            json_tabular(std::slice::from_ref(&entry), view)
+        ))
+        .unwrap();
+        let columns = tabular["columns"].as_array().unwrap();
+        let csreq = columns.iter().position(|c| c == "csreq").unwrap();
+        assert_eq!(csreq, columns.len() - 1);
+        assert_eq!(tabular["rows"][0][csreq], "+t4MAA==");
     }
-    format!(
-        "{{\"count\":{},\"entries\":[{}]}}",
-        entries.len(),
-        entry_json.join(",")
-    )
-}
 
-fn json_services_data() -> String {
-    let mut pairs: Vec<_> = SERVICE_MAP.iter().collect();
-    pairs.sort_by_key(|(_, desc)| *desc);
-    let services = pairs
-        .iter()
-        .map(|(key, desc)| {
-            format!(
+    #[test]
+    fn json_string_survives_a_real_parser() {
+        let nasty = "quote\" back\\ nl\n cr\r tab\t bell\u{07} del\u{7f} é ✓";
+        let json = json_string(nasty);
+        assert_eq!(json_error(&json), None);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_str(), Some(nasty));
+
+        let entry = stream_entry("kTCCServiceCamera", nasty);
+        assert_eq!(json_error(&json_entry(&entry, ClientView::default())), None);
+        assert!(json_error("{\"a\":1,}").is_some());
+    }
+
+    #[test]
+    fn json_list_includes_diagnostics() {
+        let failures = vec![SourceFailure {
+            source: tcc::SourceLabel::System,
+            path: PathBuf::from("/bogus.db"),
+            message: "file is not a database".to_string(),
+        }];
+        let data = json_list_data(
+            &[],
+            ClientView::default(),
+            &failures,
+            1_800_000_000,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(
+            data,
+            "{\"count\":0,\"generated_at\":1800000000,\"entries\":[],\"diagnostics\":[{\"source\":\"system\",\"path\":\"/bogus.db\",\"message\":\"file is not a database\"}]}"
+        );
+    }
+
+    #[test]
+    fn json_list_measure_reports_rows_before_filtering() {
+        let counts = vec![
+            SourceCount {
+                source: tcc::SourceLabel::User,
+                path: PathBuf::from("/u.db"),
+                rows: 480,
+            },
+            SourceCount {
+                source: tcc::SourceLabel::System,
+                path: PathBuf::from("/s.db"),
+                rows: 20,
+            },
+        ];
+        let data = json_list_data(
+            &[],
+            ClientView::default(),
+            &[],
+            1_800_000_000,
+            Some(&counts),
+            None,
+            false,
+        );
+        assert!(
+            data.ends_with(
+                ",\"measure\":{\"read\":500,\"sources\":[{\"source\":\"user\",\"path\":\"/u.db\",\"rows\":480},{\"source\":\"system\",\"path\":\"/s.db\",\"rows\":20}]}}"
+            ),
+            "{}",
+            data
+        );
+        assert_eq!(
+            measure_summary(&counts, 3),
+            "Read 500 rows (user 480, system 20); 3 after filters"
+        );
+    }
+
+    #[test]
+    fn dedupe_note_is_carried_as_a_json_warning() {
+        take_held_warnings();
+        hold_warning(dedupe_note(2));
+        assert_eq!(
+            json_held_warnings(),
+            "[\"2 duplicate rows collapsed (system DB preferred)\"]"
+        );
+    }
+
+    #[test]
+    fn json_entry_names_the_automation_target_only_when_there_is_one() {
+        let plain = stream_entry("kTCCServiceCamera", "com.foo");
+        assert!(!json_entry(&plain, ClientView::default()).contains("indirect_object"));
+
+        let mut automation = stream_entry("kTCCServiceAppleEvents", "com.foo");
+        automation.indirect_object = Some("com.apple.finder".to_string());
+        assert!(
+            json_entry(&automation, ClientView::default())
+                .contains("\"indirect_object\":\"com.apple.finder\"")
+        );
+        let tabular = json_tabular(&[plain, automation], ClientView::default());
+        assert!(tabular.contains("\"indirect_object\"]"));
+        assert!(tabular.contains(",null],["));
+        assert!(tabular.ends_with(",\"com.apple.finder\"]]"));
+    }
+
+    #[test]
+    fn json_list_newer_than_adds_both_timestamps() {
+        let mut entry = stream_entry("kTCCServiceCamera", "com.foo");
+        entry.last_modified_ts = 1_800_000_000;
+        let data = json_list_data(
+            &[entry],
+            ClientView::default(),
+            &[],
+            1_800_000_000,
+            None,
+            Some(&[None]),
+            false,
+        );
+        assert_eq!(json_error(&data), None);
+        assert!(data.contains(
+            "\"last_modified_ts\":1800000000,\"client_type\":1,\"auth_reason\":0,\"other_last_modified\":null,\"other_last_modified_ts\":null}"
+        ));
+        assert_eq!(data.matches("\"last_modified_ts\"").count(), 1);
+    }
+
+    #[test]
+    fn json_list_tabular_rows_match_columns() {
+        let mut reminded = stream_entry(
+            "kTCCServiceCamera",
+            "/Applications/Foo.app/Contents/MacOS/Foo",
+        );
+        reminded.pid = Some(42);
+        let entries = [reminded, stream_entry("kTCCServiceMicrophone", "com.bar")];
+        let view = ClientView {
+            truncate: Some(8),
+            ..ClientView::default()
+        };
+        let data = json_list_data(&entries, view, &[], 1_800_000_000, None, None, true);
+        let parsed: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert!(parsed.get("entries").is_none());
+        let columns = parsed["columns"].as_array().unwrap();
+        assert_eq!(columns[2], "client");
+        assert!(columns.contains(&"client_full".into()));
+        assert!(columns.contains(&"pid".into()));
+        assert!(!columns.contains(&"last_reminded".into()));
+        let rows = parsed["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            assert_eq!(row.as_array().unwrap().len(), columns.len());
+        }
+        let pid = columns.iter().position(|c| c == "pid").unwrap();
+        assert_eq!(rows[0][pid], 42);
+        assert!(rows[1][pid].is_null());
+    }
+
+    #[test]
+    fn json_deleted_rows_keep_every_column() {
+        use rusqlite::types::Value;
+        let row = RawRow {
+            source: tcc::SourceLabel::User,
+            columns: vec![
+                ("client".to_string(), Value::Text("com.foo".to_string())),
+                ("auth_value".to_string(), Value::Integer(2)),
+                ("csreq".to_string(), Value::Blob(vec![0xFA, 0xDE])),
+                ("pid".to_string(), Value::Null),
+            ],
+        };
+        let data = json_with_deleted(
+            json_message_data("Reset", "kTCCServiceCamera"),
+            "deleted",
+            std::slice::from_ref(&row),
+        );
+        assert_eq!(json_error(&data), None);
+        assert!(data.ends_with(
+            ",\"deleted\":[{\"source\":\"user\",\"row\":{\"client\":\"com.foo\",\"auth_value\":2,\"csreq\":\"+t4=\",\"pid\":null}}]}"
+        ));
+        assert_eq!(
+            deleted_row_line(&row),
+            "user: client=com.foo auth_value=2 csreq=X'fade' pid=NULL"
+        );
+    }
+
+    fn empty_info() -> InfoReport {
+        InfoReport {
+            lines: Vec::new(),
+            journals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn json_info_journals_shape() {
+        let report = InfoReport {
+            lines: vec!["User DB: /u.db".to_string()],
+            journals: vec![
+                tcc::DbJournal {
+                    path: PathBuf::from("/u.db"),
+                    is_system: false,
+                    journal_mode: Some("wal".to_string()),
+                    wal_bytes: Some(4096),
+                    shm_bytes: None,
+                },
+                tcc::DbJournal {
+                    path: PathBuf::from("/s.db"),
+                    is_system: true,
+                    journal_mode: None,
+                    wal_bytes: None,
+                    shm_bytes: None,
+                },
+            ],
+        };
+        assert_eq!(
+            json_info_data("2026-10-17T09:30:00Z", &report, None, None),
+            "{\"generated_at\":\"2026-10-17T09:30:00Z\",\"lines\":[\"User DB: /u.db\"],\"journals\":[{\"db\":\"user\",\"path\":\"/u.db\",\"journal_mode\":\"wal\",\"wal_bytes\":4096,\"shm_bytes\":null},{\"db\":\"system\",\"path\":\"/s.db\",\"journal_mode\":null,\"wal_bytes\":null,\"shm_bytes\":null}]}"
+        );
+    }
+
+    #[test]
+    fn json_info_permissions_shape() {
+        let access = vec![ServiceAccess {
+            service: Service::try_from("Accessibility").unwrap(),
+            path: PathBuf::from("/s.db"),
+            is_system: true,
+            blocked_by: Some("needs sudo"),
+        }];
+        let at = "2026-10-17T09:30:00Z";
+        assert_eq!(
+            json_info_data(at, &empty_info(), None, None),
+            "{\"generated_at\":\"2026-10-17T09:30:00Z\",\"lines\":[],\"journals\":[]}"
+        );
+        assert_eq!(
+            json_info_data(at, &empty_info(), Some(&access), None),
+            "{\"generated_at\":\"2026-10-17T09:30:00Z\",\"lines\":[],\"journals\":[],\"permissions\":[{\"service\":\"Accessibility\",\"service_raw\":\"kTCCServiceAccessibility\",\"db\":\"system\",\"path\":\"/s.db\",\"writable\":false,\"blocked_by\":\"needs sudo\"}]}"
+        );
+    }
+
+    #[test]
+    fn json_info_families_shape() {
+        let families = vec![
+            (ServiceFamily::Devices, 2),
+            (ServiceFamily::PersonalData, 0),
+        ];
+        assert_eq!(
+            json_info_data("2026-10-17T09:30:00Z", &empty_info(), None, Some(&families)),
+            "{\"generated_at\":\"2026-10-17T09:30:00Z\",\"lines\":[],\"journals\":[],\"families\":{\"devices\":2,\"personal_data\":0}}"
+        );
+    }
+
+    #[test]
+    fn info_generated_at_is_rfc3339() {
+        for utc in [false, true] {
+            let data = json_info_data(&rfc3339_now(utc), &empty_info(), None, None);
+            let parsed: serde_json::Value = serde_json::from_str(&data).unwrap();
+            let at = parsed["generated_at"].as_str().unwrap();
+            let ts = chrono::DateTime::parse_from_rfc3339(at).unwrap();
+            assert!((chrono::Utc::now().timestamp() - ts.timestamp()).abs() < 60);
+            assert_eq!(at.ends_with('Z'), utc, "{}", at);
+        }
+    }
+
+    #[test]
+    fn json_history_shape() {
+        let periods = vec![("2026-02-01".to_string(), 3)];
+        assert_eq!(
+            json_history_data(Bucket::Day, &periods, 1),
+            "{\"bucket\":\"day\",\"periods\":[{\"period\":\"2026-02-01\",\"count\":3}],\"unknown\":1}"
+        );
+    }
+
+    #[test]
+    fn parse_services() {
+        let cli = parse(&["tcc", "services"]).unwrap();
+        assert!(matches!(cli.command, Commands::Services { count: false }));
+    }
+
+    #[test]
+    fn parse_services_count() {
+        let cli = parse(&["tcc", "services", "--count"]).unwrap();
+        assert!(matches!(cli.command, Commands::Services { count: true }));
+    }
+
+    #[test]
+    fn services_count_matches_map() {
+        let (total, system, user) = service_counts();
+        assert_eq!(total, SERVICE_MAP.len());
+        assert_eq!(system + user, total);
+        assert!(system > 0 && user > 0);
+        assert_eq!(
+            json_services_count_data((3, 1, 2)),
+            "{\"total\":3,\"by_category\":{\"system\":1,\"user\":2}}"
+        );
+    }
+
+    #[test]
+    fn parse_info() {
+        let cli = parse(&["tcc", "info"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Info {
+                check_permissions: false,
+                group_services: false,
+                utc: false
+            }
+        ));
+        let cli = parse(&["tcc", "info", "--check-permissions"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Info {
+                check_permissions: true,
+                group_services: false,
+                utc: false
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_list_auth_ignores_case() {
+        for args in [
+            ["tcc", "list", "--auth", "DENIED"],
+            ["tcc", "list", "--status", "denied"],
+        ] {
+            let cli = parse(&args).unwrap();
+            match cli.command {
+                Commands::List(args) => assert_eq!(args.auth, Some(Auth::Denied)),
+                _ => panic!("expected List"),
+            }
+        }
+        let err = parse(&["tcc", "list", "--auth", "allowed"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn list_source_defaults_to_both_and_rejects_system_with_user() {
+        let cli = parse(&["tcc", "list"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert_eq!(args.source, Source::Both),
+            _ => panic!("expected List"),
+        }
+        let cli = parse(&["tcc", "list", "--filter-source", "system"]).unwrap();
+        match cli.command {
+            Commands::List(args) => assert_eq!(args.source, Source::System),
+            _ => panic!("expected List"),
+        }
+
+        let err = check_source_target(Source::System, DbTarget::User).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        assert!(check_source_target(Source::System, DbTarget::Default).is_ok());
+        assert!(check_source_target(Source::User, DbTarget::User).is_ok());
+        assert!(check_source_target(Source::Both, DbTarget::User).is_ok());
+        let err = check_source_target(Source::System, DbTarget::File).unwrap_err();
+        assert!(err.to_string().contains("'--db'"));
+        assert!(check_source_target(Source::User, DbTarget::File).is_ok());
+    }
+
+    #[test]
+    fn time_bounds_accept_seconds_iso_dates_and_ages() {
+        use chrono::{Local, NaiveDate, TimeZone};
+
+        assert_eq!(parse_time_bound("1770000000"), Ok(1_770_000_000));
+        assert_eq!(parse_time_bound("2026-03-01T09:30:00Z"), Ok(1_772_357_400));
+        assert_eq!(
+            parse_time_bound("2026-03-01T10:30:00+01:00"),
+            Ok(1_772_357_400)
+        );
+        let midnight = NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let local = Local.from_local_datetime(&midnight).earliest().unwrap();
+        assert_eq!(parse_time_bound("2026-03-01"), Ok(local.timestamp()));
+        assert_eq!(
+            parse_time_bound("2026-03-01T09:30:00"),
+            parse_time_bound("2026-03-01 09:30:00")
+        );
+
+        let week_ago = chrono::Utc::now().timestamp() - 7 * 86_400;
+        assert!((parse_time_bound("7d").unwrap() - week_ago).abs() < 60);
+        assert!((parse_time_bound("1w").unwrap() - week_ago).abs() < 60);
+
+        for bad in ["7y", "yesterday", "2026-13-01", "d"] {
+            assert!(parse_time_bound(bad).is_err(), "{} should be rejected", bad);
+        }
+        let err = parse(&["tcc", "list", "--since", "soon"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+
+        assert!(check_time_window(Some(1), Some(2)).is_ok());
+        assert!(check_time_window(Some(2), None).is_ok());
+        let err = check_time_window(Some(2), Some(1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn json_toggle_reports_both_statuses() {
+        assert_eq!(
+            json_toggle_data("Toggled", "kTCCServiceCamera", 2, 0),
+            "{\"message\":\"Toggled\",\"service_raw\":\"kTCCServiceCamera\",\"previous_status\":\"granted\",\"previous_auth_value\":2,\"status\":\"denied\",\"auth_value\":0}"
+        );
+    }
+
+    #[test]
+    fn parse_set_caps_the_value_unless_forced() {
+        let cli = parse(&["tcc", "set", "Photos", "com.app.test", "-1"]).unwrap();
+        match cli.command {
+            Commands::Set { value, force, .. } => {
+                assert_eq!(value, -1);
+                assert_eq!(
+                    check_auth_value(value, force).unwrap_err().kind(),
+                    ErrorKind::ValueValidation
+                );
+            }
+            _ => panic!("expected Set"),
+        }
+        assert!(check_auth_value(3, false).is_ok());
+        assert!(check_auth_value(4, false).is_err());
+        assert!(check_auth_value(4, true).is_ok());
+        assert_eq!(
+            json_set_data("Set", "kTCCServicePhotos", 3),
+            "{\"message\":\"Set\",\"service_raw\":\"kTCCServicePhotos\",\"status\":\"limited\",\"auth_value\":3}"
+        );
+    }
+
+    #[test]
+    fn parse_grant() {
+        let cli = parse(&["tcc", "grant", "Camera", "com.app.test"]).unwrap();
+        match cli.command {
+            Commands::Grant {
+                service,
+                client_path,
+                target_app,
+                pid,
+                client_file,
+                if_missing,
+                force,
+                merge_csreq_from_db,
+                verbose_diff,
+            } => {
+                assert_eq!(service, "Camera");
+                assert_eq!(client_path.as_deref(), Some("com.app.test"));
+                assert!(target_app.is_none());
+                assert!(pid.is_none());
+                assert!(client_file.is_none());
+                assert!(!if_missing);
+                assert!(!force);
+                assert!(!merge_csreq_from_db);
+                assert!(!verbose_diff);
+            }
+            _ => panic!("expected Grant"),
+        }
+    }
+
+    #[test]
+    fn parse_automation_target_only_for_apple_events() {
+        for cmd in ["grant", "revoke"] {
+            let cli = parse(&[
+                "tcc",
+                cmd,
+                "AppleEvents",
+                "com.foo",
+                "com.apple.systemevents",
+            ])
+            .unwrap();
+            let (service, target_app) = match cli.command {
+                Commands::Grant {
+                    service,
+                    target_app,
+                    ..
+                }
+                | Commands::Revoke {
+                    service,
+                    target_app,
+                    ..
+                } => (service, target_app),
+                _ => panic!("expected Grant or Revoke"),
+            };
+            assert_eq!(target_app.as_deref(), Some("com.apple.systemevents"));
+            assert!(check_target_app(&service, target_app.as_deref()).is_ok());
+        }
+        let err = check_target_app("Camera", Some("com.apple.systemevents")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        assert!(check_target_app("Camera", None).is_ok());
+        // The target follows the client, so it cannot stand in for one
+        let err = parse(&[
+            "tcc",
+            "grant",
+            "AppleEvents",
+            "--pid",
+            "1",
+            "com.apple.finder",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_client_file_excludes_positional_client() {
+        let cli = parse(&["tcc", "grant", "Camera", "--client-file", "/tmp/c"]).unwrap();
+        match cli.command {
+            Commands::Grant {
+                client_path,
+                client_file,
+                ..
+            } => {
+                assert!(client_path.is_none());
+                assert_eq!(client_file, Some(PathBuf::from("/tmp/c")));
+            }
+            _ => panic!("expected Grant"),
+        }
+        for cmd in ["grant", "revoke", "enable", "disable", "toggle", "reset"] {
+            let err =
+                parse(&["tcc", cmd, "Camera", "com.app.x", "--client-file", "/tmp/c"]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ArgumentConflict, "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn parse_revoke() {
+        let cli = parse(&["tcc", "revoke", "Camera", "com.app.test"]).unwrap();
+        match cli.command {
+            Commands::Revoke {
+                service,
+                client_path,
+                pid,
+                ..
+            } => {
+                assert_eq!(service, "Camera");
+                assert_eq!(client_path.as_deref(), Some("com.app.test"));
+                assert!(pid.is_none());
+            }
+            _ => panic!("expected Revoke"),
+        }
+    }
+
+    #[test]
+    fn parse_grant_with_pid() {
+        let cli = parse(&["tcc", "grant", "Camera", "--pid", "1234"]).unwrap();
+        match cli.command {
+            Commands::Grant {
+                client_path, pid, ..
+            } => {
+                assert!(client_path.is_none());
+                assert_eq!(pid, Some(1234));
+            }
+            _ => panic!("expected Grant"),
+        }
+    }
+
+    #[test]
+    fn parse_revoke_pid_conflicts_with_client() {
+        let err = parse(&["tcc", "revoke", "Camera", "com.app.x", "--pid", "1"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_enable() {
+        let cli = parse(&["tcc", "enable", "Accessibility", "/usr/bin/foo"]).unwrap();
+        match cli.command {
+            Commands::Enable {
+                service,
+                client_path,
+                all_clients,
+                ..
+            } => {
+                assert_eq!(service, "Accessibility");
+                assert_eq!(client_path.as_deref(), Some("/usr/bin/foo"));
+                assert!(!all_clients);
+            }
+            _ => panic!("expected Enable"),
+        }
+    }
+
+    #[test]
+    fn parse_disable() {
+        let cli = parse(&["tcc", "disable", "Microphone", "com.app.x"]).unwrap();
+        match cli.command {
+            Commands::Disable {
+                service,
+                client_path,
+                ..
+            } => {
+                assert_eq!(service, "Microphone");
+                assert_eq!(client_path.as_deref(), Some("com.app.x"));
+            }
+            _ => panic!("expected Disable"),
+        }
+    }
+
+    #[test]
+    fn parse_disable_all_clients_requires_yes() {
+        let cli = parse(&["tcc", "disable", "Camera", "--all-clients", "--yes"]).unwrap();
+        match cli.command {
+            Commands::Disable {
+                client_path,
+                all_clients,
+                ..
+            } => {
+                assert!(client_path.is_none());
+                assert!(all_clients);
+            }
+            _ => panic!("expected Disable"),
+        }
+
+        let err = parse(&["tcc", "disable", "Camera", "--all-clients"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        let err = parse(&[
+            "tcc",
+            "disable",
+            "Camera",
+            "com.app.x",
+            "--all-clients",
+            "--yes",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        assert!(parse(&["tcc", "enable", "Camera"]).is_err());
+    }
+
+    #[test]
+    fn parse_reset_with_client() {
+        let cli = parse(&["tcc", "reset", "Camera", "com.app.test"]).unwrap();
+        match cli.command {
+            Commands::Reset {
+                service,
+                client_path,
+                ..
+            } => {
+                assert_eq!(service.as_deref(), Some("Camera"));
+                assert_eq!(client_path.as_deref(), Some("com.app.test"));
+            }
+            _ => panic!("expected Reset"),
+        }
+    }
+
+    #[test]
+    fn parse_reset_without_client() {
+        let cli = parse(&["tcc", "reset", "Camera"]).unwrap();
+        match cli.command {
+            Commands::Reset {
+                service,
+                client_path,
+                client_file,
+                keep,
+                confirm_each,
+                interactive,
+                yes,
+                summary_only,
+                show_deleted,
+                family,
+            } => {
+                assert_eq!(service.as_deref(), Some("Camera"));
+                assert!(client_path.is_none());
+                assert!(client_file.is_none());
+                assert!(keep.is_empty());
+                assert!(!confirm_each);
+                assert!(!interactive);
+                assert!(!yes);
+                assert!(!summary_only);
+                assert!(!show_deleted);
+                assert!(family.is_none());
+            }
+            _ => panic!("expected Reset"),
+        }
+    }
+
+    #[test]
+    fn parse_reset_family_needs_yes_and_no_service() {
+        let cli = parse(&["tcc", "reset", "--family", "personal-data", "--yes"]).unwrap();
+        match cli.command {
+            Commands::Reset {
+                service, family, ..
+            } => {
+                assert!(service.is_none());
+                assert_eq!(family, Some(Family::PersonalData));
+            }
+            _ => panic!("expected Reset"),
+        }
+        let err = parse(&["tcc", "reset", "--family", "files"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        let err = parse(&["tcc", "reset", "Camera", "--family", "files", "--yes"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        // Without --family or --interactive there is nothing for --yes to confirm
+        assert!(parse(&["tcc", "reset", "Camera", "--yes"]).is_err());
+        assert!(parse(&["tcc", "reset"]).is_err());
+    }
+
+    #[test]
+    fn parse_reset_keep_repeatable() {
+        let cli = parse(&[
+            "tcc",
+            "reset",
+            "Camera",
+            "--keep",
+            "com.app.a",
+            "--keep",
+            "com.app.b",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Reset {
+                client_path, keep, ..
+            } => {
+                assert!(client_path.is_none());
+                assert_eq!(keep, vec!["com.app.a", "com.app.b"]);
+            }
+            _ => panic!("expected Reset"),
+        }
+    }
+
+    #[test]
+    fn parse_reset_keep_conflicts_with_client() {
+        let err =
+            parse(&["tcc", "reset", "Camera", "com.app.a", "--keep", "com.app.b"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_reset_confirm_each_conflicts_with_client_and_keep() {
+        assert!(parse(&["tcc", "reset", "Camera", "--confirm-each"]).is_ok());
+        let err = parse(&["tcc", "reset", "Camera", "com.app.a", "--confirm-each"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        let err = parse(&["tcc", "reset", "Camera", "--keep", "x", "--confirm-each"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn parse_reset_yes_requires_interactive() {
+        assert!(parse(&["tcc", "reset", "Camera", "--interactive", "--yes"]).is_ok());
+        assert!(parse(&["tcc", "reset", "Camera", "--interactive", "--keep", "a"]).is_ok());
+        let err = parse(&["tcc", "reset", "Camera", "--yes"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        let err =
+            parse(&["tcc", "reset", "Camera", "--interactive", "--confirm-each"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn confirm_once_only_accepts_yes() {
+        for (answer, expected) in [
+            ("y\n", true),
+            ("YES\n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("", false),
+        ] {
+            let mut output = Vec::new();
+            let confirmed = confirm_once(
+                "Delete?",
+                &mut io::Cursor::new(answer.as_bytes()),
+                &mut output,
+            )
+            .unwrap();
+            assert_eq!(confirmed, expected, "answer {:?}", answer);
+            assert_eq!(String::from_utf8(output).unwrap(), "Delete? [y/N] ");
+        }
+    }
+
+    fn confirm_answers(answers: &str) -> (Vec<String>, String) {
+        let entries = [
+            stream_entry("Camera", "com.app.a"),
+            stream_entry("Camera", "com.app.b"),
+            stream_entry("Camera", "com.app.c"),
+        ];
+        let mut input = io::Cursor::new(answers.as_bytes());
+        let mut output = Vec::new();
+        let picked = confirm_each(&entries, &mut input, &mut output).unwrap();
+        (
+            picked.iter().map(|e| e.client.clone()).collect(),
+            String::from_utf8(output).unwrap(),
+        )
+    }
+
+    #[test]
+    fn confirm_each_yes_no_default() {
+        let (picked, prompts) = confirm_answers("y\n\nY\n");
+        assert_eq!(picked, vec!["com.app.a", "com.app.c"]);
+        assert!(prompts.starts_with("Delete Camera for com.app.a? [y/N/a/q] "));
+        assert_eq!(prompts.matches("[y/N/a/q]").count(), 3);
+    }
+
+    #[test]
+    fn confirm_each_all_takes_remaining() {
+        let (picked, prompts) = confirm_answers("n\na\n");
+        assert_eq!(picked, vec!["com.app.b", "com.app.c"]);
+        assert_eq!(prompts.matches("[y/N/a/q]").count(), 2);
+    }
+
+    #[test]
+    fn confirm_each_quit_and_eof_stop_prompting() {
+        let (picked, prompts) = confirm_answers("y\nq\ny\n");
+        assert_eq!(picked, vec!["com.app.a"]);
+        assert_eq!(prompts.matches("[y/N/a/q]").count(), 2);
+
+        let (picked, _) = confirm_answers("y\n");
+        assert_eq!(picked, vec!["com.app.a"]);
+    }
+
+    #[test]
+    fn parse_db_conflicts_with_user_and_home() {
+        let cli = parse(&["tcc", "list", "--db", "/tmp/copy.db"]).unwrap();
+        assert_eq!(cli.db, Some(PathBuf::from("/tmp/copy.db")));
+        assert!(parse(&["tcc", "--db", "/tmp/copy.db", "--user", "list"]).is_err());
+        assert!(parse(&["tcc", "--db", "/tmp/copy.db", "--home", "/tmp/x", "list"]).is_err());
+    }
+
+    #[test]
+    fn parse_paths_with_home() {
+        let cli = parse(&["tcc", "paths", "--home", "/tmp/x"]).unwrap();
+        assert!(matches!(cli.command, Commands::Paths));
+        assert_eq!(cli.home, Some(PathBuf::from("/tmp/x")));
+    }
+
+    #[test]
+    fn completions_offer_service_names_without_restricting_parsing() {
+        let cli = parse(&["tcc", "completions", "zsh"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Completions { shell: Shell::Zsh }
+        ));
+        let help = Cli::command().render_help().to_string();
+        assert!(!help.contains("completions"));
+
+        let mut out = Vec::new();
+        clap_complete::generate(
+            Shell::Zsh,
+            &mut completion_command(),
+            "tccutil-rs",
+            &mut out,
+        );
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.starts_with("#compdef tccutil-rs"));
+        assert!(script.contains("_tccutil-rs()"));
+        assert!(script.contains(r"Screen\ Recording"));
+
+        // Only the generated script lists the names; the parser still
+        // resolves internal keys and partial names itself.
+        assert!(parse(&["tcc", "grant", "kTCCServiceCamera", "x"]).is_ok());
+        assert!(parse(&["tcc", "list", "--service", "screen"]).is_ok());
+    }
+
+    #[test]
+    fn parse_assume_schema() {
+        let cli = parse(&["tcc", "grant", "Camera", "x", "--assume-schema", "strict"]).unwrap();
+        assert_eq!(cli.assume_schema, Some(AssumeSchema::Strict));
+        let cli = parse(&["tcc", "--assume-schema", "known", "list"]).unwrap();
+        assert_eq!(cli.assume_schema, Some(AssumeSchema::Known));
+        let err = parse(&["tcc", "--assume-schema", "maybe", "list"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn parse_repair() {
+        let cli = parse(&["tcc", "repair"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Repair {
+                fix_duplicates: false
+            }
+        ));
+        let cli = parse(&["tcc", "repair", "--fix-duplicates"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Repair {
+                fix_duplicates: true
+            }
+        ));
+    }
+
+    #[test]
+    fn json_doctor_reports_worst_status() {
+        let checks = vec![
+            DoctorCheck {
+                id: "root",
+                label: "Root privileges",
+                status: CheckStatus::Ok,
+                detail: "Running as root".to_string(),
+                remediation: None,
+            },
+            DoctorCheck {
+                id: "fda",
+                label: "Full Disk Access",
+                status: CheckStatus::Warn,
+                detail: "user DB \"x\"".to_string(),
+                remediation: Some("Grant it".to_string()),
+            },
+        ];
+        assert_eq!(
+            json_doctor_data(&checks),
+            "{\"status\":\"warn\",\"checks\":[\
+             {\"id\":\"root\",\"label\":\"Root privileges\",\"status\":\"ok\",\"detail\":\"Running as root\",\"remediation\":null},\
+             {\"id\":\"fda\",\"label\":\"Full Disk Access\",\"status\":\"warn\",\"detail\":\"user DB \\\"x\\\"\",\"remediation\":\"Grant it\"}]}"
+        );
+    }
+
+    #[test]
+    fn parse_self_test() {
+        let cli = parse(&["tcc", "self-test"]).unwrap();
+        assert!(matches!(cli.command, Commands::SelfTest));
+    }
+
+    #[test]
+    fn parse_user_flag_global() {
+        let cli = parse(&["tcc", "--user", "list"]).unwrap();
+        assert!(cli.user);
+    }
+
+    #[test]
+    fn parse_user_flag_after_subcommand() {
+        let cli = parse(&["tcc", "list", "--user"]).unwrap();
+        assert!(cli.user);
+    }
+
+    #[test]
+    fn parse_json_flag_global() {
+        let cli = parse(&["tcc", "--json", "services"]).unwrap();
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn parse_json_flag_after_subcommand() {
+        let cli = parse(&["tcc", "services", "--json"]).unwrap();
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn parse_json_short_flag() {
+        let cli = parse(&["tcc", "-j", "info"]).unwrap();
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn parse_no_subcommand_is_error() {
+        let err = parse(&["tcc"]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+        );
+    }
+
+    #[test]
+    fn parse_unknown_subcommand_is_error() {
+        let err = parse(&["tcc", "foobar"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidSubcommand);
+    }
+
+    #[test]
+    fn parse_grant_missing_args_is_error() {
+        let err = parse(&["tcc", "grant"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn cli_has_version() {
+        let cmd = Cli::command();
+        assert!(cmd.get_version().is_some());
+    }
+}
+
+/// Prompt for each entry and return the ones to delete. `y` picks the
+/// entry, `a` picks it and every remaining one, `q` (or end of input) stops
+/// and keeps what was picked so far; anything else skips it.
+fn confirm_each<'a>(
+    entries: &'a [TccEntry],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<Vec<&'a TccEntry>> {
+    let mut picked = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        write!(
+            output,
+            "Delete {} for {}? [y/N/a/q] ",
+            entry.service_display, entry.client
+        )?;
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            break;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => picked.push(entry),
+            "a" | "all" => {
+                picked.extend(&entries[i..]);
+                break;
+            }
+            "q" | "quit" => break,
+            _ => {}
+        }
+    }
+    Ok(picked)
+}
+
+/// Ask a single yes/no question; only `y`/`yes` counts as yes, so end of
+/// input declines.
+fn confirm_once(
+    prompt: &str,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    write!(output, "{} [y/N] ", prompt)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Exit codes for `check`: granted, not granted, no entry (errors exit 1)
+const CHECK_EXIT_GRANTED: i32 = 0;
+const CHECK_EXIT_NOT_GRANTED: i32 = 2;
+const CHECK_EXIT_NO_ENTRY: i32 = 3;
+
+fn check_exit_code(entry: Option<&TccEntry>) -> i32 {
+    match entry {
+        Some(e) if e.auth_value == 2 => CHECK_EXIT_GRANTED,
+        Some(_) => CHECK_EXIT_NOT_GRANTED,
+        None => CHECK_EXIT_NO_ENTRY,
+    }
+}
+
+/// The status as printed, before coloring; mono prefixes a mark
+fn status_label(auth_value: i32, scheme: ColorScheme, glyphs: &Glyphs) -> String {
+    let status = auth_value_display(auth_value);
+    if scheme != ColorScheme::Mono {
+        return status;
+    }
+    match auth_value {
+        0 => format!("{} {}", glyphs.denied, status),
+        2 => format!("{} {}", glyphs.granted, status),
+        3 => format!("{} {}", glyphs.limited, status),
+        _ => status,
+    }
+}
+
+fn colored_status(auth_value: i32, scheme: ColorScheme, glyphs: &Glyphs) -> String {
+    let status = status_label(auth_value, scheme, glyphs);
+    match scheme.status_color(auth_value) {
+        Some(color) => status.color(color).to_string(),
+        None => status,
+    }
+}
+
+/// Entry fields a `--template` can reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TemplateField {
+    ServiceRaw,
+    ServiceDisplay,
+    Client,
+    Status,
+    AuthValue,
+    Source,
+    LastModified,
+}
+
+impl TemplateField {
+    const NAMES: &[&str] = &[
+        "service",
+        "service_raw",
+        "service_display",
+        "client",
+        "status",
+        "auth_value",
+        "source",
+        "last_modified",
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "service" | "service_display" => Some(Self::ServiceDisplay),
+            "service_raw" => Some(Self::ServiceRaw),
+            "client" => Some(Self::Client),
+            "status" => Some(Self::Status),
+            "auth_value" => Some(Self::AuthValue),
+            "source" => Some(Self::Source),
+            "last_modified" => Some(Self::LastModified),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TemplatePart<F> {
+    Literal(String),
+    Field(F),
+}
+
+/// A parsed `--template`: literal text with `{field}` placeholders.
+/// `{{` and `}}` stand for literal braces.
+#[derive(Clone, Debug, PartialEq)]
+struct OutputTemplate(Vec<TemplatePart<TemplateField>>);
+
+/// Totals a `--footer-format` can reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FooterField {
+    Count,
+    Granted,
+    Denied,
+    Limited,
+    Unknown,
+    User,
+    System,
+}
+
+impl FooterField {
+    const NAMES: &[&str] = &[
+        "count", "granted", "denied", "limited", "unknown", "user", "system",
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(Self::Count),
+            "granted" => Some(Self::Granted),
+            "denied" => Some(Self::Denied),
+            "limited" => Some(Self::Limited),
+            "unknown" => Some(Self::Unknown),
+            "user" => Some(Self::User),
+            "system" => Some(Self::System),
+            _ => None,
+        }
+    }
+}
+
+/// The line under a table, like a `--template` but over the whole listing
+#[derive(Clone, Debug, PartialEq)]
+struct FooterTemplate(Vec<TemplatePart<FooterField>>);
+
+const DEFAULT_FOOTER: &str = "{count} entries total";
+
+fn parse_footer(input: &str) -> Result<FooterTemplate, String> {
+    parse_placeholders(input, FooterField::from_name, FooterField::NAMES).map(FooterTemplate)
+}
+
+impl Default for FooterTemplate {
+    fn default() -> Self {
+        parse_footer(DEFAULT_FOOTER).expect("default footer parses")
+    }
+}
+
+impl FooterTemplate {
+    fn render(&self, entries: &[TccEntry]) -> String {
+        let count = |keep: fn(&TccEntry) -> bool| entries.iter().filter(|e| keep(e)).count();
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                TemplatePart::Literal(text) => out.push_str(text),
+                TemplatePart::Field(field) => {
+                    let n = match field {
+                        FooterField::Count => entries.len(),
+                        FooterField::Granted => count(|e| e.auth_value == 2),
+                        FooterField::Denied => count(|e| e.auth_value == 0),
+                        FooterField::Limited => count(|e| e.auth_value == 3),
+                        FooterField::Unknown => count(|e| ![0, 2, 3].contains(&e.auth_value)),
+                        FooterField::User => count(|e| !e.is_system),
+                        FooterField::System => count(|e| e.is_system),
+                    };
+                    out.push_str(&n.to_string());
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Unix seconds for `list --since`/`--until`. Accepts plain Unix seconds, an
+/// RFC 3339 date-time, a local `YYYY-MM-DD[THH:MM:SS]` (a bare date is its
+/// midnight), or an age before now: a number followed by s, m, h, d or w.
+fn parse_time_bound(input: &str) -> Result<i64, String> {
+    use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    if let Ok(secs) = input.parse::<i64>() {
+        return Ok(secs);
+    }
+    if let Some(unit) = input.chars().last().filter(char::is_ascii_alphabetic)
+        && let Ok(n) = input[..input.len() - 1].parse::<u32>()
+    {
+        let unit_secs = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => {
+                return Err(format!(
+                    "unknown unit '{}' in '{}' (use s, m, h, d or w)",
+                    unit, input
+                ));
+            }
+        };
+        return Ok(chrono::Utc::now().timestamp() - i64::from(n) * unit_secs);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Ok(dt.timestamp());
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        });
+    match naive.and_then(|n| Local.from_local_datetime(&n).earliest()) {
+        Some(dt) => Ok(dt.timestamp()),
+        None => Err(format!(
+            "'{}' is not Unix seconds, an ISO-8601 date (2026-03-01, 2026-03-01T09:30:00, RFC 3339) or an age like 7d",
+            input
+        )),
+    }
+}
+
+fn parse_regex(input: &str) -> Result<Regex, String> {
+    Regex::new(input).map_err(|e| e.to_string())
+}
+
+fn parse_template(input: &str) -> Result<OutputTemplate, String> {
+    parse_placeholders(input, TemplateField::from_name, TemplateField::NAMES).map(OutputTemplate)
+}
+
+/// Split `input` into literals and `{field}` placeholders named in `names`
+fn parse_placeholders<F>(
+    input: &str,
+    from_name: fn(&str) -> Option<F>,
+    names: &[&str],
+) -> Result<Vec<TemplatePart<F>>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unclosed '{{{}' in template", name)),
+                    }
+                }
+                let field = from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown template field '{{{}}}' (expected one of: {})",
+                        name,
+                        names.join(", ")
+                    )
+                })?;
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(TemplatePart::Field(field));
+            }
+            '}' => return Err("unmatched '}' in template (use '}}' for a literal brace)".into()),
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(parts)
+}
+
+impl OutputTemplate {
+    fn render(&self, entry: &TccEntry, view: ClientView) -> String {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                TemplatePart::Literal(text) => out.push_str(text),
+                TemplatePart::Field(field) => match field {
+                    TemplateField::ServiceRaw => out.push_str(&entry.service_raw),
+                    TemplateField::ServiceDisplay => out.push_str(&entry.service_display),
+                    TemplateField::Client => out.push_str(&display_client(&entry.client, view)),
+                    TemplateField::Status => out.push_str(&auth_value_display(entry.auth_value)),
+                    TemplateField::AuthValue => out.push_str(&entry.auth_value.to_string()),
+                    TemplateField::Source => {
+                        out.push_str(if entry.is_system { "system" } else { "user" })
+                    }
+                    TemplateField::LastModified => out.push_str(&entry.last_modified),
+                },
+            }
+        }
+        out
+    }
+}
+
+fn check_oneline(
+    service_display: &str,
+    client: &str,
+    entry: Option<&TccEntry>,
+    scheme: ColorScheme,
+    glyphs: &Glyphs,
+) -> String {
+    let status = match entry {
+        Some(e) => colored_status(e.auth_value, scheme, glyphs),
+        None => "not set".dimmed().to_string(),
+    };
+    format!("{} {}: {}", service_display, client, status)
+}
+
+fn error_kind(error: &TccError) -> &'static str {
+    match error {
+        TccError::DbOpen { .. } => "DbOpen",
+        TccError::NotFound { .. } => "NotFound",
+        TccError::NeedsRoot { .. } => "NeedsRoot",
+        TccError::UnknownService(_) => "UnknownService",
+        TccError::AmbiguousService { .. } => "AmbiguousService",
+        TccError::QueryFailed(_) => "QueryFailed",
+        TccError::SchemaInvalid(_) => "SchemaInvalid",
+        TccError::HomeDirNotFound => "HomeDirNotFound",
+        TccError::WriteFailed(_) => "WriteFailed",
+        TccError::PidNotResolved { .. } => "PidNotResolved",
+        TccError::ClientFile { .. } => "ClientFile",
+        TccError::DumpInvalid { .. } => "DumpInvalid",
+        TccError::EntryExists { .. } => "EntryExists",
+        TccError::NotToggleable { .. } => "NotToggleable",
+        TccError::DbMissing(_) => "DbMissing",
+        TccError::SchemaMismatch { .. } => "SchemaMismatch",
+        TccError::DbLocked(_) => "DbLocked",
+    }
+}
+
+fn json_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{08}' => escaped.push_str("\\b"),
+            '\u{0C}' => escaped.push_str("\\f"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn json_string(value: &str) -> String {
+    format!("\"{}\"", json_escape(value))
+}
+
+/// Why `raw_json` is not a single valid JSON value, if it is not
+fn json_error(raw_json: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(raw_json)
+        .err()
+        .map(|e| e.to_string())
+}
+
+/// Safety net for the hand-built JSON: catches escaping and splicing bugs
+/// by failing the process rather than printing a broken document. Runs
+/// with `--strict-json`; debug builds, and so the test suite, always check.
+fn check_json(json_out: JsonOutput, raw_json: &str) {
+    if !cfg!(debug_assertions) && !json_out.strict {
+        return;
+    }
+    if let Some(e) = json_error(raw_json) {
+        eprintln!(
+            "{}: internal error, refusing to print invalid JSON ({}): {}",
+            "Error".red().bold(),
+            e,
+            raw_json
+        );
+        exit(1);
+    }
+}
+
+fn emit_json(json_out: JsonOutput, raw_json: String) {
+    check_json(json_out, &raw_json);
+    println!("{}", raw_json);
+}
+
+/// How the JSON emitters print a result: the envelope, or with `--csv`
+/// a single summary row in its place
+#[derive(Clone, Copy, Default)]
+struct JsonOutput {
+    csv: bool,
+    /// `--strict-json`: validate every document before printing it
+    strict: bool,
+}
+
+const CSV_LIST_HEADER: &str = "service,service_raw,client,status,auth_value,source,last_modified";
+
+/// `value` as an RFC 4180 field, quoted when it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+fn csv_entry(entry: &TccEntry, view: ClientView) -> String {
+    csv_row(&[
+        &entry.service_display,
+        &entry.service_raw,
+        &display_client(&entry.client, view),
+        &auth_value_display(entry.auth_value),
+        &entry.auth_value.to_string(),
+        if entry.is_system { "system" } else { "user" },
+        &entry.last_modified,
+    ])
+}
+
+/// A header and one `command,ok,message,warnings` row, where `warnings`
+/// is how many were raised
+fn csv_summary(command: &str, ok: bool, message: &str, warnings: usize) -> String {
+    format!(
+        "{}{}",
+        csv_row(&["command", "ok", "message", "warnings"]),
+        csv_row(&[command, &ok.to_string(), message, &warnings.to_string()])
+    )
+}
+
+/// Under `--csv`, print the summary row for a run and report true. The
+/// row only counts the warnings, so their text goes to stderr.
+fn emit_csv_summary(json_out: JsonOutput, command: &str, ok: bool, message: &str) -> bool {
+    if !json_out.csv {
+        return false;
+    }
+    let warnings = take_held_warnings();
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    print!("{}", csv_summary(command, ok, message, warnings.len()));
+    true
+}
+
+fn emit_json_success(json_out: JsonOutput, command: &'static str, data_json: String) {
+    emit_json_message(json_out, command, "", data_json);
+}
+
+/// Like `emit_json_success`, for commands whose data carries a `message`;
+/// it is what `--csv` prints
+fn emit_json_message(
+    json_out: JsonOutput,
+    command: &'static str,
+    message: &str,
+    data_json: String,
+) {
+    if emit_csv_summary(json_out, command, true, message) {
+        return;
+    }
+    emit_json(
+        json_out,
+        format!(
+            "{{\"ok\":true,\"command\":{},\"data\":{},\"error\":null,\"warnings\":{}}}",
+            json_string(command),
+            data_json,
+            json_held_warnings()
+        ),
+    );
+}
+
+thread_local! {
+    /// Warnings held back from stderr during a JSON run, so the envelope
+    /// can carry them
+    static HELD_WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Hold a warning for `take_held_warnings` instead of printing it
+fn hold_warning(message: String) {
+    HELD_WARNINGS.with_borrow_mut(|held| held.push(message));
+}
+
+/// Every warning held so far, in the order they were raised; clears them
+fn take_held_warnings() -> Vec<String> {
+    HELD_WARNINGS.take()
+}
+
+/// Pass on warnings the library returned: held for the JSON envelope, or
+/// printed to stderr
+fn report_warnings(warnings: Vec<String>, json_mode: bool) {
+    if json_mode {
+        warnings.into_iter().for_each(hold_warning);
+    } else {
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+}
+
+/// `[...]` of the warnings held during the run, for the envelope
+fn json_held_warnings() -> String {
+    let warnings: Vec<String> = take_held_warnings()
+        .iter()
+        .map(|w| json_string(w))
+        .collect();
+    format!("[{}]", warnings.join(","))
+}
+
+fn emit_json_error(
+    json_out: JsonOutput,
+    command: &'static str,
+    kind: &'static str,
+    message: String,
+) {
+    emit_json_error_with(json_out, command, kind, message, "");
+}
+
+/// Like `emit_json_error`, plus any structured fields the error carries
+fn emit_json_tcc_error(json_out: JsonOutput, command: &'static str, err: &TccError) {
+    emit_json_error_with(
+        json_out,
+        command,
+        error_kind(err),
+        err.to_string(),
+        &json_error_extra(err),
+    );
+}
+
+fn emit_json_error_with(
+    json_out: JsonOutput,
+    command: &'static str,
+    kind: &'static str,
+    message: String,
+    extra: &str,
+) {
+    if emit_csv_summary(json_out, command, false, &message) {
+        return;
+    }
+    emit_json(
+        json_out,
+        format!(
+            "{{\"ok\":false,\"command\":{},\"data\":null,\"error\":{{\"kind\":{},\"message\":{}{}}},\"warnings\":{}}}",
+            json_string(command),
+            json_string(kind),
+            json_string(&message),
+            extra,
+            json_held_warnings()
+        ),
+    );
+}
+
+/// Extra `error` fields, each with a leading comma; empty for most errors
+fn json_error_extra(err: &TccError) -> String {
+    match err {
+        TccError::AmbiguousService { candidates, .. } => {
+            let items: Vec<String> = candidates
+                .iter()
+                .map(|(key, display)| {
+                    format!(
+                        "{{\"service_raw\":{},\"display\":{}}}",
+                        json_string(key),
+                        json_string(display)
+                    )
+                })
+                .collect();
+            format!(",\"candidates\":[{}]", items.join(","))
+        }
+        _ => String::new(),
+    }
+}
+
+/// `service_raw` is the canonical key the write resolved to
+fn json_message_data(message: &str, service_raw: &str) -> String {
+    format!(
+        "{{\"message\":{},\"service_raw\":{}}}",
+        json_string(message),
+        json_string(service_raw)
+    )
+}
+
+/// `json_message_data` plus the status and auth value before and after a toggle
+fn json_toggle_data(message: &str, service_raw: &str, before: i32, after: i32) -> String {
+    let mut data = json_message_data(message, service_raw);
+    data.pop();
+    data.push_str(&format!(
+        ",\"previous_status\":{},\"previous_auth_value\":{},\"status\":{},\"auth_value\":{}}}",
+        json_string(&auth_value_display(before)),
+        before,
+        json_string(&auth_value_display(after)),
+        after
+    ));
+    data
+}
+
+fn restore_message(outcome: &RestoreOutcome, dry_run: bool) -> String {
+    format!(
+        "{} {} rows, skipped {}",
+        if dry_run { "Would restore" } else { "Restored" },
+        outcome.restored,
+        outcome.skipped
+    )
+}
+
+fn json_restore_data(outcome: &RestoreOutcome, dry_run: bool) -> String {
+    format!(
+        "{{\"message\":{},\"dry_run\":{},\"restored\":{},\"skipped\":{}}}",
+        json_string(&restore_message(outcome, dry_run)),
+        dry_run,
+        outcome.restored,
+        outcome.skipped
+    )
+}
+
+/// `json_message_data` plus the status and auth value `set` wrote
+fn json_set_data(message: &str, service_raw: &str, auth_value: i32) -> String {
+    let mut data = json_message_data(message, service_raw);
+    data.pop();
+    data.push_str(&format!(
+        ",\"status\":{},\"auth_value\":{}}}",
+        json_string(&auth_value_display(auth_value)),
+        auth_value
+    ));
+    data
+}
+
+/// How clients are shown: shortened for display (`--compact`,
+/// `--truncate-client`), and with their code requirement (`--show-csreq`)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ClientView {
+    /// Keep the last N path components
+    compact: Option<usize>,
+    /// Cap at N characters, the last ones replaced by an ellipsis
+    truncate: Option<usize>,
+    /// Add each entry's csreq, in this encoding for text
+    csreq: Option<CsreqEncoding>,
+}
+
+/// The client as shown: compacted to the given path depth, then capped, or in full
+fn display_client(client: &str, view: ClientView) -> String {
+    let shown = match view.compact {
+        Some(depth) => compact_client(client, depth),
+        None => client.to_string(),
+    };
+    match view.truncate {
+        Some(max) => truncate_chars(&shown, max),
+        None => shown,
+    }
+}
+
+/// `s` cut to at most `max` characters, ending in `…` when anything was cut
+fn truncate_chars(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+/// `list --newer-than`: each entry with its own and the other DB's modification time
+fn print_newer_entries(
+    entries: &[TccEntry],
+    other_ts: &[Option<i64>],
+    view: ClientView,
+    glyphs: &Glyphs,
+) {
+    if entries.is_empty() {
+        println!("{}", "No entries newer than the other DB.".dimmed());
+        return;
+    }
+    let rows: Vec<(&str, String, &str, String)> = entries
+        .iter()
+        .zip(other_ts)
+        .map(|(e, ts)| {
+            (
+                e.service_display.as_str(),
+                display_client(&e.client, view),
+                e.last_modified.as_str(),
+                ts.map_or("missing".to_string(), TccDb::format_timestamp),
+            )
+        })
+        .collect();
+    let hdr = ["SERVICE", "CLIENT", "LAST MODIFIED", "OTHER DB"];
+    let svc_w = rows
+        .iter()
+        .map(|r| r.0.len())
+        .max()
+        .unwrap_or(0)
+        .max(hdr[0].len());
+    let client_w = rows
+        .iter()
+        .map(|r| r.1.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(hdr[1].len());
+    let modified_w = rows
+        .iter()
+        .map(|r| r.2.len())
+        .max()
+        .unwrap_or(0)
+        .max(hdr[2].len());
+    let other_w = rows
+        .iter()
+        .map(|r| r.3.len())
+        .max()
+        .unwrap_or(0)
+        .max(hdr[3].len());
+    println!(
+        "{:<svc_w$}  {:<client_w$}  {:<modified_w$}  {}",
+        hdr[0], hdr[1], hdr[2], hdr[3]
+    );
+    println!(
+        "{}  {}  {}  {}",
+        glyphs.rule.repeat(svc_w),
+        glyphs.rule.repeat(client_w),
+        glyphs.rule.repeat(modified_w),
+        glyphs.rule.repeat(other_w)
+    );
+    for (service, client, modified, other) in &rows {
+        println!("{service:<svc_w$}  {client:<client_w$}  {modified:<modified_w$}  {other}");
+    }
+}
+
+/// Machine output is independent of the terminal: every JSON/NDJSON writer
+/// goes through here and emits services and clients in full whatever the
+/// width. Only an explicit `--compact` or `--truncate-client` shortens the
+/// client; with `--truncate-client`, `client_full` keeps the original.
+fn json_entry(entry: &TccEntry, view: ClientView) -> String {
+    let client = display_client(&entry.client, view);
+    let client_full = if view.truncate.is_some() {
+        format!(",\"client_full\":{}", json_string(&entry.client))
+    } else {
+        String::new()
+    };
+    let source = if entry.is_system { "system" } else { "user" };
+    let mut extended = String::new();
+    if let Some(pid) = entry.pid {
+        extended.push_str(&format!(",\"pid\":{}", pid));
+    }
+    if let Some(reminded) = &entry.last_reminded {
+        extended.push_str(&format!(",\"last_reminded\":{}", json_string(reminded)));
+    }
+    if let Some(target) = &entry.indirect_object {
+        extended.push_str(&format!(",\"indirect_object\":{}", json_string(target)));
+    }
+    if view.csreq.is_some() {
+        extended.push_str(&format!(",\"csreq\":{}", json_csreq(entry)));
+    }
+    format!(
+        "{{\"service\":{},\"service_raw\":{},\"client\":{}{},\"status\":{},\"auth_value\":{},\"source\":{},\"last_modified\":{},\"last_modified_ts\":{},\"client_type\":{},\"auth_reason\":{}{}}}",
+        json_string(&entry.service_display),
+        json_string(&entry.service_raw),
+        json_string(&client),
+        client_full,
+        json_string(&auth_value_display(entry.auth_value)),
+        entry.auth_value,
+        json_string(source),
+        json_string(&entry.last_modified),
+        entry.last_modified_ts,
+        entry.client_type,
+        entry.auth_reason,
+        extended,
+    )
+}
+
+fn json_access_row(row: &AccessRow) -> String {
+    let flags = row.flags.map_or("null".to_string(), |f| f.to_string());
+    let source = if row.is_system { "system" } else { "user" };
+    format!(
+        "{{\"service_raw\":{},\"client\":{},\"client_type\":{},\"auth_value\":{},\"status\":{},\"auth_reason\":{},\"auth_version\":{},\"flags\":{},\"last_modified\":{},\"source\":{}}}",
+        json_string(&row.service_raw),
+        json_string(&row.client),
+        row.client_type,
+        row.auth_value,
+        json_string(&auth_value_display(row.auth_value)),
+        row.auth_reason,
+        row.auth_version,
+        flags,
+        row.last_modified,
+        json_string(source),
+    )
+}
+
+/// `{"message":..., "entry":...}` for writes that report the affected row,
+/// plus `"outcome"` (`created`/`skipped`) for conditional writes
+fn json_write_data(
+    message: &str,
+    service_raw: &str,
+    row: Option<&AccessRow>,
+    created: Option<bool>,
+) -> String {
+    let outcome = match created {
+        Some(true) => ",\"outcome\":\"created\"",
+        Some(false) => ",\"outcome\":\"skipped\"",
+        None => "",
+    };
+    format!(
+        "{{\"message\":{},\"service_raw\":{},\"entry\":{}{}}}",
+        json_string(message),
+        json_string(service_raw),
+        row.map_or("null".to_string(), json_access_row),
+        outcome
+    )
+}
+
+/// `generated_at` is the Unix time the listing was taken; pass it back as
+/// `list --since` to collect only what changed afterwards.
+fn json_list_data(
+    entries: &[TccEntry],
+    view: ClientView,
+    failures: &[SourceFailure],
+    generated_at: i64,
+    measure: Option<&[SourceCount]>,
+    other_ts: Option<&[Option<i64>]>,
+    tabular: bool,
+) -> String {
+    let entry_json: Vec<String> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let mut object = json_entry(e, view);
+            // With --newer-than, each entry also carries the other DB's timestamps
+            if let Some(other_ts) = other_ts {
+                object.pop();
+                object.push_str(&format!(
+                    ",\"other_last_modified\":{},\"other_last_modified_ts\":{}}}",
+                    other_ts[i].map_or("null".to_string(), |ts| {
+                        json_string(&TccDb::format_timestamp(ts))
+                    }),
+                    other_ts[i].map_or("null".to_string(), |ts| ts.to_string()),
+                ));
+            }
+            object
+        })
+        .collect();
+    let diagnostics_json: Vec<String> = failures
+        .iter()
+        .map(|f| {
+            format!(
+                "{{\"source\":{},\"path\":{},\"message\":{}}}",
+                json_string(f.source.as_str()),
+                json_string(&f.path.display().to_string()),
+                json_string(&f.message),
+            )
+        })
+        .collect();
+    let measure_json = measure.map_or(String::new(), |counts| {
+        format!(",\"measure\":{}", json_measure(counts))
+    });
+    let entries_json = if tabular {
+        json_tabular(entries, view)
+    } else {
+        format!("\"entries\":[{}]", entry_json.join(","))
+    };
+    format!(
+        "{{\"count\":{},\"generated_at\":{},{},\"diagnostics\":[{}]{}}}",
+        entries.len(),
+        generated_at,
+        entries_json,
+        diagnostics_json.join(","),
+        measure_json
+    )
+}
+
+/// `"columns":[...],"rows":[[...],...]` for `list --tabular-json`: the
+/// fields of `json_entry` with the names given once. `client_full`, `pid`,
+/// `last_reminded` and `indirect_object` become columns when any entry would carry them, `null` where one does not, and
+/// `csreq` with `--show-csreq`.
+fn json_tabular(entries: &[TccEntry], view: ClientView) -> String {
+    let with_full = view.truncate.is_some();
+    let with_pid = entries.iter().any(|e| e.pid.is_some());
+    let with_reminded = entries.iter().any(|e| e.last_reminded.is_some());
+    let with_target = entries.iter().any(|e| e.indirect_object.is_some());
+    let mut columns = vec![
+        "service",
+        "service_raw",
+        "client",
+        "status",
+        "auth_value",
+        "source",
+        "last_modified",
+        "last_modified_ts",
+        "client_type",
+        "auth_reason",
+    ];
+    if with_full {
+        columns.push("client_full");
+    }
+    if with_pid {
+        columns.push("pid");
+    }
+    if with_reminded {
+        columns.push("last_reminded");
+    }
+    if with_target {
+        columns.push("indirect_object");
+    }
+    if view.csreq.is_some() {
+        columns.push("csreq");
+    }
+    let rows: Vec<String> = entries
+        .iter()
+        .map(|e| {
+            let mut cells = vec![
+                json_string(&e.service_display),
+                json_string(&e.service_raw),
+                json_string(&display_client(&e.client, view)),
+                json_string(&auth_value_display(e.auth_value)),
+                e.auth_value.to_string(),
+                json_string(if e.is_system { "system" } else { "user" }),
+                json_string(&e.last_modified),
+                e.last_modified_ts.to_string(),
+                e.client_type.to_string(),
+                e.auth_reason.to_string(),
+            ];
+            if with_full {
+                cells.push(json_string(&e.client));
+            }
+            if with_pid {
+                cells.push(e.pid.map_or("null".to_string(), |p| p.to_string()));
+            }
+            if with_reminded {
+                cells.push(
+                    e.last_reminded
+                        .as_deref()
+                        .map_or("null".to_string(), json_string),
+                );
+            }
+            if with_target {
+                cells.push(
+                    e.indirect_object
+                        .as_deref()
+                        .map_or("null".to_string(), json_string),
+                );
+            }
+            if view.csreq.is_some() {
+                cells.push(json_csreq(e));
+            }
+            format!("[{}]", cells.join(","))
+        })
+        .collect();
+    let columns: Vec<String> = columns.iter().map(|c| json_string(c)).collect();
+    format!(
+        "\"columns\":[{}],\"rows\":[{}]",
+        columns.join(","),
+        rows.join(",")
+    )
+}
+
+fn json_measure(counts: &[SourceCount]) -> String {
+    let sources: Vec<String> = counts
+        .iter()
+        .map(|c| {
+            format!(
+                "{{\"source\":{},\"path\":{},\"rows\":{}}}",
+                json_string(c.source.as_str()),
+                json_string(&c.path.display().to_string()),
+                c.rows
+            )
+        })
+        .collect();
+    format!(
+        "{{\"read\":{},\"sources\":[{}]}}",
+        counts.iter().map(|c| c.rows).sum::<usize>(),
+        sources.join(",")
+    )
+}
+
+/// What `list --dedupe` notes: a dimmed line under the table, or a warning
+/// in the JSON envelope
+fn dedupe_note(collapsed: usize) -> String {
+    format!(
+        "{} duplicate rows collapsed (system DB preferred)",
+        collapsed
+    )
+}
+
+/// One-line summary for `list --measure`, e.g.
+/// `Read 500 rows (user 480, system 20); 3 after filters`.
+fn measure_summary(counts: &[SourceCount], shown: usize) -> String {
+    let per_source: Vec<String> = counts
+        .iter()
+        .map(|c| format!("{} {}", c.source.as_str(), c.rows))
+        .collect();
+    format!(
+        "Read {} rows ({}); {} after filters",
+        counts.iter().map(|c| c.rows).sum::<usize>(),
+        per_source.join(", "),
+        shown
+    )
+}
+
+/// Write entries one object at a time as `entries` yields them, framed as a
+/// JSON array or NDJSON. The array framing stays valid JSON (`[]`) when
+/// there are no entries.
+fn write_json_stream(
+    out: &mut impl Write,
+    entries: impl IntoIterator<Item = impl Borrow<TccEntry>>,
+    view: ClientView,
+    framing: JsonStream,
+    json_out: JsonOutput,
+) -> io::Result<()> {
+    match framing {
+        JsonStream::Array => {
+            write!(out, "[")?;
+            for (i, entry) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                let object = json_entry(entry.borrow(), view);
+                check_json(json_out, &object);
+                write!(out, "{}", object)?;
+            }
+            writeln!(out, "]")?;
+        }
+        JsonStream::Ndjson => {
+            for entry in entries {
+                let object = json_entry(entry.borrow(), view);
+                check_json(json_out, &object);
+                writeln!(out, "{}", object)?;
+            }
+        }
+    }
+    out.flush()
+}
+
+fn json_check_data(
+    service_key: &str,
+    client: &str,
+    entry: Option<&TccEntry>,
+    effective: Option<EffectiveAuth>,
+) -> String {
+    let status = entry.map_or("not set".to_string(), |e| auth_value_display(e.auth_value));
+    let auth_value = entry.map_or("null".to_string(), |e| e.auth_value.to_string());
+    let effective_json = effective.map_or(String::new(), |effective| {
+        let body = match effective {
+            EffectiveAuth::Probed(granted) => format!(
+                "{{\"source\":\"probe\",\"scope\":\"this process\",\"granted\":{},\"mismatch\":{}}}",
+                granted,
+                effective_mismatch(entry, granted)
+            ),
+            EffectiveAuth::DbOnly(reason) => format!(
+                "{{\"source\":\"db-only\",\"reason\":{}}}",
+                json_string(reason)
+            ),
+        };
+        format!(",\"effective\":{}", body)
+    });
+    format!(
+        "{{\"service_raw\":{},\"client\":{},\"status\":{},\"auth_value\":{}{}}}",
+        json_string(service_key),
+        json_string(client),
+        json_string(&status),
+        auth_value,
+        effective_json,
+    )
+}
+
+/// Whether the DB and the runtime disagree; a missing row counts as not granted.
+/// A probe only ever answers for this process, which is then the client.
+fn effective_mismatch(entry: Option<&TccEntry>, granted: bool) -> bool {
+    entry.is_some_and(|e| e.auth_value == 2) != granted
+}
+
+fn effective_line(entry: Option<&TccEntry>, effective: EffectiveAuth) -> String {
+    match effective {
+        EffectiveAuth::Probed(granted) => {
+            let runtime = if granted { "granted" } else { "denied" };
+            if effective_mismatch(entry, granted) {
+                format!("Effective for this process: {} (mismatch with DB)", runtime)
+                    .yellow()
+                    .to_string()
+            } else {
+                format!("Effective for this process: {} (matches DB)", runtime)
+            }
+        }
+        EffectiveAuth::DbOnly(reason) => format!("Effective: DB-only ({})", reason)
+            .dimmed()
+            .to_string(),
+    }
+}
+
+fn print_history(periods: &[(String, usize)], unknown: usize, glyphs: &Glyphs) {
+    if periods.is_empty() && unknown == 0 {
+        println!("{}", "No entries found.".dimmed());
+        return;
+    }
+
+    let hdr_period = "PERIOD";
+    let hdr_changes = "CHANGES";
+    let period_w = periods
+        .iter()
+        .map(|(p, _)| p.len())
+        .max()
+        .unwrap_or(0)
+        .max(hdr_period.len());
+    let changes_w = hdr_changes.len();
+
+    println!(
+        "{:<pw$}  {:>cw$}",
+        hdr_period,
+        hdr_changes,
+        pw = period_w,
+        cw = changes_w
+    );
+    println!(
+        "{}  {}",
+        glyphs.rule.repeat(period_w),
+        glyphs.rule.repeat(changes_w)
+    );
+    for (period, count) in periods {
+        println!(
+            "{:<pw$}  {:>cw$}  {}",
+            period,
+            count,
+            glyphs.bar.repeat(*count).cyan(),
+            pw = period_w,
+            cw = changes_w
+        );
+    }
+    if unknown > 0 {
+        println!(
+            "\n{}",
+            format!("{} entries without a modification time", unknown).dimmed()
+        );
+    }
+}
+
+fn print_duplicates(groups: &[DuplicateGroup], fixed: bool) {
+    if groups.is_empty() {
+        println!("{}", "No duplicate rows found.".dimmed());
+        return;
+    }
+    for group in groups {
+        let target = group
+            .indirect_object
+            .as_deref()
+            .map(|t| format!(", target {}", t))
+            .unwrap_or_default();
+        let mut line = format!(
+            "{} DB: {} / {} (client_type {}{}): {} rows",
+            group.source.as_str(),
+            TccDb::service_display_name(&group.service_raw),
+            group.client,
+            group.client_type,
+            target,
+            group.rows
+        );
+        if fixed {
+            line.push_str(&format!(", removed {}", group.removed));
+        }
+        println!("{}", line);
+    }
+    if fixed {
+        let removed: usize = groups.iter().map(|g| g.removed).sum();
+        println!(
+            "\n{}",
+            format!(
+                "Removed {} duplicate rows, kept the newest of each",
+                removed
+            )
+            .green()
+        );
+    } else {
+        println!(
+            "\n{} duplicate groups found. Run with --fix-duplicates to keep only the newest row of each.",
+            groups.len()
+        );
+    }
+}
+
+fn json_repair_data(groups: &[DuplicateGroup], fixed: bool) -> String {
+    let groups_json = groups
+        .iter()
+        .map(|g| {
+            format!(
+                "{{\"source\":{},\"service_raw\":{},\"client\":{},\"client_type\":{},\"indirect_object\":{},\"rows\":{},\"removed\":{}}}",
+                json_string(g.source.as_str()),
+                json_string(&g.service_raw),
+                json_string(&g.client),
+                g.client_type,
+                g.indirect_object.as_deref().map_or("null".to_string(), json_string),
+                g.rows,
+                g.removed,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"fixed\":{},\"removed\":{},\"duplicates\":[{}]}}",
+        fixed,
+        groups.iter().map(|g| g.removed).sum::<usize>(),
+        groups_json
+    )
+}
+
+fn json_history_data(bucket: Bucket, periods: &[(String, usize)], unknown: usize) -> String {
+    let bucket = match bucket {
+        Bucket::Day => "day",
+        Bucket::Month => "month",
+    };
+    let periods_json = periods
+        .iter()
+        .map(|(period, count)| {
+            format!("{{\"period\":{},\"count\":{}}}", json_string(period), count)
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"bucket\":{},\"periods\":[{}],\"unknown\":{}}}",
+        json_string(bucket),
+        periods_json,
+        unknown
+    )
+}
+
+/// The CLI as `completions` describes it: every `service` argument offers the
+/// `SERVICE_MAP` display names. These are attached only here, so parsing
+/// still accepts internal keys and partial names.
+fn completion_command() -> clap::Command {
+    let mut names: Vec<&'static str> = SERVICE_MAP.values().copied().collect();
+    names.sort_unstable();
+    Cli::command().mut_subcommands(|sub| {
+        sub.mut_args(|a| {
+            if a.get_id() == "service" {
+                a.value_parser(PossibleValuesParser::new(names.clone()))
+            } else {
+                a
+            }
+        })
+    })
+}
+
+/// Count known services as (total, system, user), categorised by which DB
+/// their entries live in.
+fn service_counts() -> (usize, usize, usize) {
+    let system = SERVICE_MAP
+        .keys()
+        .filter(|key| Service::try_from(**key).is_ok_and(|svc| svc.is_system()))
+        .count();
+    (SERVICE_MAP.len(), system, SERVICE_MAP.len() - system)
+}
+
+fn json_services_count_data(counts: (usize, usize, usize)) -> String {
+    let (total, system, user) = counts;
+    format!(
+        "{{\"total\":{},\"by_category\":{{\"system\":{},\"user\":{}}}}}",
+        total, system, user
+    )
+}
+
+fn json_services_data() -> String {
+    let mut pairs: Vec<_> = SERVICE_MAP.iter().collect();
+    pairs.sort_by_key(|(_, desc)| *desc);
+    let services = pairs
+        .iter()
+        .map(|(key, desc)| {
+            format!(
                 "{{\"internal_name\":{},\"description\":{}}}",
                 json_string(key),
                 json_string(desc),
@@ -486,16 +4040,372 @@ fn json_services_data() -> String {
         })
         .collect::<Vec<_>>()
         .join(",");
-    format!("{{\"services\":[{}]}}", services)
+    format!("{{\"services\":[{}]}}", services)
+}
+
+fn json_schema_digests(digests: &[SchemaDigest]) -> String {
+    let rows: Vec<String> = digests
+        .iter()
+        .map(|d| {
+            format!(
+                "{{\"source\":{},\"path\":{},\"digest\":{}}}",
+                json_string(d.source.as_str()),
+                json_string(&d.path.display().to_string()),
+                d.digest.as_deref().map_or("null".to_string(), json_string),
+            )
+        })
+        .collect();
+    format!("{{\"digests\":[{}]}}", rows.join(","))
+}
+
+/// `--verify-schema` without a subcommand: the expected digest, then each
+/// targeted DB's with whether it matches
+fn print_schema_comparison(expected: &str, digests: &[SchemaDigest]) {
+    println!("expected  {}", expected.trim().to_lowercase());
+    for d in digests {
+        let matches = d
+            .digest
+            .as_deref()
+            .is_some_and(|digest| digest.eq_ignore_ascii_case(expected.trim()));
+        println!(
+            "{:<8}  {}  {}  {}",
+            d.source.as_str(),
+            d.digest.as_deref().unwrap_or("none"),
+            if matches { "ok" } else { "mismatch" },
+            d.path.display()
+        );
+    }
+}
+
+fn json_paths_data(db: &TccDb) -> String {
+    let paths = db
+        .target_paths(Op::Read)
+        .into_iter()
+        .map(|(path, source, _)| {
+            format!(
+                "{{\"source\":{},\"path\":{}}}",
+                json_string(source.as_str()),
+                json_string(&path.display().to_string()),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{\"paths\":[{}]}}", paths)
+}
+
+fn print_doctor(checks: &[DoctorCheck]) {
+    for check in checks {
+        let tag = match check.status {
+            CheckStatus::Ok => "[ok]  ".green(),
+            CheckStatus::Warn => "[warn]".yellow(),
+            CheckStatus::Fail => "[fail]".red().bold(),
+        };
+        println!("{} {}: {}", tag, check.label, check.detail);
+        if let Some(fix) = &check.remediation {
+            println!("       {}", fix.dimmed());
+        }
+    }
+}
+
+fn print_doctor_fixes(fixes: &[DoctorFix]) {
+    if fixes.is_empty() {
+        println!("{}", "No fixes to apply.".dimmed());
+    }
+    for fix in fixes {
+        let (tag, detail) = match &fix.result {
+            Ok(detail) => ("[ok]  ".green(), detail),
+            Err(detail) => ("[fail]".red().bold(), detail),
+        };
+        match fix.source {
+            Some(source) => println!("{} {} ({} DB): {}", tag, fix.id, source.as_str(), detail),
+            None => println!("{} {}: {}", tag, fix.id, detail),
+        }
+    }
+    println!();
+}
+
+fn json_doctor_fixes(fixes: &[DoctorFix]) -> String {
+    let fixes = fixes
+        .iter()
+        .map(|fix| {
+            let (ok, detail) = match &fix.result {
+                Ok(detail) => (true, detail),
+                Err(detail) => (false, detail),
+            };
+            format!(
+                "{{\"id\":{},\"source\":{},\"ok\":{},\"detail\":{}}}",
+                json_string(fix.id),
+                fix.source
+                    .map_or("null".to_string(), |s| json_string(s.as_str())),
+                ok,
+                json_string(detail),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("[{}]", fixes)
+}
+
+fn json_doctor_data(checks: &[DoctorCheck]) -> String {
+    let worst = checks
+        .iter()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(CheckStatus::Ok);
+    let checks_json = checks
+        .iter()
+        .map(|check| {
+            format!(
+                "{{\"id\":{},\"label\":{},\"status\":{},\"detail\":{},\"remediation\":{}}}",
+                json_string(check.id),
+                json_string(check.label),
+                json_string(check.status.as_str()),
+                json_string(&check.detail),
+                check
+                    .remediation
+                    .as_deref()
+                    .map_or("null".to_string(), json_string),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"status\":{},\"checks\":[{}]}}",
+        json_string(worst.as_str()),
+        checks_json
+    )
+}
+
+fn json_self_test_data(checks: &[SelfTestCheck]) -> String {
+    let checks_json = checks
+        .iter()
+        .map(|check| {
+            let error = match &check.result {
+                Ok(()) => "null".to_string(),
+                Err(e) => json_string(e),
+            };
+            format!(
+                "{{\"name\":{},\"ok\":{},\"error\":{}}}",
+                json_string(check.name),
+                check.result.is_ok(),
+                error,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"passed\":{},\"checks\":[{}]}}",
+        checks.iter().all(|c| c.result.is_ok()),
+        checks_json
+    )
 }
 
-fn json_info_data(lines: &[String]) -> String {
-    let lines_json = lines
+fn json_info_data(
+    generated_at: &str,
+    report: &InfoReport,
+    access: Option<&[ServiceAccess]>,
+    families: Option<&[(ServiceFamily, usize)]>,
+) -> String {
+    let lines_json = report
+        .lines
         .iter()
         .map(|line| json_string(line))
         .collect::<Vec<_>>()
         .join(",");
-    format!("{{\"lines\":[{}]}}", lines_json)
+    let optional_bytes = |bytes: Option<u64>| bytes.map_or("null".to_string(), |n| n.to_string());
+    let journals_json = report
+        .journals
+        .iter()
+        .map(|j| {
+            format!(
+                "{{\"db\":{},\"path\":{},\"journal_mode\":{},\"wal_bytes\":{},\"shm_bytes\":{}}}",
+                json_string(if j.is_system { "system" } else { "user" }),
+                json_string(&j.path.display().to_string()),
+                j.journal_mode
+                    .as_deref()
+                    .map_or("null".to_string(), json_string),
+                optional_bytes(j.wal_bytes),
+                optional_bytes(j.shm_bytes),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let access_json = access.map_or(String::new(), |access| {
+        let rows: Vec<String> = access
+            .iter()
+            .map(|a| {
+                format!(
+                    "{{\"service\":{},\"service_raw\":{},\"db\":{},\"path\":{},\"writable\":{},\"blocked_by\":{}}}",
+                    json_string(a.service.display_name()),
+                    json_string(a.service.key()),
+                    json_string(if a.is_system { "system" } else { "user" }),
+                    json_string(&a.path.display().to_string()),
+                    a.blocked_by.is_none(),
+                    a.blocked_by.map_or("null".to_string(), json_string),
+                )
+            })
+            .collect();
+        format!(",\"permissions\":[{}]", rows.join(","))
+    });
+    let families_json = families.map_or(String::new(), |families| {
+        let fields: Vec<String> = families
+            .iter()
+            .map(|(family, n)| format!("{}:{}", json_string(family.as_str()), n))
+            .collect();
+        format!(",\"families\":{{{}}}", fields.join(","))
+    });
+    format!(
+        "{{\"generated_at\":{},\"lines\":[{}],\"journals\":[{}]{}{}}}",
+        json_string(generated_at),
+        lines_json,
+        journals_json,
+        access_json,
+        families_json
+    )
+}
+
+/// The current time as RFC 3339 to the second, in UTC (`Z`) or with the
+/// local offset
+fn rfc3339_now(utc: bool) -> String {
+    if utc {
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    } else {
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+    }
+}
+
+/// `  Devices        3`, one line per family, counts aligned
+fn print_family_counts(families: &[(ServiceFamily, usize)]) {
+    let label_w = families
+        .iter()
+        .map(|(f, _)| f.label().len())
+        .max()
+        .unwrap_or(0);
+    for (family, n) in families {
+        println!("  {:<label_w$}  {}", family.label(), n);
+    }
+}
+
+/// One client's entries in one DB, for `list --app-centric`
+#[derive(Debug, PartialEq)]
+struct AppPermissions {
+    client: String,
+    source: SourceLabel,
+    /// `(service display name, auth_values)`, sorted by service. A service
+    /// with several rows (one per Automation target or client type) keeps
+    /// each distinct value, sorted.
+    permissions: Vec<(String, Vec<i32>)>,
+}
+
+/// Pivot entries into one record per client and DB, sorted by client with
+/// the user DB first. Records are keyed on the full client, then shown as
+/// `view` shortens it.
+fn pivot_by_app(entries: &[TccEntry], view: ClientView) -> Vec<AppPermissions> {
+    type Cells = BTreeMap<String, BTreeSet<i32>>;
+    let mut apps: BTreeMap<(String, &str, bool), Cells> = BTreeMap::new();
+    for e in entries {
+        apps.entry((display_client(&e.client, view), &e.client, e.is_system))
+            .or_default()
+            .entry(e.service_display.clone())
+            .or_default()
+            .insert(e.auth_value);
+    }
+    apps.into_iter()
+        .map(|((client, _, is_system), permissions)| AppPermissions {
+            client,
+            source: if is_system {
+                SourceLabel::System
+            } else {
+                SourceLabel::User
+            },
+            permissions: permissions
+                .into_iter()
+                .map(|(service, values)| (service, values.into_iter().collect()))
+                .collect(),
+        })
+        .collect()
+}
+
+/// `{"count":N,"apps":[{"client","source","permissions":{service: status}}]}`;
+/// a service whose rows disagree maps to an array of statuses instead
+fn json_app_centric(apps: &[AppPermissions]) -> String {
+    let items: Vec<String> = apps
+        .iter()
+        .map(|app| {
+            let permissions: Vec<String> = app
+                .permissions
+                .iter()
+                .map(|(service, values)| {
+                    let statuses: Vec<String> = values
+                        .iter()
+                        .map(|auth| json_string(&auth_value_display(*auth)))
+                        .collect();
+                    match statuses.as_slice() {
+                        [status] => format!("{}:{}", json_string(service), status),
+                        _ => format!("{}:[{}]", json_string(service), statuses.join(",")),
+                    }
+                })
+                .collect();
+            format!(
+                "{{\"client\":{},\"source\":{},\"permissions\":{{{}}}}}",
+                json_string(&app.client),
+                json_string(app.source.as_str()),
+                permissions.join(",")
+            )
+        })
+        .collect();
+    format!(
+        "{{\"count\":{},\"apps\":[{}]}}",
+        apps.len(),
+        items.join(",")
+    )
+}
+
+/// A `client (user DB)` header per app, then its services and statuses
+fn print_app_centric(apps: &[AppPermissions], glyphs: &Glyphs, scheme: ColorScheme) {
+    for (i, app) in apps.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{} ({} DB)", app.client.bold(), app.source.as_str());
+        let name_w = app
+            .permissions
+            .iter()
+            .map(|(service, _)| service.len())
+            .max()
+            .unwrap_or(0);
+        for (service, values) in &app.permissions {
+            let statuses: Vec<String> = values
+                .iter()
+                .map(|auth| colored_status(*auth, scheme, glyphs))
+                .collect();
+            println!("  {:<name_w$}  {}", service, statuses.join(" / "));
+        }
+    }
+}
+
+/// `Camera → user DB → writable`, one line per service, names aligned
+fn print_service_access(access: &[ServiceAccess], glyphs: &Glyphs) {
+    let name_w = access
+        .iter()
+        .map(|a| a.service.display_name().len())
+        .max()
+        .unwrap_or(0);
+    for a in access {
+        let db = if a.is_system { "system DB" } else { "user DB" };
+        let state = match a.blocked_by {
+            None => "writable".green().to_string(),
+            Some(reason) => format!("NOT writable ({})", reason).red().to_string(),
+        };
+        println!(
+            "{:<name_w$} {} {:<9} {} {}",
+            a.service.display_name(),
+            glyphs.arrow,
+            db,
+            glyphs.arrow,
+            state,
+        );
+    }
 }
 
 fn run_command(result: Result<String, TccError>) {
@@ -503,223 +4413,1604 @@ fn run_command(result: Result<String, TccError>) {
         Ok(msg) => println!("{}", msg.green()),
         Err(e) => {
             eprintln!("{}: {}", "Error".red().bold(), e);
-            process::exit(1);
+            exit(1);
+        }
+    }
+}
+
+/// A raw access row as `{"source":..., "row":{column: value, ...}}`, with
+/// BLOB columns (such as csreq) in base64: the form `dump` prints and
+/// `restore` reads back, also used for deleted rows
+fn json_raw_row(row: &RawRow) -> String {
+    let columns: Vec<String> = row
+        .columns
+        .iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), json_sql_value(value)))
+        .collect();
+    format!(
+        "{{\"source\":{},\"row\":{{{}}}}}",
+        json_string(row.source.as_str()),
+        columns.join(",")
+    )
+}
+
+fn json_sql_value(value: &rusqlite::types::Value) -> String {
+    use rusqlite::types::Value;
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Real(f) if f.is_finite() => f.to_string(),
+        Value::Real(_) => "null".to_string(),
+        Value::Text(s) => json_string(s),
+        Value::Blob(b) => json_string(&base64(b)),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// An entry's csreq as a base64 JSON string, or `null`
+fn json_csreq(entry: &TccEntry) -> String {
+    entry
+        .csreq
+        .as_deref()
+        .map_or("null".to_string(), |b| json_string(&base64(b)))
+}
+
+/// Add `"changed":N`, the rows a write touched (0 for a no-op), to a JSON
+/// object built by one of the `json_*_data` helpers
+fn json_with_changed(mut data: String, changed: usize) -> String {
+    data.pop();
+    data.push_str(&format!(",\"changed\":{}}}", changed));
+    data
+}
+
+/// Add `"total_deleted":N` for resets that clear every entry of a service
+/// or family
+fn json_with_total_deleted(mut data: String, deleted: usize) -> String {
+    data.pop();
+    data.push_str(&format!(",\"total_deleted\":{}}}", deleted));
+    data
+}
+
+/// Add `"<key>":[...deleted rows...]` to a JSON object built by one of the
+/// `json_*_data` helpers
+fn json_with_deleted(mut data: String, key: &str, rows: &[RawRow]) -> String {
+    let rows: Vec<String> = rows.iter().map(json_raw_row).collect();
+    data.pop();
+    data.push_str(&format!(",{}:[{}]}}", json_string(key), rows.join(",")));
+    data
+}
+
+/// `--show-deleted` line for one row, e.g.
+/// `user: service=kTCCServiceCamera client=com.foo ... csreq=X'fade0c00'`
+fn deleted_row_line(row: &RawRow) -> String {
+    let columns: Vec<String> = row
+        .columns
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, sql_value_text(value)))
+        .collect();
+    format!("{}: {}", row.source.as_str(), columns.join(" "))
+}
+
+/// A column value as SQLite would print it, with BLOBs as `X'..'` literals
+fn sql_value_text(value: &rusqlite::types::Value) -> String {
+    use rusqlite::types::Value;
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(s) => s.clone(),
+        Value::Blob(b) => format!("X'{}'", hex(b)),
+    }
+}
+
+/// `--verbose-diff` line for one changed column, e.g. `auth_value: 0 → 2`,
+/// with `(none)` on the side where the row did not exist
+fn column_change_line(change: &ColumnChange) -> String {
+    let side = |value: &Option<rusqlite::types::Value>| {
+        value.as_ref().map_or("(none)".to_string(), sql_value_text)
+    };
+    format!(
+        "{}: {} → {}",
+        change.column,
+        side(&change.before),
+        side(&change.after)
+    )
+}
+
+/// Print the columns a grant/revoke changed, for `--verbose-diff`
+fn print_column_changes(changes: &[ColumnChange]) {
+    if changes.is_empty() {
+        println!("No columns changed");
+        return;
+    }
+    println!("Changed columns:");
+    for change in changes {
+        println!("  {}", column_change_line(change));
+    }
+}
+
+/// Add `"diff":[{"column":..., "before":..., "after":...}]` to a JSON object
+/// built by one of the `json_*_data` helpers. A side where the row did not
+/// exist is `null`.
+fn json_with_diff(mut data: String, changes: &[ColumnChange]) -> String {
+    let side = |value: &Option<rusqlite::types::Value>| {
+        value.as_ref().map_or("null".to_string(), json_sql_value)
+    };
+    let changes: Vec<String> = changes
+        .iter()
+        .map(|c| {
+            format!(
+                "{{\"column\":{},\"before\":{},\"after\":{}}}",
+                json_string(c.column),
+                side(&c.before),
+                side(&c.after)
+            )
+        })
+        .collect();
+    data.pop();
+    data.push_str(&format!(",\"diff\":[{}]}}", changes.join(",")));
+    data
+}
+
+/// Print the rows a revoke/reset removed, for `--show-deleted`
+fn print_deleted_rows(rows: &[RawRow]) {
+    if rows.is_empty() {
+        return;
+    }
+    println!("Deleted rows:");
+    for row in rows {
+        println!("  {}", deleted_row_line(row));
+    }
+}
+
+/// The one-line `--summary-only` report for revoke/reset
+fn delete_summary(deleted: usize, dbs: usize) -> String {
+    format!(
+        "Deleted {} {} across {} {}",
+        deleted,
+        if deleted == 1 { "entry" } else { "entries" },
+        dbs,
+        if dbs == 1 { "DB" } else { "DBs" }
+    )
+}
+
+/// `reset --family`: delete the family's entries and report per service
+fn reset_family(
+    db: &TccDb,
+    family: ServiceFamily,
+    json_mode: bool,
+    json_out: JsonOutput,
+    summary_only: bool,
+    show_deleted: bool,
+) {
+    let result = db.reset_family(family);
+    report_warnings(db.take_warnings(), json_mode);
+    if json_mode {
+        match result {
+            // `deleted` is already the count here, so the rows get their own key
+            Ok(outcome) => {
+                outcome.warnings.iter().cloned().for_each(hold_warning);
+                let data = json_with_total_deleted(
+                    json_with_changed(json_family_reset_data(family, &outcome), outcome.deleted),
+                    outcome.deleted,
+                );
+                emit_json_message(
+                    json_out,
+                    "reset",
+                    &outcome.to_string(),
+                    json_with_deleted(data, "deleted_rows", &outcome.deleted_rows),
+                )
+            }
+            Err(e) => {
+                emit_json_tcc_error(json_out, "reset", &e);
+                exit(1);
+            }
         }
+    } else if summary_only {
+        run_command(result.map(|outcome| {
+            for warning in &outcome.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            delete_summary(outcome.deleted, outcome.dbs)
+        }));
+    } else {
+        run_command(result.map(|outcome| {
+            if show_deleted {
+                print_deleted_rows(&outcome.deleted_rows);
+            }
+            outcome.to_string()
+        }));
+    }
+}
+
+fn json_family_reset_data(family: ServiceFamily, outcome: &ResetOutcome) -> String {
+    let services: Vec<String> = outcome
+        .per_service
+        .iter()
+        .map(|(svc, n)| {
+            format!(
+                "{{\"service\":{},\"service_raw\":{},\"deleted\":{}}}",
+                json_string(svc.display_name()),
+                json_string(svc.key()),
+                n
+            )
+        })
+        .collect();
+    format!(
+        "{{\"message\":{},\"family\":{},\"deleted\":{},\"services\":[{}]}}",
+        json_string(&outcome.to_string()),
+        json_string(family.as_str()),
+        outcome.deleted,
+        services.join(",")
+    )
+}
+
+/// Use the explicit client argument, or resolve it from `--pid` or `--client-file`
+fn resolve_client(
+    client_path: Option<String>,
+    pid: Option<i32>,
+    client_file: Option<PathBuf>,
+) -> Result<String, TccError> {
+    match (client_path, pid, client_file) {
+        (Some(client), _, _) => Ok(client),
+        (None, Some(pid), _) => tcc::resolve_pid_client(pid),
+        (None, None, Some(path)) => tcc::read_client_file(&path),
+        (None, None, None) => unreachable!("clap requires a client, --pid or --client-file"),
+    }
+}
+
+/// Global flags that shape how a `TccDb` is constructed
+struct DbOptions {
+    home: Option<PathBuf>,
+    db: Option<PathBuf>,
+    allow_missing_db: bool,
+    follow_symlinks: bool,
+    assume_schema: Option<AssumeSchema>,
+    lock_retries: u32,
+}
+
+thread_local! {
+    /// The private copy of stdin behind `--db -`, kept for the whole run
+    static STDIN_DB: RefCell<Option<tempfile::TempDir>> = const { RefCell::new(None) };
+}
+
+/// Copy stdin into a fresh temp dir and return the read-only DB file in it;
+/// `remove_stdin_db` deletes it again
+fn copy_stdin_db() -> Result<PathBuf, TccError> {
+    let failed = |e: io::Error| TccError::DbOpen {
+        path: PathBuf::from("<stdin>"),
+        source: e.to_string(),
+    };
+    let dir = tempfile::Builder::new()
+        .prefix("tccutil-rs-stdin-")
+        .tempdir()
+        .map_err(failed)?;
+    let path = dir.path().join("TCC.db");
+    // Hand the dir over first so it is cleaned up even if the copy fails
+    STDIN_DB.set(Some(dir));
+    let mut file = fs::File::create(&path).map_err(failed)?;
+    let copied = io::copy(&mut io::stdin().lock(), &mut file).map_err(failed)?;
+    if copied == 0 {
+        return Err(failed(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "stdin was empty",
+        )));
     }
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).map_err(failed)?;
+    Ok(path)
+}
+
+/// Delete the stdin copy behind `--db -`, if one was made
+fn remove_stdin_db() {
+    STDIN_DB.take();
+}
+
+/// `process::exit` runs no destructors, so every exit goes through here to
+/// remove the stdin copy first
+fn exit(code: i32) -> ! {
+    remove_stdin_db();
+    process::exit(code)
 }
 
-fn make_db(target: DbTarget, suppress_warnings: bool) -> Result<TccDb, TccError> {
-    let mut db = TccDb::new(target)?;
-    db.set_suppress_warnings(suppress_warnings);
+/// `clap::Error::exit`, removing the stdin copy first
+fn exit_with(err: clap::Error) -> ! {
+    remove_stdin_db();
+    err.exit()
+}
+
+fn make_db(opts: &DbOptions, target: DbTarget) -> Result<TccDb, TccError> {
+    let mut db = match (&opts.db, &opts.home) {
+        (Some(path), _) => TccDb::with_file(path.clone()),
+        (None, Some(home)) => TccDb::with_home(target, home),
+        (None, None) => TccDb::new(target)?,
+    };
+    if opts.follow_symlinks {
+        db.resolve_symlinks();
+    }
+    // Only an explicit --home or --db is checked; the default paths may legitimately be absent
+    db.set_require_user_db((opts.home.is_some() || opts.db.is_some()) && !opts.allow_missing_db);
+    db.set_lock_retries(opts.lock_retries);
+    db.set_schema_policy(
+        opts.assume_schema
+            .map_or(SchemaPolicy::Warn, SchemaPolicy::from),
+    );
     Ok(db)
 }
 
+/// The command line, and whether it is a schema-only run: `--hash-schema`
+/// and `--verify-schema` may be given without a subcommand. Such a run is
+/// parsed with `paths` in its place, which never runs.
+fn parse_cli() -> Result<(Cli, bool), clap::Error> {
+    let args: Vec<OsString> = env::args_os().collect();
+    let matches = Cli::command()
+        .subcommand_required(false)
+        .try_get_matches_from(&args)?;
+    if matches.subcommand().is_some() {
+        return Cli::from_arg_matches(&matches)
+            .map(|cli| (cli, false))
+            .map_err(|e| e.format(&mut Cli::command()));
+    }
+    if !matches.get_flag("hash_schema") && !matches.contains_id("verify_schema") {
+        return Err(Cli::command().error(
+            ErrorKind::MissingSubcommand,
+            "a subcommand is required unless --hash-schema or --verify-schema is given",
+        ));
+    }
+    Cli::try_parse_from(args.into_iter().chain([OsString::from("paths")])).map(|cli| (cli, true))
+}
+
 fn wants_json_from_args() -> bool {
     env::args().any(|arg| arg == "--json" || arg == "-j")
 }
 
 fn main() {
+    run();
+    remove_stdin_db();
+}
+
+fn run() {
     let json_requested = wants_json_from_args();
-    let cli = match Cli::try_parse() {
-        Ok(cli) => cli,
+    let (cli, schema_only) = match parse_cli() {
+        Ok(parsed) => parsed,
         Err(err) => {
             if json_requested {
-                emit_json_error("parse", "ParseError", err.to_string());
-                process::exit(1);
+                emit_json_error(
+                    JsonOutput::default(),
+                    "parse",
+                    "ParseError",
+                    err.to_string(),
+                );
+                exit(1);
             }
-            err.exit();
+            exit_with(err);
         }
     };
 
-    let target = if cli.user {
+    let target = if cli.db.is_some() {
+        DbTarget::File
+    } else if cli.user {
         DbTarget::User
     } else {
         DbTarget::Default
     };
-    let json_mode = cli.json;
+    let json_mode = cli.json || cli.csv;
+    let csv = cli.csv;
+    let json_out = JsonOutput {
+        csv,
+        strict: cli.strict_json,
+    };
+    let glyphs = Glyphs::for_mode(cli.ascii);
+    let scheme = cli.color_scheme;
+    let machine_output = json_mode
+        || matches!(
+            &cli.command,
+            Commands::List(args) if args.json_stream.is_some() || args.tabular_json
+        );
+    if let Some(enabled) = color_override(
+        cli.color,
+        cli.no_color,
+        env::var_os("NO_COLOR").as_deref(),
+        machine_output,
+    ) {
+        colored::control::set_override(enabled);
+    }
+    if let Err(err) = check_stdin_db(cli.db.as_deref(), &cli.command) {
+        if json_mode {
+            emit_json_error(json_out, "parse", "ParseError", err.to_string());
+            exit(1);
+        }
+        exit_with(err);
+    }
+    if let Err(err) = check_csv_command(csv, schema_only || cli.hash_schema, &cli.command) {
+        exit_with(err);
+    }
+    let db = match cli.db {
+        Some(path) if path == Path::new("-") => match copy_stdin_db() {
+            Ok(copy) => Some(copy),
+            Err(e) => {
+                if json_mode {
+                    emit_json_tcc_error(json_out, "open", &e);
+                } else {
+                    eprintln!("{}: {}", "Error".red().bold(), e);
+                }
+                exit(1);
+            }
+        },
+        db => db,
+    };
+    let db_opts = DbOptions {
+        home: cli.home,
+        db,
+        allow_missing_db: cli.allow_missing_db,
+        follow_symlinks: cli.follow_symlinks,
+        assume_schema: cli.assume_schema,
+        lock_retries: cli.retry,
+    };
+
+    if cli.hash_schema || cli.verify_schema.is_some() {
+        let db = match make_db(&db_opts, target) {
+            Ok(db) => db,
+            Err(e) => {
+                if json_mode {
+                    emit_json_tcc_error(json_out, "verify-schema", &e);
+                } else {
+                    eprintln!("{}: {}", "Error".red().bold(), e);
+                }
+                exit(1);
+            }
+        };
+        if cli.hash_schema {
+            let digests = db.schema_digests();
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                emit_json_success(json_out, "hash-schema", json_schema_digests(&digests));
+            } else {
+                for d in &digests {
+                    println!(
+                        "{:<6}  {}  {}",
+                        d.source.as_str(),
+                        d.digest.as_deref().unwrap_or("none"),
+                        d.path.display()
+                    );
+                }
+            }
+            exit(if digests.is_empty() { 1 } else { 0 });
+        }
+        let verified = cli.verify_schema.as_deref().map(|d| db.verify_schema(d));
+        // On its own, the run's output is the comparison, match or not
+        let compared = schema_only.then(|| db.schema_digests());
+        report_warnings(db.take_warnings(), json_mode);
+        if let (Some(expected), Some(digests)) = (&cli.verify_schema, &compared)
+            && !json_mode
+        {
+            print_schema_comparison(expected, digests);
+        }
+        match verified {
+            Some(Err(e)) => {
+                if json_mode {
+                    emit_json_tcc_error(json_out, "verify-schema", &e);
+                } else {
+                    eprintln!("{}: {}", "Error".red().bold(), e);
+                }
+                exit(1);
+            }
+            Some(Ok(_)) if schema_only => {
+                if let (Some(expected), Some(digests)) = (&cli.verify_schema, &compared)
+                    && json_mode
+                {
+                    let mut data = json_schema_digests(digests);
+                    data.pop();
+                    data.push_str(&format!(",\"expected\":{}}}", json_string(expected)));
+                    emit_json_success(json_out, "verify-schema", data);
+                }
+                exit(0);
+            }
+            _ => {}
+        }
+    }
 
     match cli.command {
-        Commands::List {
-            client,
+        Commands::List(args) => {
+            let ListArgs {
+                client,
+                service,
+                service_key,
+                client_regex,
+                service_regex,
+                only,
+                only_modifiable,
+                template,
+                no_footer,
+                footer_format,
+                since,
+                until,
+                only_modified,
+                source,
+                auth,
+                since_boot,
+                measure,
+                compact,
+                compact_depth,
+                truncate_client,
+                wide,
+                newer_than,
+                no_sort,
+                sort,
+                reverse,
+                dedupe,
+                no_dedupe: _,
+                include_tcc_internal: _,
+                no_tcc_internal,
+                redact,
+                json_stream,
+                distinct_clients,
+                app_centric,
+                tabular_json,
+                checksum,
+                show_csreq,
+            } = *args;
+            let json_mode = json_mode || json_stream.is_some() || tabular_json;
+            let csv_check = check_csv_list(
+                csv,
+                &[
+                    ("--json-stream", json_stream.is_some()),
+                    ("--tabular-json", tabular_json),
+                    ("--template", template.is_some()),
+                    ("--newer-than", newer_than.is_some()),
+                    ("--distinct-clients", distinct_clients),
+                    ("--app-centric", app_centric),
+                    ("--checksum", checksum),
+                    ("--show-csreq", show_csreq.is_some()),
+                ],
+            );
+            if let Err(err) = check_source_target(source, target)
+                .and(csv_check)
+                .and(check_time_window(since, until))
+            {
+                if json_mode {
+                    emit_json_error(json_out, "parse", "ParseError", err.to_string());
+                    exit(1);
+                }
+                exit_with(err);
+            }
+            let view = ClientView {
+                compact: compact.then_some(compact_depth as usize),
+                truncate: truncate_client.map(|n| n as usize),
+                csreq: show_csreq,
+            };
+            // Resolve the whole --only set before reading, reporting every bad name
+            let mut only_services = Vec::new();
+            let mut errors = Vec::new();
+            for name in &only {
+                match Service::try_from(name.as_str()) {
+                    Ok(s) => only_services.push(s),
+                    Err(e) => errors.push(e),
+                }
+            }
+            if let Some(first) = errors.first() {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                if json_mode {
+                    emit_json_error(json_out, "list", error_kind(first), messages.join("; "));
+                } else {
+                    for message in &messages {
+                        eprintln!("{}: {}", "Error".red().bold(), message);
+                    }
+                }
+                exit(1);
+            }
+            let boot_uuid = if since_boot {
+                let Some(uuid) = tcc::current_boot_uuid() else {
+                    let msg =
+                        "Could not read the current boot session (sysctl kern.bootsessionuuid)";
+                    if json_mode {
+                        emit_json_error(json_out, "list", "BootSessionUnknown", msg.to_string());
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), msg);
+                    }
+                    exit(1);
+                };
+                Some(uuid)
+            } else {
+                None
+            };
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "list", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+
+            let filter = ListFilter {
+                client: client.as_deref(),
+                service: service.as_deref(),
+                service_key: service_key.as_deref(),
+                client_regex: client_regex.as_ref(),
+                service_regex: service_regex.as_ref(),
+                only: (!only.is_empty()).then_some(only_services.as_slice()),
+                only_modifiable,
+                since,
+                until,
+                only_modified,
+                boot_uuid: boot_uuid.as_deref(),
+                hide_tcc_internal: no_tcc_internal,
+                source: source.label(),
+                auth: auth.map(AuthStatus::from),
+                read_order: no_sort,
+                sort: sort.into(),
+                reverse,
+            };
+            // In read order, with nothing that needs every row first, each
+            // entry is written as it is read
+            if let Some(framing) = json_stream
+                && no_sort
+                && !dedupe
+                && !measure
+            {
+                let mut entries = match db.list_iter(&filter) {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        emit_json_tcc_error(json_out, "list", &e);
+                        exit(1);
+                    }
+                };
+                let redactor = redact.then(Redactor::new);
+                let redacted = entries.by_ref().map(|mut entry| {
+                    if let Some(redactor) = &redactor {
+                        redactor.redact_entries(std::slice::from_mut(&mut entry));
+                    }
+                    entry
+                });
+                let written =
+                    write_json_stream(&mut io::stdout().lock(), redacted, view, framing, json_out);
+                for warning in take_held_warnings()
+                    .into_iter()
+                    .chain(entries.take_warnings())
+                {
+                    eprintln!("Warning: {}", warning);
+                }
+                if let Err(e) = written {
+                    eprintln!("{}: {}", "Error".red().bold(), e);
+                    exit(1);
+                }
+                return;
+            }
+            // Taken before reading so a follow-up `--since` misses nothing
+            let generated_at = chrono::Utc::now().timestamp();
+            match db.list_measured(&filter) {
+                Ok(MeasuredList {
+                    mut entries,
+                    failures,
+                    counts,
+                    warnings,
+                }) => {
+                    report_warnings(warnings, json_mode);
+                    // Only the envelope carries them as diagnostics; CSV rows and streams do not
+                    if !json_mode || json_stream.is_some() || csv {
+                        for failure in &failures {
+                            eprintln!("Warning: {}", failure.message);
+                        }
+                    }
+                    let deduped = if dedupe {
+                        dedupe_entries(&mut entries)
+                    } else {
+                        0
+                    };
+                    if json_mode && deduped > 0 {
+                        hold_warning(dedupe_note(deduped));
+                    }
+                    // Keyed on the real clients, so before any redaction
+                    let other_ts = match &newer_than {
+                        Some(path) => match TccDb::read_other_db(path, db_opts.allow_missing_db) {
+                            Ok(other) => Some(retain_newer_than(&mut entries, &other)),
+                            Err(e) => {
+                                if json_mode {
+                                    emit_json_tcc_error(json_out, "list", &e);
+                                } else {
+                                    eprintln!("{}: {}", "Error".red().bold(), e);
+                                }
+                                exit(1);
+                            }
+                        },
+                        None => None,
+                    };
+                    // Over the real clients: redaction is salted per run
+                    let checksum = checksum.then(|| entries_checksum(&entries));
+                    if redact {
+                        Redactor::new().redact_entries(&mut entries);
+                    }
+                    // Streams have no envelope to carry the counts, so they go to stderr
+                    if measure && (!json_mode || json_stream.is_some() || csv) {
+                        eprintln!("{}", measure_summary(&counts, entries.len()));
+                    }
+                    if distinct_clients {
+                        let clients: BTreeSet<String> = entries
+                            .iter()
+                            .map(|e| display_client(&e.client, view))
+                            .collect();
+                        if json_mode {
+                            let clients: Vec<String> =
+                                clients.iter().map(|c| json_string(c)).collect();
+                            emit_json_success(
+                                json_out,
+                                "list",
+                                format!(
+                                    "{{\"count\":{},\"clients\":[{}]}}",
+                                    clients.len(),
+                                    clients.join(",")
+                                ),
+                            );
+                        } else {
+                            for client in &clients {
+                                println!("{}", client);
+                            }
+                        }
+                    } else if app_centric {
+                        let apps = pivot_by_app(&entries, view);
+                        if json_mode {
+                            let mut data = json_app_centric(&apps);
+                            if let Some(checksum) = &checksum {
+                                data.pop();
+                                data.push_str(&format!(
+                                    ",\"checksum\":{}}}",
+                                    json_string(checksum)
+                                ));
+                            }
+                            emit_json_success(json_out, "list", data);
+                        } else {
+                            print_app_centric(&apps, glyphs, scheme);
+                        }
+                    } else if csv {
+                        print!("{}\r\n", CSV_LIST_HEADER);
+                        for entry in &entries {
+                            print!("{}", csv_entry(entry, view));
+                        }
+                        // Rows only, so warnings go to stderr as in text mode
+                        for warning in take_held_warnings() {
+                            eprintln!("Warning: {}", warning);
+                        }
+                    } else if let Some(framing) = json_stream {
+                        let stdout = io::stdout();
+                        if let Err(e) =
+                            write_json_stream(&mut stdout.lock(), &entries, view, framing, json_out)
+                        {
+                            eprintln!("{}: {}", "Error".red().bold(), e);
+                            exit(1);
+                        }
+                        // No envelope to hold them either
+                        for warning in take_held_warnings() {
+                            eprintln!("Warning: {}", warning);
+                        }
+                    } else if json_mode {
+                        let mut data = json_list_data(
+                            &entries,
+                            view,
+                            &failures,
+                            generated_at,
+                            measure.then_some(counts.as_slice()),
+                            other_ts.as_deref(),
+                            tabular_json,
+                        );
+                        if let Some(checksum) = &checksum {
+                            data.pop();
+                            data.push_str(&format!(",\"checksum\":{}}}", json_string(checksum)));
+                        }
+                        emit_json_success(json_out, "list", data);
+                    } else if let Some(template) = &template {
+                        for entry in &entries {
+                            println!("{}", template.render(entry, view));
+                        }
+                    } else if let Some(other_ts) = &other_ts {
+                        print_newer_entries(&entries, other_ts, view, glyphs);
+                    } else {
+                        let footer = footer_format.unwrap_or_default();
+                        print_entries(
+                            &entries,
+                            view,
+                            glyphs,
+                            scheme,
+                            wide,
+                            (!no_footer).then_some(&footer),
+                        );
+                        if deduped > 0 {
+                            println!("{}", dedupe_note(deduped).dimmed());
+                        }
+                    }
+                    if let Some(checksum) = &checksum
+                        && !json_mode
+                    {
+                        println!("Checksum: {}", checksum);
+                    }
+                }
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "list", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            }
+        }
+        Commands::Grant {
+            service,
+            client_path,
+            target_app,
+            pid,
+            client_file,
+            if_missing,
+            force,
+            merge_csreq_from_db,
+            verbose_diff,
+        } => {
+            if let Err(err) = check_target_app(&service, target_app.as_deref()) {
+                if json_mode {
+                    emit_json_error(json_out, "parse", "ParseError", err.to_string());
+                    exit(1);
+                }
+                exit_with(err);
+            }
+            let mut db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "grant", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+            db.set_merge_csreq(merge_csreq_from_db);
+            db.set_force_grant(force);
+            let result = resolve_client(client_path, pid, client_file).and_then(|client| {
+                let target_app = target_app.as_deref();
+                let before = if verbose_diff {
+                    db.read_access_row(&service, &client, target_app)
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                let (message, created, changed) = if if_missing {
+                    let (message, created) = db.grant_if_missing(&service, &client, target_app)?;
+                    // A created row is exactly one insert
+                    (message, Some(created), usize::from(created))
+                } else {
+                    let (message, changed) = db.grant_counted(&service, &client, target_app)?;
+                    (message, None, changed)
+                };
+                // Read back the row with DB defaults filled in
+                let row = if json_mode || verbose_diff {
+                    db.read_access_row(&service, &client, target_app)
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                let changes = verbose_diff.then(|| diff_access_rows(before.as_ref(), row.as_ref()));
+                Ok((message, row, created, changes, changed))
+            });
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                match result {
+                    Ok((message, row, created, changes, changed)) => {
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        let mut data = json_with_changed(
+                            json_write_data(&message, &service_raw, row.as_ref(), created),
+                            changed,
+                        );
+                        if let Some(changes) = &changes {
+                            data = json_with_diff(data, changes);
+                        }
+                        emit_json_message(json_out, "grant", &message, data)
+                    }
+                    Err(e) => {
+                        emit_json_tcc_error(json_out, "grant", &e);
+                        exit(1);
+                    }
+                }
+            } else {
+                run_command(result.map(|(message, _, _, changes, _)| {
+                    if let Some(changes) = &changes {
+                        print_column_changes(changes);
+                    }
+                    message
+                }));
+            }
+        }
+        Commands::Revoke {
             service,
-            compact,
+            client_path,
+            target_app,
+            pid,
+            client_file,
+            summary_only,
+            show_deleted,
+            verbose_diff,
         } => {
-            let db = match make_db(target, json_mode) {
+            if let Err(err) = check_target_app(&service, target_app.as_deref()) {
+                if json_mode {
+                    emit_json_error(json_out, "parse", "ParseError", err.to_string());
+                    exit(1);
+                }
+                exit_with(err);
+            }
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("list", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "revoke", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
-
-            match db.list(client.as_deref(), service.as_deref()) {
-                Ok(entries) => {
-                    if json_mode {
-                        emit_json_success("list", json_list_data(&entries, compact));
-                    } else {
-                        print_entries(&entries, compact);
+            let result = resolve_client(client_path, pid, client_file).and_then(|client| {
+                let target_app = target_app.as_deref();
+                // Capture the row before it is deleted
+                let row = if json_mode || verbose_diff {
+                    db.read_access_row(&service, &client, target_app)
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                let (message, deleted) = db.revoke_capturing(&service, &client, target_app)?;
+                let changes = if verbose_diff {
+                    let after = db
+                        .read_access_row(&service, &client, target_app)
+                        .ok()
+                        .flatten();
+                    Some(diff_access_rows(row.as_ref(), after.as_ref()))
+                } else {
+                    None
+                };
+                Ok((message, row, deleted, changes))
+            });
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                match result {
+                    Ok((message, row, deleted, changes)) => {
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        let mut data = json_with_deleted(
+                            json_with_changed(
+                                json_write_data(&message, &service_raw, row.as_ref(), None),
+                                deleted.len(),
+                            ),
+                            "deleted",
+                            &deleted,
+                        );
+                        if let Some(changes) = &changes {
+                            data = json_with_diff(data, changes);
+                        }
+                        emit_json_message(json_out, "revoke", &message, data)
+                    }
+                    Err(e) => {
+                        emit_json_tcc_error(json_out, "revoke", &e);
+                        exit(1);
                     }
                 }
+            } else {
+                run_command(result.map(|(message, _, deleted, changes)| {
+                    if summary_only {
+                        delete_summary(deleted.len(), 1)
+                    } else {
+                        if show_deleted {
+                            print_deleted_rows(&deleted);
+                        }
+                        if let Some(changes) = &changes {
+                            print_column_changes(changes);
+                        }
+                        message
+                    }
+                }));
+            }
+        }
+        Commands::Enable {
+            service,
+            client_path,
+            client_file,
+            all_clients,
+            yes: _,
+        } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("list", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "enable", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
+                }
+            };
+            let result = if all_clients {
+                db.set_all_clients(&service, "enable", 2)
+            } else {
+                resolve_client(client_path, None, client_file)
+                    .and_then(|client| db.enable_counted(&service, &client))
+            };
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                match result {
+                    Ok((message, changed)) => {
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        emit_json_message(
+                            json_out,
+                            "enable",
+                            &message,
+                            json_with_changed(json_message_data(&message, &service_raw), changed),
+                        )
+                    }
+                    Err(e) => {
+                        emit_json_tcc_error(json_out, "enable", &e);
+                        exit(1);
+                    }
                 }
+            } else {
+                run_command(result.map(|(message, _)| message));
             }
         }
-        Commands::Grant {
+        Commands::Disable {
             service,
             client_path,
+            client_file,
+            all_clients,
+            yes: _,
         } => {
-            let db = match make_db(target, json_mode) {
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("grant", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "disable", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
-            let result = db.grant(&service, &client_path);
+            let result = if all_clients {
+                db.set_all_clients(&service, "disable", 0)
+            } else {
+                resolve_client(client_path, None, client_file)
+                    .and_then(|client| db.disable_counted(&service, &client))
+            };
+            report_warnings(db.take_warnings(), json_mode);
             if json_mode {
                 match result {
-                    Ok(message) => emit_json_success("grant", json_message_data(&message)),
+                    Ok((message, changed)) => {
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        emit_json_message(
+                            json_out,
+                            "disable",
+                            &message,
+                            json_with_changed(json_message_data(&message, &service_raw), changed),
+                        )
+                    }
                     Err(e) => {
-                        emit_json_error("grant", error_kind(&e), e.to_string());
-                        process::exit(1);
+                        emit_json_tcc_error(json_out, "disable", &e);
+                        exit(1);
                     }
                 }
             } else {
-                run_command(result);
+                run_command(result.map(|(message, _)| message));
             }
         }
-        Commands::Revoke {
+        Commands::Toggle {
             service,
             client_path,
+            client_file,
         } => {
-            let db = match make_db(target, json_mode) {
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("revoke", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "toggle", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
-            let result = db.revoke(&service, &client_path);
+            let result = resolve_client(client_path, None, client_file)
+                .and_then(|client| db.toggle(&service, &client));
+            report_warnings(db.take_warnings(), json_mode);
             if json_mode {
                 match result {
-                    Ok(message) => emit_json_success("revoke", json_message_data(&message)),
+                    Ok((message, before, after)) => {
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        emit_json_message(
+                            json_out,
+                            "toggle",
+                            &message,
+                            json_toggle_data(&message, &service_raw, before, after),
+                        )
+                    }
                     Err(e) => {
-                        emit_json_error("revoke", error_kind(&e), e.to_string());
-                        process::exit(1);
+                        emit_json_tcc_error(json_out, "toggle", &e);
+                        exit(1);
                     }
                 }
             } else {
-                run_command(result);
+                run_command(result.map(|(message, _, _)| message));
             }
         }
-        Commands::Enable {
+        Commands::Set {
             service,
             client_path,
+            value,
+            force,
         } => {
-            let db = match make_db(target, json_mode) {
+            if let Err(err) = check_auth_value(value, force) {
+                if json_mode {
+                    emit_json_error(json_out, "parse", "ParseError", err.to_string());
+                    exit(1);
+                }
+                exit_with(err);
+            }
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("enable", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "set", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
-            let result = db.enable(&service, &client_path);
+            let result = db.set(&service, &client_path, value);
+            report_warnings(db.take_warnings(), json_mode);
             if json_mode {
                 match result {
-                    Ok(message) => emit_json_success("enable", json_message_data(&message)),
+                    Ok(message) => {
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        emit_json_message(
+                            json_out,
+                            "set",
+                            &message,
+                            json_set_data(&message, &service_raw, value),
+                        )
+                    }
                     Err(e) => {
-                        emit_json_error("enable", error_kind(&e), e.to_string());
-                        process::exit(1);
+                        emit_json_tcc_error(json_out, "set", &e);
+                        exit(1);
                     }
                 }
             } else {
                 run_command(result);
             }
         }
-        Commands::Disable {
+        Commands::Reset {
             service,
             client_path,
+            client_file,
+            keep,
+            confirm_each: confirm,
+            interactive,
+            yes,
+            summary_only,
+            show_deleted,
+            family,
         } => {
-            let db = match make_db(target, json_mode) {
+            if interactive && json_mode {
+                emit_json_error(
+                    json_out,
+                    "reset",
+                    "NotInteractive",
+                    "--interactive cannot be used with --json".to_string(),
+                );
+                exit(1);
+            }
+            if confirm && (json_mode || !io::stdin().is_terminal()) {
+                let msg =
+                    "--confirm-each needs an interactive terminal and cannot be used with --json";
+                if json_mode {
+                    emit_json_error(json_out, "reset", "NotInteractive", msg.to_string());
+                } else {
+                    eprintln!("{}: {}", "Error".red().bold(), msg);
+                }
+                exit(1);
+            }
+            let client_path = match client_file.map(|path| tcc::read_client_file(&path)) {
+                None => client_path,
+                Some(Ok(client)) => Some(client),
+                Some(Err(e)) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "reset", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("disable", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "reset", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
-            let result = db.disable(&service, &client_path);
+            if let Some(family) = family {
+                reset_family(
+                    &db,
+                    family.into(),
+                    json_mode,
+                    json_out,
+                    summary_only,
+                    show_deleted,
+                );
+                return;
+            }
+            let Some(service) = service else {
+                unreachable!("clap requires a service without --family")
+            };
+            // Not one client, not all but --keep: every entry of the service
+            let all_entries = client_path.is_none() && keep.is_empty();
+            let result = if confirm {
+                db.resolve_service_name(&service)
+                    .and_then(|service_key| {
+                        db.list_filtered(&ListFilter {
+                            service_key: Some(&service_key),
+                            ..ListFilter::default()
+                        })
+                    })
+                    .and_then(|entries| {
+                        // Before the prompt, not after it
+                        report_warnings(db.take_warnings(), json_mode);
+                        let stdin = io::stdin();
+                        let picked = confirm_each(&entries, &mut stdin.lock(), &mut io::stdout())
+                            .map_err(|e| {
+                            TccError::WriteFailed(format!("Failed to read answer: {}", e))
+                        })?;
+                        if picked.is_empty() {
+                            return Ok(ResetOutcome::new(
+                                "Nothing selected, no entries deleted".to_string(),
+                                0,
+                                0,
+                            ));
+                        }
+                        db.reset_selected(&service, &picked)
+                    })
+            } else if interactive {
+                db.resolve_service_name(&service)
+                    .and_then(|service_key| {
+                        db.list_filtered(&ListFilter {
+                            service_key: Some(&service_key),
+                            ..ListFilter::default()
+                        })
+                    })
+                    .and_then(|mut entries| {
+                        // Before the prompt, not after it
+                        report_warnings(db.take_warnings(), json_mode);
+                        entries.retain(|e| match &client_path {
+                            Some(client) => &e.client == client,
+                            None => !keep.contains(&e.client),
+                        });
+                        if entries.is_empty() {
+                            return Ok(ResetOutcome::new(
+                                "Nothing to reset, no entries deleted".to_string(),
+                                0,
+                                0,
+                            ));
+                        }
+                        println!("The following entries would be deleted:\n");
+                        print_entries(
+                            &entries,
+                            ClientView::default(),
+                            glyphs,
+                            scheme,
+                            false,
+                            Some(&FooterTemplate::default()),
+                        );
+                        println!();
+                        let stdin = io::stdin();
+                        let prompt = format!("Delete these {} entries?", entries.len());
+                        let confirmed = yes
+                            || confirm_once(&prompt, &mut stdin.lock(), &mut io::stdout())
+                                .map_err(|e| {
+                                    TccError::WriteFailed(format!("Failed to read answer: {}", e))
+                                })?;
+                        if !confirmed {
+                            return Ok(ResetOutcome::new(
+                                "Aborted, no entries deleted".to_string(),
+                                0,
+                                0,
+                            ));
+                        }
+                        let picked: Vec<&TccEntry> = entries.iter().collect();
+                        db.reset_selected(&service, &picked)
+                    })
+            } else if keep.is_empty() {
+                db.reset(&service, client_path.as_deref())
+            } else {
+                db.reset_except(&service, &keep)
+            };
+            report_warnings(db.take_warnings(), json_mode);
             if json_mode {
                 match result {
-                    Ok(message) => emit_json_success("disable", json_message_data(&message)),
+                    Ok(outcome) => {
+                        outcome.warnings.iter().cloned().for_each(hold_warning);
+                        let service_raw = db
+                            .resolve_service_name(&service)
+                            .unwrap_or_else(|_| service.clone());
+                        let mut data = json_with_changed(
+                            json_message_data(&outcome.to_string(), &service_raw),
+                            outcome.deleted,
+                        );
+                        if all_entries {
+                            data = json_with_total_deleted(data, outcome.deleted);
+                        }
+                        emit_json_message(
+                            json_out,
+                            "reset",
+                            &outcome.to_string(),
+                            json_with_deleted(data, "deleted", &outcome.deleted_rows),
+                        )
+                    }
                     Err(e) => {
-                        emit_json_error("disable", error_kind(&e), e.to_string());
-                        process::exit(1);
+                        emit_json_tcc_error(json_out, "reset", &e);
+                        exit(1);
                     }
                 }
+            } else if summary_only {
+                // Warnings still surface, just not on stdout
+                run_command(result.map(|outcome| {
+                    for warning in &outcome.warnings {
+                        eprintln!("Warning: {}", warning);
+                    }
+                    delete_summary(outcome.deleted, outcome.dbs)
+                }));
             } else {
-                run_command(result);
+                run_command(result.map(|outcome| {
+                    if show_deleted {
+                        print_deleted_rows(&outcome.deleted_rows);
+                    }
+                    outcome.to_string()
+                }));
             }
         }
-        Commands::Reset {
+        Commands::Check {
             service,
             client_path,
+            oneline,
+            effective,
         } => {
-            let db = match make_db(target, json_mode) {
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("reset", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "check", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+
+            let checked = db.check(&service, &client_path);
+            report_warnings(db.take_warnings(), json_mode);
+            let entry = match checked {
+                Ok(entry) => entry,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "check", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
-            let result = db.reset(&service, client_path.as_deref());
+            let service_key = db
+                .resolve_service_name(&service)
+                .unwrap_or_else(|_| service.clone());
+            let service_display = TccDb::service_display_name(&service_key);
+            let effective = effective
+                .then(|| Service::try_from(service_key.as_str()).ok())
+                .flatten()
+                .map(|svc| tcc::effective_authorization(svc, &client_path));
+
             if json_mode {
-                match result {
-                    Ok(message) => emit_json_success("reset", json_message_data(&message)),
-                    Err(e) => {
-                        emit_json_error("reset", error_kind(&e), e.to_string());
-                        process::exit(1);
+                emit_json_success(
+                    json_out,
+                    "check",
+                    json_check_data(&service_key, &client_path, entry.as_ref(), effective),
+                );
+            } else if oneline {
+                println!(
+                    "{}",
+                    check_oneline(
+                        &service_display,
+                        &client_path,
+                        entry.as_ref(),
+                        scheme,
+                        glyphs
+                    )
+                );
+            } else {
+                match &entry {
+                    Some(e) => print_entries(
+                        std::slice::from_ref(e),
+                        ClientView::default(),
+                        glyphs,
+                        scheme,
+                        false,
+                        Some(&FooterTemplate::default()),
+                    ),
+                    None => println!(
+                        "{}",
+                        format!("No entry for {} and '{}'", service_display, client_path).dimmed()
+                    ),
+                }
+                if let Some(effective) = effective {
+                    println!("{}", effective_line(entry.as_ref(), effective));
+                }
+            }
+            exit(check_exit_code(entry.as_ref()));
+        }
+        Commands::Get {
+            service,
+            client_path,
+        } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "get", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+
+            let got = db.get(&service, &client_path);
+            report_warnings(db.take_warnings(), json_mode);
+            match got {
+                Ok(entry) => {
+                    if json_mode {
+                        emit_json_success(
+                            json_out,
+                            "get",
+                            json_entry(&entry, ClientView::default()),
+                        );
+                    } else {
+                        print_entries(
+                            std::slice::from_ref(&entry),
+                            ClientView::default(),
+                            glyphs,
+                            scheme,
+                            false,
+                            Some(&FooterTemplate::default()),
+                        );
+                    }
+                }
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "get", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            }
+        }
+        Commands::Repair { fix_duplicates } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "repair", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+
+            let found = db.find_duplicates(fix_duplicates);
+            report_warnings(db.take_warnings(), json_mode);
+            match found {
+                Ok(groups) => {
+                    if json_mode {
+                        emit_json_success(
+                            json_out,
+                            "repair",
+                            json_repair_data(&groups, fix_duplicates),
+                        );
+                    } else {
+                        print_duplicates(&groups, fix_duplicates);
+                    }
+                }
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "repair", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            }
+        }
+        Commands::History { bucket } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "history", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+
+            match db.list_entries(&ListFilter::default()) {
+                Ok(ListResult { entries, warnings }) => {
+                    report_warnings(warnings, json_mode);
+                    let (periods, unknown) = bucket_history(&entries, bucket.into());
+                    if json_mode {
+                        emit_json_success(
+                            json_out,
+                            "history",
+                            json_history_data(bucket, &periods, unknown),
+                        );
+                    } else {
+                        print_history(&periods, unknown, glyphs);
                     }
                 }
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "history", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            }
+        }
+        Commands::Services { count: true } => {
+            let counts = service_counts();
+            if json_mode {
+                emit_json_success(json_out, "services", json_services_count_data(counts));
             } else {
-                run_command(result);
+                let (total, system, user) = counts;
+                println!("{} known services", total);
+                println!("  system  {}", system);
+                println!("  user    {}", user);
             }
         }
-        Commands::Services => {
+        Commands::Services { count: false } => {
             if json_mode {
-                emit_json_success("services", json_services_data());
+                emit_json_success(json_out, "services", json_services_data());
             } else {
                 println!("{:<35}  DESCRIPTION", "INTERNAL NAME");
-                println!("{:<35}  {}", "─".repeat(35), "─".repeat(25));
+                println!("{:<35}  {}", glyphs.rule.repeat(35), glyphs.rule.repeat(25));
                 let mut pairs: Vec<_> = SERVICE_MAP.iter().collect();
                 pairs.sort_by_key(|(_, desc)| *desc);
                 for (key, desc) in pairs {
@@ -727,26 +6018,267 @@ fn main() {
                 }
             }
         }
-        Commands::Info => {
-            let db = match make_db(target, json_mode) {
+        Commands::Info {
+            check_permissions,
+            group_services,
+            utc,
+        } => {
+            let generated_at = rfc3339_now(utc);
+            let db = match make_db(&db_opts, target) {
                 Ok(db) => db,
                 Err(e) => {
                     if json_mode {
-                        emit_json_error("info", error_kind(&e), e.to_string());
+                        emit_json_tcc_error(json_out, "info", &e);
                     } else {
                         eprintln!("{}: {}", "Error".red().bold(), e);
                     }
-                    process::exit(1);
+                    exit(1);
                 }
             };
 
-            let lines = db.info();
+            let report = db.info();
+            let access = check_permissions.then(|| db.service_access());
+            let families = group_services.then(|| db.family_counts()).transpose();
+            report_warnings(db.take_warnings(), json_mode);
+            let families = match families {
+                Ok(families) => families,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "info", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
             if json_mode {
-                emit_json_success("info", json_info_data(&lines));
+                emit_json_success(
+                    json_out,
+                    "info",
+                    json_info_data(
+                        &generated_at,
+                        &report,
+                        access.as_deref(),
+                        families.as_deref(),
+                    ),
+                );
             } else {
-                for line in lines {
+                println!("Generated at: {}", generated_at);
+                for line in report.lines {
                     println!("{}", line);
                 }
+                if let Some(access) = &access {
+                    println!("Service permissions:");
+                    print_service_access(access, glyphs);
+                }
+                if let Some(families) = &families {
+                    println!("Entries by service family:");
+                    print_family_counts(families);
+                }
+            }
+        }
+        Commands::Dump => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "dump", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+            let dumped = db.dump();
+            report_warnings(db.take_warnings(), json_mode);
+            match dumped {
+                Ok(rows) => {
+                    // A bare array whatever the output mode, so it can be saved as is
+                    let rows: Vec<String> = rows.iter().map(json_raw_row).collect();
+                    let array = format!("[{}]", rows.join(","));
+                    check_json(json_out, &array);
+                    println!("{}", array);
+                    for warning in take_held_warnings() {
+                        eprintln!("Warning: {}", warning);
+                    }
+                }
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "dump", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            }
+        }
+        Commands::Restore { file, dry_run } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "restore", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+            let result = db.restore(&file, dry_run);
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                match result {
+                    Ok(outcome) => emit_json_message(
+                        json_out,
+                        "restore",
+                        &restore_message(&outcome, dry_run),
+                        json_restore_data(&outcome, dry_run),
+                    ),
+                    Err(e) => {
+                        emit_json_tcc_error(json_out, "restore", &e);
+                        exit(1);
+                    }
+                }
+            } else {
+                run_command(result.map(|outcome| restore_message(&outcome, dry_run)));
+            }
+        }
+        Commands::Export {
+            path,
+            format,
+            checksum,
+            redact,
+        } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "export", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+            let result = match format {
+                ExportFormat::Sqlite => {
+                    db.export_sqlite(&path, redact.then(Redactor::new).as_ref())
+                }
+            }
+            .map(|outcome| (outcome.rows, checksum.then_some(outcome.checksum)));
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                match result {
+                    Ok((rows, checksum)) => emit_json_success(
+                        json_out,
+                        "export",
+                        format!(
+                            "{{\"path\":{},\"format\":\"sqlite\",\"rows\":{}{}}}",
+                            json_string(&path.display().to_string()),
+                            rows,
+                            checksum.map_or(String::new(), |c| format!(
+                                ",\"checksum\":{}",
+                                json_string(&c)
+                            ))
+                        ),
+                    ),
+                    Err(e) => {
+                        emit_json_tcc_error(json_out, "export", &e);
+                        exit(1);
+                    }
+                }
+            } else {
+                run_command(result.map(|(rows, checksum)| {
+                    let message = format!("Exported {} rows to {}", rows, path.display());
+                    match checksum {
+                        Some(checksum) => format!("{}\nChecksum: {}", message, checksum),
+                        None => message,
+                    }
+                }));
+            }
+        }
+        Commands::Paths => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "paths", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+
+            if json_mode {
+                emit_json_success(json_out, "paths", json_paths_data(&db));
+            } else {
+                for (path, _, _) in db.target_paths(Op::Read) {
+                    println!("{}", path.display());
+                }
+            }
+        }
+        Commands::Doctor { fix } => {
+            let db = match make_db(&db_opts, target) {
+                Ok(db) => db,
+                Err(e) => {
+                    if json_mode {
+                        emit_json_tcc_error(json_out, "doctor", &e);
+                    } else {
+                        eprintln!("{}: {}", "Error".red().bold(), e);
+                    }
+                    exit(1);
+                }
+            };
+            // Fix first, so the checks describe the state it left behind
+            let fixes = if fix { Some(db.doctor_fix()) } else { None };
+            let checks = db.doctor();
+            report_warnings(db.take_warnings(), json_mode);
+            if json_mode {
+                let mut data = json_doctor_data(&checks);
+                if let Some(fixes) = &fixes {
+                    data.pop();
+                    data.push_str(&format!(",\"fixes\":{}}}", json_doctor_fixes(fixes)));
+                }
+                emit_json_success(json_out, "doctor", data);
+            } else {
+                if let Some(fixes) = &fixes {
+                    print_doctor_fixes(fixes);
+                }
+                print_doctor(&checks);
+            }
+            if checks.iter().any(|c| c.status == CheckStatus::Fail) {
+                exit(1);
+            }
+        }
+        Commands::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut completion_command(),
+                "tccutil-rs",
+                &mut io::stdout(),
+            );
+        }
+        Commands::SelfTest => {
+            let checks = tcc::self_test();
+            let passed = checks.iter().all(|c| c.result.is_ok());
+            if json_mode {
+                emit_json_success(json_out, "self-test", json_self_test_data(&checks));
+            } else {
+                for check in &checks {
+                    match &check.result {
+                        Ok(()) => println!("{} {}", glyphs.pass.green(), check.name),
+                        Err(e) => println!("{} {}: {}", glyphs.fail.red(), check.name, e),
+                    }
+                }
+                if passed {
+                    println!("\n{}", "Self-test passed".green());
+                } else {
+                    println!("\n{}", "Self-test failed".red().bold());
+                }
+            }
+            if !passed {
+                exit(1);
             }
         }
     }
@@ -1,10 +1,13 @@
 use chrono::{Local, TimeZone};
+use regex::Regex;
 use rusqlite::{Connection, OpenFlags};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::LazyLock;
+use std::time::Duration;
 
 pub static SERVICE_MAP: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
     let mut m = HashMap::new();
@@ -63,17 +66,125 @@ const KNOWN_DIGESTS: &[&str] = &[
     "f773496775", // Sonoma (alt)
 ];
 
+/// `(major, minor)`, as `macos_release` returns it
+type MacosRelease = (u32, u32);
+
+/// The macOS releases each entry of `KNOWN_DIGESTS` belongs to, as inclusive
+/// bounds; `None` is open-ended.
+const DIGEST_RELEASES: &[(&str, &str, MacosRelease, Option<MacosRelease>)] = &[
+    ("8e93d38f7c", "before El Capitan", (10, 0), Some((10, 10))),
+    (
+        "9b2ea61b30",
+        "El Capitan to High Sierra",
+        (10, 11),
+        Some((10, 13)),
+    ),
+    (
+        "1072dc0e4b",
+        "El Capitan to High Sierra",
+        (10, 11),
+        Some((10, 13)),
+    ),
+    ("ecc443615f", "Mojave or Catalina", (10, 14), Some((10, 15))),
+    ("80a4bb6912", "Mojave or Catalina", (10, 14), Some((10, 15))),
+    ("3d1c2a0e97", "Big Sur or later", (11, 0), None),
+    ("cef70648de", "Big Sur or later", (11, 0), None),
+    ("34abf99d20", "Sonoma or later", (14, 0), None),
+    ("e3a2181c14", "Sonoma or later", (14, 0), None),
+    ("f773496775", "Sonoma or later", (14, 0), None),
+];
+
+/// `(major, minor)` of a macOS version string. From Big Sur (11) on only the
+/// major number names a release, so the minor is dropped.
+fn macos_release(version: &str) -> Option<MacosRelease> {
+    let mut parts = version.trim().split('.');
+    let major: u32 = parts.next()?.parse().ok()?;
+    let minor: u32 = match parts.next() {
+        Some(minor) => minor.parse().ok()?,
+        None => 0,
+    };
+    Some(if major >= 11 {
+        (major, 0)
+    } else {
+        (major, minor)
+    })
+}
+
+/// A note when a known schema digest belongs to other macOS releases than
+/// `version`, which usually means the DB was copied from another machine.
+/// `None` when they agree, the digest is unknown, or the version unparsable.
+pub fn digest_version_mismatch(digest: &str, version: &str) -> Option<String> {
+    let &(_, releases, from, to) = DIGEST_RELEASES.iter().find(|(d, ..)| *d == digest)?;
+    let release = macos_release(version)?;
+    if release >= from && to.is_none_or(|to| release <= to) {
+        return None;
+    }
+    Some(format!(
+        "Schema digest {} is from macOS {}, but this system runs macOS {}; the DB may have been copied from another machine",
+        digest,
+        releases,
+        version.trim()
+    ))
+}
+
 #[derive(Debug)]
 pub enum TccError {
-    DbOpen { path: PathBuf, source: String },
-    NotFound { service: String, client: String },
-    NeedsRoot { message: String },
+    DbOpen {
+        path: PathBuf,
+        source: String,
+    },
+    NotFound {
+        service: String,
+        client: String,
+    },
+    NeedsRoot {
+        message: String,
+    },
     UnknownService(String),
-    AmbiguousService { input: String, matches: Vec<String> },
+    /// `candidates` are (key, display name) pairs, sorted by display name
+    AmbiguousService {
+        input: String,
+        candidates: Vec<(String, String)>,
+    },
     QueryFailed(String),
     SchemaInvalid(String),
     HomeDirNotFound,
     WriteFailed(String),
+    PidNotResolved {
+        pid: i32,
+        reason: String,
+    },
+    ClientFile {
+        path: PathBuf,
+        reason: String,
+    },
+    /// A `restore` file that is not a `dump` array
+    DumpInvalid {
+        path: PathBuf,
+        reason: String,
+    },
+    /// `grant` found an entry with another status and was not forced
+    EntryExists {
+        service: String,
+        client: String,
+        auth_value: i32,
+    },
+    /// `toggle` found a status other than granted, denied or limited
+    NotToggleable {
+        service: String,
+        client: String,
+        auth_value: i32,
+    },
+    /// An explicitly located DB that does not exist
+    DbMissing(PathBuf),
+    /// SQLite reported the user DB busy or locked, i.e. tccd has it open
+    DbLocked(PathBuf),
+    /// `--verify-schema` found a different digest (or none) in this DB
+    SchemaMismatch {
+        path: PathBuf,
+        expected: String,
+        found: Option<String>,
+    },
 }
 
 impl fmt::Display for TccError {
@@ -99,16 +210,72 @@ impl fmt::Display for TccError {
                 "Unknown service '{}'. Run `tcc services` to see available services.",
                 s
             ),
-            TccError::AmbiguousService { input, matches } => write!(
+            TccError::AmbiguousService { input, candidates } => write!(
                 f,
                 "Ambiguous service '{}'. Matches: {}",
                 input,
-                matches.join(", ")
+                candidates
+                    .iter()
+                    .map(|(_, display)| display.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
             ),
             TccError::QueryFailed(s) => write!(f, "{}", s),
             TccError::SchemaInvalid(s) => write!(f, "{}", s),
             TccError::HomeDirNotFound => write!(f, "Cannot determine home directory"),
             TccError::WriteFailed(s) => write!(f, "{}", s),
+            TccError::PidNotResolved { pid, reason } => {
+                write!(f, "Cannot resolve client for PID {}: {}", pid, reason)
+            }
+            TccError::ClientFile { path, reason } => {
+                write!(f, "Cannot read client from {}: {}", path.display(), reason)
+            }
+            TccError::EntryExists {
+                service,
+                client,
+                auth_value,
+            } => write!(
+                f,
+                "{} already has an entry for '{}' ({}). Use `tcc enable` to change it, or pass --force to grant over it",
+                service,
+                client,
+                auth_value_display(*auth_value)
+            ),
+            TccError::NotToggleable {
+                service,
+                client,
+                auth_value,
+            } => write!(
+                f,
+                "{} for '{}' is {}, which has no opposite to toggle to. Use `tcc set` to change it",
+                service,
+                client,
+                auth_value_display(*auth_value)
+            ),
+            TccError::DumpInvalid { path, reason } => {
+                write!(f, "Cannot restore from {}: {}", path.display(), reason)
+            }
+            TccError::SchemaMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Schema digest mismatch for {}: expected {}, found {}",
+                path.display(),
+                expected,
+                found.as_deref().unwrap_or("no readable access table")
+            ),
+            TccError::DbMissing(path) => write!(
+                f,
+                "No TCC database at {}. Check the path, or pass --allow-missing-db to treat it as empty.",
+                path.display()
+            ),
+            TccError::DbLocked(path) => write!(
+                f,
+                "The TCC daemon (tccd) has {} open and locked. Try again, or pass --retry <N> to wait for it; note that tccd may revert a change written while it holds the database.",
+                path.display()
+            ),
         }
     }
 }
@@ -134,215 +301,155 @@ fn tcc_open_access_denied_hint(path: &Path, source: &str) -> Option<String> {
     )
 }
 
-fn is_tcc_db_path(path: &Path) -> bool {
-    path == Path::new("/Library/Application Support/com.apple.TCC/TCC.db")
-        || path.ends_with("Library/Application Support/com.apple.TCC/TCC.db")
+/// The first symlink on `path` (the file itself or any parent directory)
+/// that points nowhere, with its dangling target
+fn broken_symlink_target(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let mut ancestors: Vec<&Path> = path.ancestors().collect();
+    ancestors.reverse();
+    ancestors.into_iter().find_map(|link| {
+        let meta = std::fs::symlink_metadata(link).ok()?;
+        if meta.file_type().is_symlink() && !link.exists() {
+            std::fs::read_link(link)
+                .ok()
+                .map(|target| (link.to_path_buf(), target))
+        } else {
+            None
+        }
+    })
 }
 
-#[derive(Debug)]
-pub struct TccEntry {
-    pub service_raw: String,
-    pub service_display: String,
-    pub client: String,
-    pub auth_value: i32,
-    pub last_modified: String,
-    pub is_system: bool,
+/// A broken symlink would otherwise look like a missing DB (read) or get
+/// silently created at the dangling target (write), so report it instead.
+fn check_not_broken_symlink(path: &Path) -> Result<(), TccError> {
+    match broken_symlink_target(path) {
+        Some((link, target)) if link == path => Err(TccError::DbOpen {
+            path: path.to_path_buf(),
+            source: format!("broken symlink to {}", target.display()),
+        }),
+        Some((link, target)) => Err(TccError::DbOpen {
+            path: path.to_path_buf(),
+            source: format!(
+                "{} is a broken symlink to {}",
+                link.display(),
+                target.display()
+            ),
+        }),
+        None => Ok(()),
+    }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum DbTarget {
-    /// Use both DBs for reads, system for writes (default)
-    Default,
-    /// User DB only
-    User,
+/// `DbMissing` unless something exists at `path`, or without `required`
+/// only the broken symlink check. A broken symlink on the way is never
+/// mistaken for a missing (or, with `--allow-missing-db`, empty) DB.
+fn check_db_present(path: &Path, required: bool) -> Result<(), TccError> {
+    check_not_broken_symlink(path)?;
+    if required && path.symlink_metadata().is_err() {
+        return Err(TccError::DbMissing(path.to_path_buf()));
+    }
+    Ok(())
 }
 
-pub struct TccDb {
-    user_db_path: PathBuf,
-    system_db_path: PathBuf,
-    target: DbTarget,
-    suppress_warnings: bool,
+/// Short SHA1 of the access table's CREATE statement, as listed in
+/// `KNOWN_DIGESTS`. `None` when the table is missing or unreadable.
+fn access_schema_digest(conn: &Connection) -> Option<String> {
+    let sql: String = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE name='access' AND type='table'",
+            [],
+            |row| row.get(0),
+        )
+        .ok()?;
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(sql.as_bytes());
+    Some(hasher.digest().to_string()[..10].to_string())
 }
 
-impl TccDb {
-    pub fn new(target: DbTarget) -> Result<Self, TccError> {
-        let home = dirs::home_dir().ok_or(TccError::HomeDirNotFound)?;
-        Ok(Self {
-            user_db_path: home.join("Library/Application Support/com.apple.TCC/TCC.db"),
-            system_db_path: PathBuf::from("/Library/Application Support/com.apple.TCC/TCC.db"),
-            target,
-            suppress_warnings: false,
-        })
-    }
+/// The access schema digest of one targeted DB
+#[derive(Debug)]
+pub struct SchemaDigest {
+    pub source: SourceLabel,
+    pub path: PathBuf,
+    /// `None` when the DB cannot be opened or has no access table
+    pub digest: Option<String>,
+}
 
-    #[cfg(test)]
-    pub fn with_paths(user: PathBuf, system: PathBuf, target: DbTarget) -> Self {
-        Self {
-            user_db_path: user,
-            system_db_path: system,
-            target,
-            suppress_warnings: false,
-        }
-    }
+/// `sw_vers -productVersion` output, e.g. `14.5` — use absolute path for
+/// defensive coding
+fn macos_version() -> Option<String> {
+    Command::new("/usr/bin/sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|v| !v.is_empty())
+}
 
-    pub fn set_suppress_warnings(&mut self, suppress_warnings: bool) {
-        self.suppress_warnings = suppress_warnings;
-    }
+/// `csrutil status` output — use absolute path for defensive coding
+fn sip_status() -> Option<String> {
+    Command::new("/usr/bin/csrutil")
+        .arg("status")
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+}
 
-    pub(crate) fn format_timestamp(ts: i64) -> String {
-        if ts == 0 {
-            return "N/A".to_string();
-        }
-        // macOS TCC uses CoreData timestamps (seconds since 2001-01-01) or Unix timestamps.
-        let unix_ts = if ts < 1_000_000_000 {
-            ts + 978_307_200
-        } else {
-            ts
-        };
+fn is_tcc_db_path(path: &Path) -> bool {
+    path == Path::new("/Library/Application Support/com.apple.TCC/TCC.db")
+        || path.ends_with("Library/Application Support/com.apple.TCC/TCC.db")
+}
 
-        match Local.timestamp_opt(unix_ts, 0) {
-            chrono::LocalResult::Single(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-            _ => format!("{}", ts),
-        }
-    }
+/// A known TCC service, holding its canonical `SERVICE_MAP` key.
+/// Build one with `Service::try_from`, which accepts the same spellings as
+/// the CLI (raw key, display name, unique partial name, key without prefix).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Service(&'static str);
 
-    pub(crate) fn service_display_name(raw: &str) -> String {
-        SERVICE_MAP
-            .get(raw)
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| raw.strip_prefix("kTCCService").unwrap_or(raw).to_string())
+impl Service {
+    pub fn key(self) -> &'static str {
+        self.0
     }
 
-    fn read_db(
-        path: &Path,
-        is_system: bool,
-        emit_warnings: bool,
-    ) -> Result<Vec<TccEntry>, TccError> {
-        if !path.exists() {
-            return Ok(vec![]);
-        }
-
-        let conn =
-            Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|e| {
-                TccError::DbOpen {
-                    path: path.to_path_buf(),
-                    source: e.to_string(),
-                }
-            })?;
-
-        let query = "SELECT service, client, auth_value, \
-                     COALESCE(last_modified, 0) as modified \
-                     FROM access";
-
-        let result = conn.prepare(query);
-        let mut stmt = match result {
-            Ok(s) => s,
-            Err(_) => {
-                let fallback = "SELECT service, client, auth_value, 0 as modified FROM access";
-                conn.prepare(fallback).map_err(|e| {
-                    TccError::QueryFailed(format!("Query failed on {}: {}", path.display(), e))
-                })?
-            }
-        };
-
-        let rows = stmt
-            .query_map([], |row| {
-                let service_raw: String = row.get(0)?;
-                let client: String = row.get(1)?;
-                let auth_value: i32 = row.get(2)?;
-                let modified: i64 = row.get(3)?;
-
-                Ok(TccEntry {
-                    service_display: Self::service_display_name(&service_raw),
-                    service_raw,
-                    client,
-                    auth_value,
-                    last_modified: Self::format_timestamp(modified),
-                    is_system,
-                })
-            })
-            .map_err(|e| {
-                TccError::QueryFailed(format!("Query error on {}: {}", path.display(), e))
-            })?;
-
-        let mut entries = Vec::new();
-        for result in rows {
-            match result {
-                Ok(entry) => entries.push(entry),
-                Err(e) => {
-                    if emit_warnings {
-                        eprintln!(
-                            "Warning: skipping malformed row in {}: {}",
-                            path.display(),
-                            e
-                        );
-                    }
-                }
-            }
-        }
-
-        Ok(entries)
+    pub fn display_name(self) -> &'static str {
+        SERVICE_MAP[self.0]
     }
 
-    pub fn list(
-        &self,
-        client_filter: Option<&str>,
-        service_filter: Option<&str>,
-    ) -> Result<Vec<TccEntry>, TccError> {
-        let mut entries = Vec::new();
-
-        if self.target == DbTarget::Default || self.target == DbTarget::User {
-            match Self::read_db(&self.user_db_path, false, !self.suppress_warnings) {
-                Ok(mut e) => entries.append(&mut e),
-                Err(e) => {
-                    if !self.suppress_warnings {
-                        eprintln!("Warning: {}", e);
-                    }
-                }
-            }
-        }
-
-        if self.target == DbTarget::Default {
-            match Self::read_db(&self.system_db_path, true, !self.suppress_warnings) {
-                Ok(mut e) => entries.append(&mut e),
-                Err(e) => {
-                    if !self.suppress_warnings {
-                        eprintln!("Warning: {}", e);
-                    }
-                }
-            }
-        }
-
-        if let Some(cf) = client_filter {
-            let cf_lower = cf.to_lowercase();
-            entries.retain(|e| e.client.to_lowercase().contains(&cf_lower));
-        }
-        if let Some(sf) = service_filter {
-            let sf_lower = sf.to_lowercase();
-            entries.retain(|e| {
-                e.service_display.to_lowercase().contains(&sf_lower)
-                    || e.service_raw.to_lowercase().contains(&sf_lower)
-            });
-        }
+    /// `Camera [kTCCServiceCamera]`, so messages show what an alias resolved to
+    pub fn labelled(self) -> String {
+        format!("{} [{}]", self.display_name(), self.0)
+    }
 
-        entries.sort_by(|a, b| {
-            a.service_display
-                .cmp(&b.service_display)
-                .then(a.client.cmp(&b.client))
-        });
+    /// Whether writes for this service go to the system DB
+    pub fn is_system(self) -> bool {
+        matches!(
+            self.0,
+            "kTCCServiceAccessibility"
+                | "kTCCServiceScreenCapture"
+                | "kTCCServiceListenEvent"
+                | "kTCCServicePostEvent"
+                | "kTCCServiceEndpointSecurityClient"
+                | "kTCCServiceDeveloperTool"
+        )
+    }
 
-        Ok(entries)
+    /// Whether macOS scopes entries of this service to a target app via
+    /// `indirect_object_identifier` (Automation: which app may be scripted)
+    pub fn needs_indirect_object(self) -> bool {
+        self.0 == "kTCCServiceAppleEvents"
     }
+}
 
-    pub fn resolve_service_name(&self, input: &str) -> Result<String, TccError> {
-        if SERVICE_MAP.contains_key(input) {
-            return Ok(input.to_string());
+impl TryFrom<&str> for Service {
+    type Error = TccError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        if let Some((key, _)) = SERVICE_MAP.get_key_value(input) {
+            return Ok(Service(key));
         }
         let input_lower = input.to_lowercase();
         // Exact display name match (case-insensitive)
         for (key, display) in SERVICE_MAP.iter() {
             if display.to_lowercase() == input_lower {
-                return Ok(key.to_string());
+                return Ok(Service(key));
             }
         }
         // Partial display name match — collect all, error if ambiguous
@@ -352,456 +459,4047 @@ impl TccDb {
             .collect();
         match partial_matches.len() {
             0 => {}
-            1 => return Ok(partial_matches[0].0.to_string()),
+            1 => return Ok(Service(partial_matches[0].0)),
             _ => {
-                let mut names: Vec<_> =
-                    partial_matches.iter().map(|(_, d)| d.to_string()).collect();
-                names.sort();
+                let mut candidates: Vec<_> = partial_matches
+                    .iter()
+                    .map(|(k, d)| (k.to_string(), d.to_string()))
+                    .collect();
+                candidates.sort_by(|a, b| a.1.cmp(&b.1));
                 return Err(TccError::AmbiguousService {
                     input: input.to_string(),
-                    matches: names,
+                    candidates,
                 });
             }
         }
         let prefixed = format!("kTCCService{}", input);
-        if SERVICE_MAP.contains_key(prefixed.as_str()) {
-            return Ok(prefixed);
+        if let Some((key, _)) = SERVICE_MAP.get_key_value(prefixed.as_str()) {
+            return Ok(Service(key));
         }
         Err(TccError::UnknownService(input.to_string()))
     }
+}
 
-    fn is_system_service(service: &str) -> bool {
-        matches!(
-            service,
-            "kTCCServiceAccessibility"
-                | "kTCCServiceScreenCapture"
-                | "kTCCServiceListenEvent"
-                | "kTCCServicePostEvent"
-                | "kTCCServiceEndpointSecurityClient"
-                | "kTCCServiceDeveloperTool"
-        )
-    }
+/// A broad grouping of services, for at-a-glance summaries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceFamily {
+    /// Camera, microphone, screen, input devices, Bluetooth
+    Devices,
+    /// Folders, volumes, and file providers
+    Files,
+    /// Control of other apps or the system
+    Automation,
+    /// Photos, contacts, calendars, location, and similar user data
+    PersonalData,
+    /// Services the CLI does not know
+    Other,
+}
 
-    /// Determine the target DB path for a write operation
-    fn write_db_path(&self, service_key: &str) -> &Path {
-        match self.target {
-            DbTarget::User => &self.user_db_path,
-            DbTarget::Default => {
-                if Self::is_system_service(service_key) {
-                    &self.system_db_path
-                } else {
-                    &self.user_db_path
-                }
-            }
+impl ServiceFamily {
+    pub const ALL: [ServiceFamily; 5] = [
+        ServiceFamily::Devices,
+        ServiceFamily::Files,
+        ServiceFamily::Automation,
+        ServiceFamily::PersonalData,
+        ServiceFamily::Other,
+    ];
+
+    pub fn of(service_raw: &str) -> Self {
+        match service_raw {
+            "kTCCServiceCamera"
+            | "kTCCServiceMicrophone"
+            | "kTCCServiceScreenCapture"
+            | "kTCCServiceListenEvent"
+            | "kTCCServiceBluetoothAlways"
+            | "kTCCServiceSpeechRecognition" => ServiceFamily::Devices,
+            "kTCCServiceSystemPolicyAllFiles"
+            | "kTCCServiceSystemPolicySysAdminFiles"
+            | "kTCCServiceSystemPolicyDesktopFolder"
+            | "kTCCServiceSystemPolicyDocumentsFolder"
+            | "kTCCServiceSystemPolicyDownloadsFolder"
+            | "kTCCServiceSystemPolicyNetworkVolumes"
+            | "kTCCServiceSystemPolicyRemovableVolumes"
+            | "kTCCServiceSystemPolicyDeveloperFiles"
+            | "kTCCServiceFileProviderDomain"
+            | "kTCCServiceFileProviderPresence" => ServiceFamily::Files,
+            "kTCCServiceAccessibility"
+            | "kTCCServiceAppleEvents"
+            | "kTCCServicePostEvent"
+            | "kTCCServiceDeveloperTool"
+            | "kTCCServiceEndpointSecurityClient" => ServiceFamily::Automation,
+            "kTCCServicePhotos"
+            | "kTCCServicePhotosAdd"
+            | "kTCCServiceCalendar"
+            | "kTCCServiceContacts"
+            | "kTCCServiceReminders"
+            | "kTCCServiceLocation"
+            | "kTCCServiceAddressBook"
+            | "kTCCServiceMediaLibrary"
+            | "kTCCServiceFocusStatus"
+            | "kTCCServiceLiverpool" => ServiceFamily::PersonalData,
+            _ => ServiceFamily::Other,
         }
     }
 
-    /// Check if root is needed and we don't have it
-    fn check_root_for_write(
-        &self,
-        service_key: &str,
-        action: &str,
-        service_input: &str,
-        client: &str,
-    ) -> Result<(), TccError> {
-        let db_path = self.write_db_path(service_key);
-        if db_path == self.system_db_path && !nix_is_root() {
-            return Err(TccError::NeedsRoot {
-                message: format!(
-                    "Service '{}' requires the system TCC database.\n\
-                     Run with sudo: sudo tcc {} {} {}",
-                    Self::service_display_name(service_key),
-                    action,
-                    service_input,
-                    client
-                ),
-            });
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ServiceFamily::Devices => "devices",
+            ServiceFamily::Files => "files",
+            ServiceFamily::Automation => "automation",
+            ServiceFamily::PersonalData => "personal_data",
+            ServiceFamily::Other => "other",
         }
-        Ok(())
     }
 
-    /// Validate the DB schema before writing. Returns Ok with an optional warning.
-    fn validate_schema(conn: &Connection) -> Result<Option<String>, TccError> {
-        let digest: Option<String> = conn
-            .query_row(
-                "SELECT sql FROM sqlite_master WHERE name='access' AND type='table'",
-                [],
-                |row| row.get(0),
-            )
-            .ok();
-
-        if let Some(sql) = digest {
-            let mut hasher = sha1_smol::Sha1::new();
-            hasher.update(sql.as_bytes());
-            let hex = hasher.digest().to_string();
-            let short = &hex[..10];
+    /// The known services in this family, sorted by display name
+    pub fn services(self) -> Vec<Service> {
+        let mut services: Vec<Service> = SERVICE_MAP
+            .keys()
+            .filter(|key| ServiceFamily::of(key) == self)
+            .map(|key| Service(key))
+            .collect();
+        services.sort_by_key(|s| s.display_name());
+        services
+    }
 
-            if KNOWN_DIGESTS.contains(&short) {
-                Ok(None)
-            } else {
-                Ok(Some(format!(
-                    "Warning: Unknown TCC database schema (digest: {}). Proceeding anyway — results may vary.",
-                    short
-                )))
-            }
-        } else {
-            Err(TccError::SchemaInvalid(
-                "Could not read TCC database schema. The access table may not exist.".to_string(),
-            ))
+    pub fn label(self) -> &'static str {
+        match self {
+            ServiceFamily::Devices => "Devices",
+            ServiceFamily::Files => "Files",
+            ServiceFamily::Automation => "Automation",
+            ServiceFamily::PersonalData => "Personal data",
+            ServiceFamily::Other => "Other",
         }
     }
+}
 
-    /// Open a writable connection with schema validation
-    fn open_writable(&self, service_key: &str) -> Result<(Connection, Option<String>), TccError> {
-        let db_path = self.write_db_path(service_key);
-        let conn = Connection::open(db_path).map_err(|e| TccError::DbOpen {
-            path: db_path.to_path_buf(),
-            source: e.to_string(),
-        })?;
-        let warning = Self::validate_schema(&conn)?;
-        Ok((conn, warning))
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
     }
+}
 
-    pub fn grant(&self, service: &str, client: &str) -> Result<String, TccError> {
-        let service_key = self.resolve_service_name(service)?;
-        self.check_root_for_write(&service_key, "grant", service, client)?;
-
-        let (conn, warning) = self.open_writable(&service_key)?;
-        if let Some(w) = &warning
-            && !self.suppress_warnings
-        {
-            eprintln!("{}", w);
-        }
+#[derive(Debug)]
+pub struct TccEntry {
+    pub service_raw: String,
+    pub service_display: String,
+    pub client: String,
+    /// 0 for a path, 1 for a bundle ID; inferred from `client` when the
+    /// schema has no `client_type` column
+    pub client_type: i32,
+    pub auth_value: i32,
+    /// How the row came to be (see `auth_reason_display`); 0 when the
+    /// schema has no `auth_reason` column
+    pub auth_reason: i32,
+    pub last_modified: String,
+    /// Unix seconds of the last modification (0 when the DB has none)
+    pub last_modified_ts: i64,
+    pub is_system: bool,
+    /// Boot session the row was written in; `None` when the schema has no
+    /// `boot_uuid` column
+    pub boot_uuid: Option<String>,
+    /// PID of the process the row was last written for; `None` when unset
+    /// or the schema has no `pid` column
+    pub pid: Option<i64>,
+    /// When macOS last re-prompted for this row, formatted like
+    /// `last_modified`; `None` when the schema has no `last_reminded` column
+    pub last_reminded: Option<String>,
+    /// The client's code requirement blob; `None` when NULL or the schema
+    /// has no `csreq` column
+    pub csreq: Option<Vec<u8>>,
+    /// The app an Automation client may script (`indirect_object_identifier`);
+    /// `None` for the `UNUSED` placeholder other services carry, or when the
+    /// schema has no such column
+    pub indirect_object: Option<String>,
+}
 
-        let client_type: i32 = if client.starts_with('/') { 0 } else { 1 };
-        let now = chrono::Utc::now().timestamp() - 978_307_200;
-        let sql = "INSERT OR REPLACE INTO access \
-                   (service, client, client_type, auth_value, auth_reason, auth_version, flags, last_modified) \
-                   VALUES (?1, ?2, ?3, 2, 0, 1, 0, ?4)";
+/// Row filters for `TccDb::list_filtered`. All set filters must match.
+#[derive(Debug, Default)]
+pub struct ListFilter<'a> {
+    /// Case-insensitive substring of the client
+    pub client: Option<&'a str>,
+    /// Case-insensitive substring of the service display name or raw key
+    pub service: Option<&'a str>,
+    /// Exact `service_raw` key (e.g. `kTCCServicePhotos`)
+    pub service_key: Option<&'a str>,
+    /// Regex searched in the client
+    pub client_regex: Option<&'a Regex>,
+    /// Regex searched in the service display name or raw key
+    pub service_regex: Option<&'a Regex>,
+    /// Any of these services, compared by canonical key
+    pub only: Option<&'a [Service]>,
+    /// Only entries whose write DB is currently writable from this session
+    pub only_modifiable: bool,
+    /// Only entries modified at or after these Unix seconds. Entries
+    /// without a modification time never match.
+    pub since: Option<i64>,
+    /// Only entries modified at or before these Unix seconds. Entries
+    /// without a modification time never match.
+    pub until: Option<i64>,
+    /// Only entries with a modification time; rows at 0 are usually
+    /// system-seeded defaults rather than something a user or app set
+    pub only_modified: bool,
+    /// Only entries written in this boot session. Entries from schemas
+    /// without a `boot_uuid` column are not filtered.
+    pub boot_uuid: Option<&'a str>,
+    /// Drop rows whose client is one of Apple's own TCC components
+    pub hide_tcc_internal: bool,
+    /// Only rows read from this DB. Unlike the target, every targeted DB is
+    /// still read.
+    pub source: Option<SourceLabel>,
+    /// Only rows whose `auth_value` falls in this status
+    pub auth: Option<AuthStatus>,
+    /// Not a filter: keep the order rows were read in (user DB, then system
+    /// DB) instead of sorting by `sort`
+    pub read_order: bool,
+    /// Not a filter: what to sort by. Ties fall back to service, then client.
+    pub sort: SortKey,
+    /// Not a filter: sort descending
+    pub reverse: bool,
+}
 
-        conn.execute(
-            sql,
-            rusqlite::params![service_key, client, client_type, now],
-        )
-        .map_err(|e| {
-            TccError::WriteFailed(format!(
-                "Failed to grant: {}. Note: SIP may prevent TCC.db writes on macOS 10.14+",
-                e
-            ))
-        })?;
+/// Sort order for `TccDb::list_filtered`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortKey {
+    /// Service display name, then client
+    #[default]
+    Service,
+    Client,
+    /// `auth_value`, so denied (0) sorts before granted (2) and limited (3)
+    Status,
+    /// `last_modified_ts`; entries without a time (0) sort as oldest
+    Modified,
+    /// User DB rows before system DB rows
+    Source,
+}
 
-        Ok(format!(
-            "Granted {} access for '{}'",
-            Self::service_display_name(&service_key),
-            client
-        ))
-    }
+/// Clients that manage TCC itself rather than use a protected resource:
+/// the daemon, and the System Settings panes that edit the DB. Exact
+/// matches only; anything under `TCC.framework` is covered separately.
+pub const TCC_INTERNAL_CLIENTS: &[&str] = &[
+    "com.apple.tccd",
+    "/usr/libexec/tccd",
+    "com.apple.systempreferences",
+    "com.apple.systempreferences.privacy",
+    "com.apple.settings.PrivacySecurity.extension",
+    "com.apple.preferences.security.remoteservice",
+];
 
-    pub fn revoke(&self, service: &str, client: &str) -> Result<String, TccError> {
-        let service_key = self.resolve_service_name(service)?;
-        self.check_root_for_write(&service_key, "revoke", service, client)?;
+const TCC_FRAMEWORK_PREFIX: &str = "/System/Library/PrivateFrameworks/TCC.framework/";
+
+/// Whether a client is one of Apple's own TCC components
+pub fn is_tcc_internal(client: &str) -> bool {
+    TCC_INTERNAL_CLIENTS.contains(&client) || client.starts_with(TCC_FRAMEWORK_PREFIX)
+}
 
-        let (conn, warning) = self.open_writable(&service_key)?;
-        if let Some(w) = &warning
-            && !self.suppress_warnings
+impl ListFilter<'_> {
+    pub fn matches(&self, entry: &TccEntry) -> bool {
+        if let Some(cf) = self.client
+            && !entry.client.to_lowercase().contains(&cf.to_lowercase())
         {
-            eprintln!("{}", w);
+            return false;
         }
-
-        let deleted = conn
-            .execute(
-                "DELETE FROM access WHERE service = ?1 AND client = ?2",
-                rusqlite::params![service_key, client],
-            )
-            .map_err(|e| {
-                TccError::WriteFailed(format!(
-                    "Failed to revoke: {}. Note: SIP may prevent TCC.db writes.",
-                    e
-                ))
-            })?;
-
-        if deleted == 0 {
-            Err(TccError::NotFound {
-                service: Self::service_display_name(&service_key),
-                client: client.to_string(),
-            })
-        } else {
-            Ok(format!(
-                "Revoked {} access for '{}'",
-                Self::service_display_name(&service_key),
-                client
-            ))
+        if let Some(sf) = self.service {
+            let sf_lower = sf.to_lowercase();
+            if !entry.service_display.to_lowercase().contains(&sf_lower)
+                && !entry.service_raw.to_lowercase().contains(&sf_lower)
+            {
+                return false;
+            }
+        }
+        if let Some(key) = self.service_key
+            && entry.service_raw != key
+        {
+            return false;
+        }
+        if let Some(re) = self.client_regex
+            && !re.is_match(&entry.client)
+        {
+            return false;
+        }
+        if let Some(re) = self.service_regex
+            && !re.is_match(&entry.service_display)
+            && !re.is_match(&entry.service_raw)
+        {
+            return false;
         }
+        if let Some(only) = self.only
+            && !only.iter().any(|s| s.key() == entry.service_raw)
+        {
+            return false;
+        }
+        if let Some(since) = self.since
+            && (entry.last_modified_ts == 0 || entry.last_modified_ts < since)
+        {
+            return false;
+        }
+        if let Some(until) = self.until
+            && (entry.last_modified_ts == 0 || entry.last_modified_ts > until)
+        {
+            return false;
+        }
+        if self.only_modified && entry.last_modified_ts == 0 {
+            return false;
+        }
+        if let Some(boot) = self.boot_uuid
+            && entry.boot_uuid.as_deref().is_some_and(|b| b != boot)
+        {
+            return false;
+        }
+        if self.hide_tcc_internal && is_tcc_internal(&entry.client) {
+            return false;
+        }
+        if let Some(source) = self.source
+            && SourceLabel::of(entry.is_system) != source
+        {
+            return false;
+        }
+        if let Some(auth) = self.auth
+            && AuthStatus::of(entry.auth_value) != auth
+        {
+            return false;
+        }
+        true
     }
+}
 
-    pub fn enable(&self, service: &str, client: &str) -> Result<String, TccError> {
-        let service_key = self.resolve_service_name(service)?;
-        self.check_root_for_write(&service_key, "enable", service, client)?;
+/// A raw row of the access table, as stored in the DB a write targets
+#[derive(Debug)]
+pub struct AccessRow {
+    pub service_raw: String,
+    pub client: String,
+    pub client_type: i32,
+    pub auth_value: i32,
+    pub auth_reason: i32,
+    pub auth_version: i32,
+    pub flags: Option<i32>,
+    pub last_modified: i64,
+    pub is_system: bool,
+}
 
-        let (conn, warning) = self.open_writable(&service_key)?;
-        if let Some(w) = &warning
-            && !self.suppress_warnings
-        {
-            eprintln!("{}", w);
-        }
+impl AccessRow {
+    /// The columns a write can change, in table order
+    fn changeable_columns(&self) -> [(&'static str, rusqlite::types::Value); 6] {
+        use rusqlite::types::Value;
+        [
+            ("client_type", Value::Integer(self.client_type.into())),
+            ("auth_value", Value::Integer(self.auth_value.into())),
+            ("auth_reason", Value::Integer(self.auth_reason.into())),
+            ("auth_version", Value::Integer(self.auth_version.into())),
+            (
+                "flags",
+                self.flags.map_or(Value::Null, |f| Value::Integer(f.into())),
+            ),
+            ("last_modified", Value::Integer(self.last_modified)),
+        ]
+    }
+}
 
-        let now = chrono::Utc::now().timestamp() - 978_307_200;
-        let updated = conn
-            .execute(
-                "UPDATE access SET auth_value = 2, last_modified = ?3 WHERE service = ?1 AND client = ?2",
-                rusqlite::params![service_key, client, now],
-            )
-            .map_err(|e| {
-                TccError::WriteFailed(format!(
-                    "Failed to enable: {}. Note: SIP may prevent TCC.db writes.",
-                    e
-                ))
-            })?;
+/// One column a write changed. `before`/`after` are `None` on the side
+/// where the row did not exist.
+#[derive(Debug, PartialEq)]
+pub struct ColumnChange {
+    pub column: &'static str,
+    pub before: Option<rusqlite::types::Value>,
+    pub after: Option<rusqlite::types::Value>,
+}
 
-        if updated == 0 {
-            Err(TccError::NotFound {
-                service: format!(
-                    "{}. Use `tcc grant` to insert a new entry",
-                    Self::service_display_name(&service_key)
-                ),
-                client: client.to_string(),
+/// The columns that differ between a row read before a write and the same
+/// row read back after it. A row that appeared or disappeared lists every
+/// column.
+pub fn diff_access_rows(
+    before: Option<&AccessRow>,
+    after: Option<&AccessRow>,
+) -> Vec<ColumnChange> {
+    let before = before.map(AccessRow::changeable_columns);
+    let after = after.map(AccessRow::changeable_columns);
+    let names = before
+        .as_ref()
+        .or(after.as_ref())
+        .map_or(Vec::new(), |cols| {
+            cols.iter().map(|(name, _)| *name).collect()
+        });
+    names
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, column)| {
+            let before = before.as_ref().map(|cols| cols[i].1.clone());
+            let after = after.as_ref().map(|cols| cols[i].1.clone());
+            (before != after).then_some(ColumnChange {
+                column,
+                before,
+                after,
             })
+        })
+        .collect()
+}
+
+/// An access row with every column of its table (csreq included), in table
+/// order, as `dump` read it or as it was just before a revoke or reset
+/// deleted it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawRow {
+    pub source: SourceLabel,
+    pub columns: RawColumns,
+}
+
+/// Column names and values of one access row, in table order
+pub type RawColumns = Vec<(String, rusqlite::types::Value)>;
+
+/// Row counts of a `restore`, or of what it would do with `--dry-run`
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoreOutcome {
+    pub restored: usize,
+    /// Rows left out, each reported as a warning with its reason
+    pub skipped: usize,
+}
+
+/// What an `export` wrote
+#[derive(Debug, PartialEq)]
+pub struct ExportOutcome {
+    pub rows: usize,
+    /// `entries_checksum` of the exported rows, before any redaction
+    pub checksum: String,
+}
+
+/// Rows of one DB that share the access table's composite primary key
+#[derive(Debug)]
+pub struct DuplicateGroup {
+    pub source: SourceLabel,
+    pub service_raw: String,
+    pub client: String,
+    pub client_type: i32,
+    /// `None` on schemas without `indirect_object_identifier`
+    pub indirect_object: Option<String>,
+    /// Rows sharing the key
+    pub rows: usize,
+    /// Rows deleted by the repair (0 when only reporting)
+    pub removed: usize,
+}
+
+/// Which database a row or path belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceLabel {
+    User,
+    System,
+}
+
+impl SourceLabel {
+    fn of(is_system: bool) -> Self {
+        if is_system {
+            SourceLabel::System
         } else {
-            Ok(format!(
-                "Enabled {} access for '{}'",
-                Self::service_display_name(&service_key),
-                client
-            ))
+            SourceLabel::User
         }
     }
 
-    pub fn disable(&self, service: &str, client: &str) -> Result<String, TccError> {
-        let service_key = self.resolve_service_name(service)?;
-        self.check_root_for_write(&service_key, "disable", service, client)?;
-
-        let (conn, warning) = self.open_writable(&service_key)?;
-        if let Some(w) = &warning
-            && !self.suppress_warnings
-        {
-            eprintln!("{}", w);
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SourceLabel::User => "user",
+            SourceLabel::System => "system",
         }
+    }
+}
 
-        let now = chrono::Utc::now().timestamp() - 978_307_200;
-        let updated = conn
-            .execute(
-                "UPDATE access SET auth_value = 0, last_modified = ?3 WHERE service = ?1 AND client = ?2",
-                rusqlite::params![service_key, client, now],
-            )
-            .map_err(|e| {
-                TccError::WriteFailed(format!(
-                    "Failed to disable: {}. Note: SIP may prevent TCC.db writes.",
-                    e
-                ))
-            })?;
+/// A targeted DB that could not be read while the others still were
+#[derive(Debug)]
+pub struct SourceFailure {
+    pub source: SourceLabel,
+    pub path: PathBuf,
+    pub message: String,
+}
 
-        if updated == 0 {
-            Err(TccError::NotFound {
-                service: Self::service_display_name(&service_key),
-                client: client.to_string(),
-            })
-        } else {
-            Ok(format!(
-                "Disabled {} access for '{}'",
-                Self::service_display_name(&service_key),
-                client
-            ))
+/// How many rows were read from one DB before any filter was applied
+#[derive(Debug)]
+pub struct SourceCount {
+    pub source: SourceLabel,
+    pub path: PathBuf,
+    pub rows: usize,
+}
+
+/// What a reset deleted, with per-DB problems that did not stop it.
+/// Displays as the full message, warnings included.
+#[derive(Debug)]
+pub struct ResetOutcome {
+    pub message: String,
+    pub warnings: Vec<String>,
+    pub deleted: usize,
+    /// DBs that had at least one row deleted
+    pub dbs: usize,
+    /// Rows deleted per service, for resets spanning several services
+    pub per_service: Vec<(Service, usize)>,
+    /// The deleted rows themselves, as they were before the reset
+    pub deleted_rows: Vec<RawRow>,
+}
+
+impl ResetOutcome {
+    pub fn new(message: String, deleted: usize, dbs: usize) -> Self {
+        ResetOutcome {
+            message,
+            warnings: Vec::new(),
+            deleted,
+            dbs,
+            per_service: Vec::new(),
+            deleted_rows: Vec::new(),
         }
     }
+}
 
-    pub fn reset(&self, service: &str, client: Option<&str>) -> Result<String, TccError> {
-        let service_key = self.resolve_service_name(service)?;
+impl fmt::Display for ResetOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for warning in &self.warnings {
+            write!(f, "\nWarning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
 
-        if let Some(c) = client {
-            // Delete specific client entry
-            self.check_root_for_write(&service_key, "reset", service, c)?;
+/// What `TccDb::list_measured` read, before any output
+#[derive(Debug)]
+pub struct MeasuredList {
+    pub entries: Vec<TccEntry>,
+    /// Targeted DBs that could not be read
+    pub failures: Vec<SourceFailure>,
+    /// Rows per readable DB before filtering
+    pub counts: Vec<SourceCount>,
+    /// Malformed rows skipped while reading
+    pub warnings: Vec<String>,
+}
 
-            let (conn, warning) = self.open_writable(&service_key)?;
-            if let Some(w) = &warning
-                && !self.suppress_warnings
-            {
-                eprintln!("{}", w);
-            }
+/// What `TccDb::list_entries` found, with every problem that did not stop
+/// it (skipped rows, unreadable DBs) as data rather than stderr output
+#[derive(Debug)]
+pub struct ListResult {
+    pub entries: Vec<TccEntry>,
+    pub warnings: Vec<String>,
+}
 
-            let deleted = conn
-                .execute(
-                    "DELETE FROM access WHERE service = ?1 AND client = ?2",
-                    rusqlite::params![service_key, c],
-                )
-                .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?;
+/// Entries yielded by `TccDb::list_iter`, batched per source: each DB is
+/// read in full when the previous batch runs out, then its rows are
+/// filtered and yielded from memory
+pub struct BatchedListIter<'a> {
+    db: &'a TccDb,
+    filter: &'a ListFilter<'a>,
+    paths: std::vec::IntoIter<(&'a Path, SourceLabel, bool)>,
+    /// The rows of the DB read last, not yet yielded
+    batch: std::vec::IntoIter<TccEntry>,
+    writable: HashMap<PathBuf, bool>,
+    warnings: Vec<String>,
+}
 
-            if deleted == 0 {
-                Err(TccError::NotFound {
-                    service: Self::service_display_name(&service_key),
-                    client: c.to_string(),
-                })
-            } else {
-                Ok(format!(
-                    "Reset {} entry for '{}'",
-                    Self::service_display_name(&service_key),
-                    c
-                ))
-            }
-        } else {
-            // Delete all entries for this service
-            // For default target, try to reset in both DBs
-            let mut total_deleted = 0usize;
-            let mut errors = Vec::new();
+impl BatchedListIter<'_> {
+    /// Skipped rows and unreadable DBs met so far; clears them
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
 
-            let paths: Vec<(&Path, &str)> = match self.target {
-                DbTarget::User => vec![(&self.user_db_path, "user")],
-                DbTarget::Default => vec![
-                    (&self.user_db_path, "user"),
-                    (&self.system_db_path, "system"),
-                ],
-            };
+impl Iterator for BatchedListIter<'_> {
+    type Item = TccEntry;
 
-            for (db_path, label) in paths {
-                if !db_path.exists() {
-                    continue;
-                }
-                // Check root for system DB writes
-                if db_path == self.system_db_path && !nix_is_root() {
-                    return Err(TccError::NeedsRoot {
-                        message: format!(
-                            "Resetting all '{}' entries requires the system TCC database.\n\
-                             Run with sudo: sudo tcc reset {}",
-                            Self::service_display_name(&service_key),
-                            service
-                        ),
-                    });
-                }
-                match Connection::open(db_path) {
-                    Ok(conn) => {
-                        if let Err(e) = Self::validate_schema(&conn) {
-                            errors.push(format!("{} DB: {}", label, e));
-                            continue;
-                        }
-                        match conn.execute(
-                            "DELETE FROM access WHERE service = ?1",
-                            rusqlite::params![service_key],
-                        ) {
-                            Ok(n) => total_deleted += n,
-                            Err(e) => errors.push(format!("{} DB: {}", label, e)),
-                        }
-                    }
-                    Err(e) => errors.push(format!("{} DB: {}", label, e)),
+    fn next(&mut self) -> Option<TccEntry> {
+        loop {
+            for entry in self.batch.by_ref() {
+                if self.filter.matches(&entry)
+                    && (!self.filter.only_modifiable
+                        || self.db.is_modifiable(&entry, &mut self.writable))
+                {
+                    return Some(entry);
                 }
             }
-
-            if total_deleted == 0 && !errors.is_empty() {
-                Err(TccError::WriteFailed(format!(
-                    "Failed to reset: {}",
-                    errors.join("; ")
-                )))
-            } else {
-                let mut msg = format!(
-                    "Reset all {} entries ({} deleted)",
-                    Self::service_display_name(&service_key),
-                    total_deleted
-                );
-                for e in errors {
-                    msg.push_str(&format!("\nWarning: {}", e));
+            let (path, _, is_system) = self.paths.next()?;
+            match TccDb::read_db(path, is_system, &self.db.schemas) {
+                Ok((entries, mut warnings)) => {
+                    self.warnings.append(&mut warnings);
+                    self.batch = entries.into_iter();
                 }
-                Ok(msg)
+                Err(e) => self.warnings.push(e.to_string()),
             }
         }
     }
+}
 
-    pub fn info(&self) -> Vec<String> {
-        let mut lines = Vec::new();
+/// The kind of operation a set of DB paths is being selected for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    /// Reading entries (list)
+    Read,
+    /// Writing a single entry for the given service
+    Write(Service),
+    /// Bulk delete for a service across every targeted DB
+    Reset,
+    /// Describing the DBs themselves (info)
+    Info,
+}
 
-        // macOS version — use absolute path for defensive coding
-        let macos_ver = Command::new("/usr/bin/sw_vers")
-            .arg("-productVersion")
-            .output()
-            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-            .unwrap_or_else(|_| "unknown".to_string());
-        lines.push(format!("macOS version: {}", macos_ver));
-
-        // SIP status — use absolute path for defensive coding
-        let sip = Command::new("/usr/bin/csrutil")
-            .arg("status")
-            .output()
-            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-            .unwrap_or_else(|_| "unknown (csrutil not available)".to_string());
-        lines.push(format!("SIP status: {}", sip));
+/// How to treat the access table schema digest before writing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SchemaPolicy {
+    /// Warn on an unknown digest and proceed (default)
+    Warn,
+    /// Skip validation entirely and proceed as if the digest matched
+    AssumeKnown,
+    /// Refuse to write when the digest is unknown
+    Strict,
+}
 
-        lines.push(String::new());
+#[derive(Clone, Copy, PartialEq)]
+pub enum DbTarget {
+    /// Use both DBs for reads, system for writes (default)
+    Default,
+    /// User DB only
+    User,
+    /// One explicit DB file for every read and write (see `TccDb::with_file`)
+    File,
+}
 
-        // DB info
-        for (label, path) in [
-            ("User DB", &self.user_db_path),
-            ("System DB", &self.system_db_path),
-        ] {
-            lines.push(format!("{}: {}", label, path.display()));
-            if path.exists() {
-                let readable =
-                    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).is_ok();
-                let writable =
-                    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE).is_ok();
-                lines.push(format!(
-                    "  Readable: {}",
-                    if readable { "yes" } else { "no" }
-                ));
-                lines.push(format!(
-                    "  Writable: {}",
-                    if writable { "yes" } else { "no" }
-                ));
+pub struct TccDb {
+    user_db_path: PathBuf,
+    system_db_path: PathBuf,
+    target: DbTarget,
+    /// Warnings raised by operations, for the caller to `take_warnings`
+    warnings: RefCell<Vec<String>>,
+    schema_policy: SchemaPolicy,
+    /// Reads fail instead of returning nothing when the user DB is absent
+    require_user_db: bool,
+    /// Grants copy a csreq stored for another service of the same client
+    merge_csreq: bool,
+    /// Grants may overwrite an existing denied or limited entry
+    force_grant: bool,
+    /// How often a single-row write is retried after finding the user DB locked
+    lock_retries: u32,
+    /// How long SQLite itself waits on a locked DB before reporting it busy
+    busy_timeout: Duration,
+    schemas: SchemaCache,
+}
 
-                // Schema digest
-                if readable
-                    && let Ok(conn) =
-                        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-                    && let Ok(sql) = conn.query_row::<String, _, _>(
-                        "SELECT sql FROM sqlite_master WHERE name='access' AND type='table'",
-                        [],
-                        |row| row.get(0),
-                    )
-                {
-                    let mut hasher = sha1_smol::Sha1::new();
-                    hasher.update(sql.as_bytes());
-                    let hex = hasher.digest().to_string();
-                    let short = &hex[..10];
-                    let known = if KNOWN_DIGESTS.contains(&short) {
-                        "known"
-                    } else {
-                        "UNKNOWN"
-                    };
-                    lines.push(format!("  Schema digest: {} ({})", short, known));
-                }
-            } else {
-                lines.push("  Not found".to_string());
-            }
-            lines.push(String::new());
-        }
+/// Pause between attempts when a write is retried on a locked user DB
+const LOCK_RETRY_PAUSE: Duration = Duration::from_secs(1);
 
-        lines
-    }
+/// SQLite's own default, which rusqlite also applies to new connections
+const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn is_lock_error(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
 }
 
-pub fn nix_is_root() -> bool {
-    unsafe { libc::geteuid() == 0 }
+/// The parts of a DB's access table that reads and writes adapt to
+#[derive(Clone, Debug, Default)]
+struct AccessSchema {
+    /// Column names, in table order
+    columns: Vec<String>,
+    /// `access_schema_digest`; `None` when the table is missing
+    digest: Option<String>,
 }
 
-/// Truncate a client path to just the binary name
-pub fn compact_client(client: &str) -> String {
-    if client.starts_with('/') {
-        // It's a path — extract just the filename
-        std::path::Path::new(client)
-            .file_name()
-            .map(|f| f.to_string_lossy().to_string())
-            .unwrap_or_else(|| client.to_string())
-    } else {
-        client.to_string()
-    }
+#[cfg(test)]
+thread_local! {
+    /// How many times this thread has run `AccessSchema::probe`
+    static SCHEMA_PROBES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
 }
 
-/// Map auth_value to a display string
+impl AccessSchema {
+    fn probe(conn: &Connection) -> Self {
+        #[cfg(test)]
+        SCHEMA_PROBES.with(|n| n.set(n.get() + 1));
+        Self {
+            columns: conn
+                .prepare("SELECT name FROM pragma_table_info('access')")
+                .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+                .unwrap_or_default(),
+            digest: access_schema_digest(conn),
+        }
+    }
+}
+
+/// Access schemas already probed this run, per DB path, so a command that
+/// opens the same DB several times reads its schema once. A DB without an
+/// access table is not cached and is probed again on the next open.
+#[derive(Debug, Default)]
+struct SchemaCache(RefCell<HashMap<PathBuf, AccessSchema>>);
+
+impl SchemaCache {
+    fn get(&self, path: &Path, conn: &Connection) -> AccessSchema {
+        if let Some(schema) = self.0.borrow().get(path) {
+            return schema.clone();
+        }
+        let schema = AccessSchema::probe(conn);
+        if schema.digest.is_some() {
+            self.0
+                .borrow_mut()
+                .insert(path.to_path_buf(), schema.clone());
+        }
+        schema
+    }
+}
+
+impl TccDb {
+    pub fn new(target: DbTarget) -> Result<Self, TccError> {
+        let home = dirs::home_dir().ok_or(TccError::HomeDirNotFound)?;
+        Ok(Self::with_home(target, &home))
+    }
+
+    /// Build a DB handle whose user DB lives under `home` instead of the current user's home
+    pub fn with_home(target: DbTarget, home: &Path) -> Self {
+        Self {
+            user_db_path: home.join("Library/Application Support/com.apple.TCC/TCC.db"),
+            system_db_path: PathBuf::from("/Library/Application Support/com.apple.TCC/TCC.db"),
+            target,
+            warnings: RefCell::default(),
+            schema_policy: SchemaPolicy::Warn,
+            require_user_db: false,
+            merge_csreq: false,
+            force_grant: false,
+            lock_retries: 0,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            schemas: SchemaCache::default(),
+        }
+    }
+
+    /// Build a DB handle for one explicit file, such as a copy taken off
+    /// another machine or out of a backup. Every read and write goes to it,
+    /// whatever the service, and its rows are labelled `user`.
+    pub fn with_file(path: PathBuf) -> Self {
+        Self {
+            user_db_path: path.clone(),
+            system_db_path: path,
+            target: DbTarget::File,
+            warnings: RefCell::default(),
+            schema_policy: SchemaPolicy::Warn,
+            require_user_db: false,
+            merge_csreq: false,
+            force_grant: false,
+            lock_retries: 0,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            schemas: SchemaCache::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_paths(user: PathBuf, system: PathBuf, target: DbTarget) -> Self {
+        Self {
+            user_db_path: user,
+            system_db_path: system,
+            target,
+            warnings: RefCell::default(),
+            schema_policy: SchemaPolicy::Warn,
+            require_user_db: false,
+            merge_csreq: false,
+            force_grant: false,
+            lock_retries: 0,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            schemas: SchemaCache::default(),
+        }
+    }
+
+    /// Hold a warning for `take_warnings`; the library never prints one
+    fn warn(&self, message: String) {
+        self.warnings.borrow_mut().push(message);
+    }
+
+    /// Every warning raised so far, in the order they were raised; clears them
+    pub fn take_warnings(&self) -> Vec<String> {
+        self.warnings.take()
+    }
+
+    pub fn set_schema_policy(&mut self, schema_policy: SchemaPolicy) {
+        self.schema_policy = schema_policy;
+    }
+
+    /// For a user DB path the caller chose explicitly: a missing file is
+    /// then most likely a typo, so reads report it instead of listing nothing.
+    pub fn set_require_user_db(&mut self, require_user_db: bool) {
+        self.require_user_db = require_user_db;
+    }
+
+    /// Let grants fill a missing csreq from another row of the same client,
+    /// so the new entry carries a code requirement without running codesign
+    pub fn set_merge_csreq(&mut self, merge_csreq: bool) {
+        self.merge_csreq = merge_csreq;
+    }
+
+    /// Let grants set an existing entry that is not granted to granted.
+    /// Without it such a grant fails with `EntryExists`, leaving the entry
+    /// as it was chosen (`enable` is the way to change it).
+    pub fn set_force_grant(&mut self, force_grant: bool) {
+        self.force_grant = force_grant;
+    }
+
+    /// Retry grant/revoke/enable/disable up to this many times while the
+    /// user DB is locked by tccd, pausing between attempts
+    pub fn set_lock_retries(&mut self, lock_retries: u32) {
+        self.lock_retries = lock_retries;
+    }
+
+    /// Replace the DB paths with their canonical form, following any symlinks
+    /// (e.g. a relocated `Library/Application Support`). Paths that cannot be
+    /// resolved are left unchanged.
+    pub fn resolve_symlinks(&mut self) {
+        for path in [&mut self.user_db_path, &mut self.system_db_path] {
+            if let Ok(real) = std::fs::canonicalize(&*path) {
+                *path = real;
+            }
+        }
+    }
+
+    /// Select the DB paths an operation should touch for the current target.
+    /// Every read/write/reset/info path decision goes through here.
+    pub fn target_paths(&self, op: Op) -> Vec<(&Path, SourceLabel, bool)> {
+        let user = (self.user_db_path.as_path(), SourceLabel::User, false);
+        let system = (self.system_db_path.as_path(), SourceLabel::System, true);
+        match (op, self.target) {
+            (_, DbTarget::File) => vec![user],
+            (Op::Info, _) => vec![user, system],
+            (_, DbTarget::User) => vec![user],
+            (Op::Read | Op::Reset, DbTarget::Default) => vec![user, system],
+            (Op::Write(service), DbTarget::Default) => {
+                if service.is_system() {
+                    vec![system]
+                } else {
+                    vec![user]
+                }
+            }
+        }
+    }
+
+    /// The access schema digest of every targeted DB that exists
+    pub fn schema_digests(&self) -> Vec<SchemaDigest> {
+        self.target_paths(Op::Read)
+            .into_iter()
+            .filter(|(path, _, _)| path.exists())
+            .map(|(path, source, _)| SchemaDigest {
+                source,
+                path: path.to_path_buf(),
+                digest: Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    .ok()
+                    .and_then(|conn| self.schemas.get(path, &conn).digest),
+            })
+            .collect()
+    }
+
+    /// Fail unless every existing targeted DB has exactly the `expected`
+    /// digest (case-insensitive). At least one DB must exist.
+    pub fn verify_schema(&self, expected: &str) -> Result<Vec<SchemaDigest>, TccError> {
+        let expected = expected.trim().to_lowercase();
+        let digests = self.schema_digests();
+        if digests.is_empty() {
+            let path = self.target_paths(Op::Read)[0].0.to_path_buf();
+            return Err(TccError::DbMissing(path));
+        }
+        if let Some(bad) = digests
+            .iter()
+            .find(|d| d.digest.as_deref() != Some(expected.as_str()))
+        {
+            return Err(TccError::SchemaMismatch {
+                path: bad.path.clone(),
+                expected,
+                found: bad.digest.clone(),
+            });
+        }
+        Ok(digests)
+    }
+
+    /// Every row of a TCC.db outside the targeted ones (a copy, a snapshot,
+    /// another machine's DB). A missing file is an error unless `allow_missing`.
+    pub fn read_other_db(path: &Path, allow_missing: bool) -> Result<Vec<TccEntry>, TccError> {
+        check_db_present(path, !allow_missing)?;
+        Self::read_db(path, false, &SchemaCache::default()).map(|(entries, _)| entries)
+    }
+
+    /// Convert a raw `last_modified` value to Unix seconds (0 stays 0).
+    pub(crate) fn normalize_timestamp(ts: i64) -> i64 {
+        if ts == 0 {
+            return 0;
+        }
+        // macOS TCC uses CoreData timestamps (seconds since 2001-01-01) or Unix timestamps.
+        if ts < 1_000_000_000 {
+            ts + 978_307_200
+        } else {
+            ts
+        }
+    }
+
+    pub(crate) fn format_timestamp(ts: i64) -> String {
+        if ts == 0 {
+            return "N/A".to_string();
+        }
+        let unix_ts = Self::normalize_timestamp(ts);
+
+        match Local.timestamp_opt(unix_ts, 0) {
+            chrono::LocalResult::Single(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+            _ => format!("{}", ts),
+        }
+    }
+
+    pub(crate) fn service_display_name(raw: &str) -> String {
+        SERVICE_MAP
+            .get(raw)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| raw.strip_prefix("kTCCService").unwrap_or(raw).to_string())
+    }
+
+    /// The entries of one DB, plus a warning for each malformed row skipped
+    fn read_db(
+        path: &Path,
+        is_system: bool,
+        schemas: &SchemaCache,
+    ) -> Result<(Vec<TccEntry>, Vec<String>), TccError> {
+        check_not_broken_symlink(path)?;
+        if !path.exists() {
+            return Ok((vec![], vec![]));
+        }
+
+        let conn =
+            Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|e| {
+                TccError::DbOpen {
+                    path: path.to_path_buf(),
+                    source: e.to_string(),
+                }
+            })?;
+
+        // Older schemas lack the later columns; select NULL in their place
+        let columns = schemas.get(path, &conn).columns;
+        let optional = |name: &'static str| {
+            if columns.iter().any(|c| c == name) {
+                name
+            } else {
+                "NULL"
+            }
+        };
+        let extra_cols = format!(
+            "{}, {}, {}, {}, {}, {}, {}",
+            optional("boot_uuid"),
+            optional("pid"),
+            optional("last_reminded"),
+            optional("csreq"),
+            optional("client_type"),
+            optional("auth_reason"),
+            optional("indirect_object_identifier")
+        );
+        let query = format!(
+            "SELECT service, client, auth_value, \
+             COALESCE(last_modified, 0) as modified, {} \
+             FROM access",
+            extra_cols
+        );
+
+        let result = conn.prepare(&query);
+        let mut stmt = match result {
+            Ok(s) => s,
+            Err(_) => {
+                let fallback = format!(
+                    "SELECT service, client, auth_value, 0 as modified, {} FROM access",
+                    extra_cols
+                );
+                conn.prepare(&fallback).map_err(|e| {
+                    TccError::QueryFailed(format!("Query failed on {}: {}", path.display(), e))
+                })?
+            }
+        };
+
+        let rows = stmt
+            .query_map([], |row| {
+                let service_raw: String = row.get(0)?;
+                let client: String = row.get(1)?;
+                let auth_value: i32 = row.get(2)?;
+                let modified: i64 = row.get(3)?;
+                let boot_uuid: Option<String> = row.get(4)?;
+                let pid: Option<i64> = row.get(5)?;
+                let last_reminded: Option<i64> = row.get(6)?;
+                let csreq: Option<Vec<u8>> = row.get(7)?;
+                let client_type: Option<i32> = row.get(8)?;
+                let auth_reason: Option<i32> = row.get(9)?;
+                let indirect_object: Option<String> = row.get(10)?;
+
+                Ok(TccEntry {
+                    service_display: Self::service_display_name(&service_raw),
+                    service_raw,
+                    client_type: client_type.unwrap_or_else(|| client_type_of(&client)),
+                    client,
+                    auth_value,
+                    auth_reason: auth_reason.unwrap_or(0),
+                    last_modified: Self::format_timestamp(modified),
+                    last_modified_ts: Self::normalize_timestamp(modified),
+                    is_system,
+                    boot_uuid,
+                    pid,
+                    last_reminded: last_reminded.map(Self::format_timestamp),
+                    csreq,
+                    indirect_object: indirect_object.filter(|t| t != "UNUSED"),
+                })
+            })
+            .map_err(|e| {
+                TccError::QueryFailed(format!("Query error on {}: {}", path.display(), e))
+            })?;
+
+        let mut entries = Vec::new();
+        let mut warnings = Vec::new();
+        for result in rows {
+            match result {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warnings.push(format!(
+                    "skipping malformed row in {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+
+        Ok((entries, warnings))
+    }
+
+    pub fn list(
+        &self,
+        client_filter: Option<&str>,
+        service_filter: Option<&str>,
+    ) -> Result<Vec<TccEntry>, TccError> {
+        self.list_filtered(&ListFilter {
+            client: client_filter,
+            service: service_filter,
+            ..ListFilter::default()
+        })
+    }
+
+    pub fn list_filtered(&self, filter: &ListFilter) -> Result<Vec<TccEntry>, TccError> {
+        let ListResult { entries, warnings } = self.list_entries(filter)?;
+        for warning in warnings {
+            self.warn(warning);
+        }
+        Ok(entries)
+    }
+
+    /// Like `list_filtered`, but returns its warnings instead of holding
+    /// them on the DB: malformed rows that were skipped and DBs that could
+    /// not be read, in that order.
+    pub fn list_entries(&self, filter: &ListFilter) -> Result<ListResult, TccError> {
+        let MeasuredList {
+            entries,
+            failures,
+            mut warnings,
+            ..
+        } = self.list_measured(filter)?;
+        warnings.extend(failures.into_iter().map(|failure| failure.message));
+        Ok(ListResult { entries, warnings })
+    }
+
+    /// Copy the access rows of every readable targeted DB into a new SQLite
+    /// file at `out`: one `access` table with the columns the sources have,
+    /// plus `source` (`user`/`system`). Sources are attached read-only; one
+    /// that cannot be read is skipped with a warning. With a `redactor`, the
+    /// client and Automation target are redacted and the code-signing blobs,
+    /// which name them, are cleared. Refuses to overwrite `out`.
+    pub fn export_sqlite(
+        &self,
+        out: &Path,
+        redactor: Option<&Redactor>,
+    ) -> Result<ExportOutcome, TccError> {
+        if out.symlink_metadata().is_ok() {
+            return Err(TccError::WriteFailed(format!(
+                "{} already exists, not overwriting it",
+                out.display()
+            )));
+        }
+        let result = self.export_sqlite_into(out, redactor);
+        if result.is_err() {
+            let _ = std::fs::remove_file(out);
+        }
+        result
+    }
+
+    fn export_sqlite_into(
+        &self,
+        out: &Path,
+        redactor: Option<&Redactor>,
+    ) -> Result<ExportOutcome, TccError> {
+        let failed = |e: rusqlite::Error| TccError::WriteFailed(format!("Export failed: {}", e));
+        let conn = Connection::open(out).map_err(|e| TccError::DbOpen {
+            path: out.to_path_buf(),
+            source: e.to_string(),
+        })?;
+
+        let mut columns: Option<Vec<String>> = None;
+        let mut rows = 0usize;
+        for (path, source, _) in self.target_paths(Op::Read) {
+            if !path.exists() {
+                continue;
+            }
+            if let Err(e) = conn.execute("ATTACH DATABASE ?1 AS src", [read_only_uri(path)]) {
+                self.warn(format!("Skipped {}: {}", path.display(), e));
+                continue;
+            }
+            let copied = copy_attached_access(&conn, source, &mut columns);
+            conn.execute_batch("DETACH DATABASE src").map_err(failed)?;
+            match copied {
+                Ok(copied) => rows += copied,
+                Err(e) => self.warn(format!("Skipped {}: {}", path.display(), e)),
+            }
+        }
+
+        let Some(columns) = columns else {
+            return Err(TccError::WriteFailed(
+                "Export failed: no readable TCC database to copy".to_string(),
+            ));
+        };
+        let checksum = exported_checksum(&conn, &columns).map_err(failed)?;
+        if let Some(redactor) = redactor {
+            redact_exported_access(&conn, &columns, redactor).map_err(failed)?;
+        }
+        Ok(ExportOutcome { rows, checksum })
+    }
+
+    /// Every access row of every targeted DB that exists, all columns as
+    /// stored, in table order. Read-only; NULLs stay NULL and services are
+    /// not mapped to display names. Like `list`, an unreadable DB is
+    /// skipped with a warning.
+    pub fn dump(&self) -> Result<Vec<RawRow>, TccError> {
+        let mut rows = Vec::new();
+        for (path, source, is_system) in self.target_paths(Op::Read) {
+            if !is_system {
+                check_db_present(path, self.require_user_db)?;
+            }
+            let read = (|| {
+                check_not_broken_symlink(path)?;
+                if !path.exists() {
+                    return Ok(Vec::new());
+                }
+                let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    .map_err(|e| TccError::DbOpen {
+                        path: path.to_path_buf(),
+                        source: e.to_string(),
+                    })?;
+                select_raw_rows(&conn, source, "1", &[]).map_err(|e| {
+                    TccError::QueryFailed(format!("Query failed on {}: {}", path.display(), e))
+                })
+            })();
+            match read {
+                Ok(mut read) => rows.append(&mut read),
+                Err(e) => self.warn(e.to_string()),
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Re-insert the rows of a `dump` file with INSERT OR REPLACE. Each row
+    /// goes to the DB `restore_db_path` picks, behind the same root check
+    /// (for every DB before the first write) and schema validation. Rows with an unknown service key,
+    /// a column the target table lacks, or a BLOB that is not base64 are
+    /// skipped with a warning. With `dry_run` every check runs, read-only,
+    /// and nothing is written; the system DB then only warns about root.
+    pub fn restore(&self, file: &Path, dry_run: bool) -> Result<RestoreOutcome, TccError> {
+        let rows = read_dump(file)?;
+        let mut outcome = RestoreOutcome::default();
+        let skip = |outcome: &mut RestoreOutcome, n: usize, reason: String| {
+            self.warn(format!("skipping dump row {}: {}", n, reason));
+            outcome.skipped += 1;
+        };
+
+        // Rows with their 1-based position in the file
+        type Numbered = Vec<(usize, RawRow)>;
+        // (DB, whether it is the system DB, rows) per DB written to
+        let mut groups: Vec<(&Path, bool, Numbered)> = Vec::new();
+        for (i, (source, columns)) in rows.into_iter().enumerate() {
+            let key = match columns.iter().find(|(name, _)| name == "service") {
+                Some((_, rusqlite::types::Value::Text(key))) => key.clone(),
+                _ => {
+                    skip(&mut outcome, i + 1, "no text `service` column".to_string());
+                    continue;
+                }
+            };
+            let svc = match Service::try_from(key.as_str()) {
+                Ok(svc) if svc.key() == key => svc,
+                _ => {
+                    skip(
+                        &mut outcome,
+                        i + 1,
+                        format!("unknown service key '{}'", key),
+                    );
+                    continue;
+                }
+            };
+            let (path, is_system) = self.restore_db_path(svc, source);
+            let row = RawRow {
+                source: if is_system {
+                    SourceLabel::System
+                } else {
+                    SourceLabel::User
+                },
+                columns,
+            };
+            match groups.iter_mut().find(|(p, _, _)| *p == path) {
+                Some((_, _, group)) => group.push((i + 1, row)),
+                None => groups.push((path, is_system, vec![(i + 1, row)])),
+            }
+        }
+
+        // Every root check before the first write, so a restore is never
+        // left half applied
+        for (path, is_system, group) in &groups {
+            if self.needs_root(path, *is_system) {
+                let message = self.root_message(
+                    path,
+                    format!(
+                        "{} rows of {} are for the system TCC database.\n\
+                     Run with sudo: sudo tcc restore {}",
+                        group.len(),
+                        file.display(),
+                        file.display()
+                    ),
+                );
+                if !dry_run {
+                    return Err(TccError::NeedsRoot { message });
+                }
+                self.warn(message.replace('\n', " "));
+            }
+        }
+
+        for (path, is_system, group) in groups {
+            check_not_broken_symlink(path)?;
+            let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| TccError::DbOpen {
+                    path: path.to_path_buf(),
+                    source: e.to_string(),
+                })?;
+            if let Some(w) = self.validate_schema(path, &conn)? {
+                self.warn(w);
+            }
+            let columns = self.schemas.get(path, &conn).columns;
+            let blob_columns = blob_columns(&conn).map_err(|e| {
+                TccError::QueryFailed(format!("Query failed on {}: {}", path.display(), e))
+            })?;
+            drop(conn);
+
+            let mut ready = Vec::new();
+            for (n, row) in group {
+                match restorable_row(row, &columns, &blob_columns) {
+                    Ok(row) => ready.push(row),
+                    Err(reason) => skip(&mut outcome, n, reason),
+                }
+            }
+            if !dry_run && !ready.is_empty() {
+                self.retry_locked(|| self.insert_raw_rows(path, is_system, &ready))?;
+            }
+            outcome.restored += ready.len();
+        }
+        Ok(outcome)
+    }
+
+    /// Where `restore` puts a row: the DB its dump `source` names, when both
+    /// DBs are targeted; else, or without a `source`, where a write to `svc` goes
+    fn restore_db_path(&self, svc: Service, source: Option<SourceLabel>) -> (&Path, bool) {
+        match (self.target, source) {
+            (DbTarget::Default, Some(SourceLabel::User)) => (&self.user_db_path, false),
+            (DbTarget::Default, Some(SourceLabel::System)) => (&self.system_db_path, true),
+            _ => self.write_db_path(svc),
+        }
+    }
+
+    fn insert_raw_rows(
+        &self,
+        path: &Path,
+        is_system: bool,
+        rows: &[RawRow],
+    ) -> Result<(), TccError> {
+        let (mut conn, _) = self.open_writable_at(path, is_system)?;
+        let write_err = |e: rusqlite::Error| {
+            if !is_system && is_lock_error(&e) {
+                TccError::DbLocked(path.to_path_buf())
+            } else {
+                TccError::WriteFailed(format!(
+                    "Failed to restore: {}. Note: SIP may prevent TCC.db writes.",
+                    e
+                ))
+            }
+        };
+        let tx = conn.transaction().map_err(write_err)?;
+        for row in rows {
+            let names: Vec<&str> = row.columns.iter().map(|(n, _)| n.as_str()).collect();
+            let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO access ({}) VALUES ({})",
+                    names.join(", "),
+                    placeholders.join(", ")
+                ),
+                rusqlite::params_from_iter(row.columns.iter().map(|(_, v)| v)),
+            )
+            .map_err(write_err)?;
+        }
+        tx.commit().map_err(write_err)
+    }
+
+    /// Per-source counterpart of `list_filtered`: yields matching entries in
+    /// read order (user DB, then system DB) without sorting them or
+    /// collecting the whole listing. Each DB is read into memory as one
+    /// batch, since a row cursor cannot outlive its statement, and is not
+    /// opened until the previous batch is exhausted, so at most one DB's
+    /// rows are held at a time. Unreadable DBs are skipped with a warning,
+    /// kept on the iterator for `BatchedListIter::take_warnings`;
+    /// `filter.read_order` is implied.
+    pub fn list_iter<'a>(
+        &'a self,
+        filter: &'a ListFilter<'a>,
+    ) -> Result<BatchedListIter<'a>, TccError> {
+        let paths = self.target_paths(Op::Read);
+        for (path, _, _) in paths.iter().filter(|(_, _, is_system)| !is_system) {
+            check_db_present(path, self.require_user_db)?;
+        }
+        Ok(BatchedListIter {
+            db: self,
+            filter,
+            paths: paths.into_iter(),
+            batch: Vec::new().into_iter(),
+            writable: HashMap::new(),
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Whether a row lives in the DB writes to its service would go to, and
+    /// that DB is writable from this session. `writable` caches per path.
+    fn is_modifiable(&self, entry: &TccEntry, writable: &mut HashMap<PathBuf, bool>) -> bool {
+        // Services the CLI cannot resolve cannot be written either
+        let Ok(service) = Service::try_from(entry.service_raw.as_str()) else {
+            return false;
+        };
+        let (path, is_system) = self.write_db_path(service);
+        is_system == entry.is_system
+            && *writable
+                .entry(path.to_path_buf())
+                .or_insert_with(|| Self::can_write_db(path, is_system))
+    }
+
+    /// Like `list_entries`, but keeps unreadable DBs apart from the skipped
+    /// rows and reports how many rows each readable DB held before filtering.
+    pub fn list_measured(&self, filter: &ListFilter) -> Result<MeasuredList, TccError> {
+        let mut entries = Vec::new();
+        let mut warnings = Vec::new();
+        let mut failures = Vec::new();
+        let mut counts = Vec::new();
+
+        for (path, source, is_system) in self.target_paths(Op::Read) {
+            if !is_system {
+                check_db_present(path, self.require_user_db)?;
+            }
+            match Self::read_db(path, is_system, &self.schemas) {
+                Ok((mut e, mut skipped)) => {
+                    warnings.append(&mut skipped);
+                    counts.push(SourceCount {
+                        source,
+                        path: path.to_path_buf(),
+                        rows: e.len(),
+                    });
+                    entries.append(&mut e);
+                }
+                Err(e) => failures.push(SourceFailure {
+                    source,
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        entries.retain(|e| filter.matches(e));
+        if filter.only_modifiable {
+            let mut writable: HashMap<PathBuf, bool> = HashMap::new();
+            entries.retain(|e| self.is_modifiable(e, &mut writable));
+        }
+
+        if !filter.read_order {
+            entries.sort_by(|a, b| {
+                let by_key = match filter.sort {
+                    SortKey::Service => std::cmp::Ordering::Equal,
+                    SortKey::Client => a.client.cmp(&b.client),
+                    SortKey::Status => a.auth_value.cmp(&b.auth_value),
+                    SortKey::Modified => a.last_modified_ts.cmp(&b.last_modified_ts),
+                    SortKey::Source => a.is_system.cmp(&b.is_system),
+                };
+                let order = by_key
+                    .then(a.service_display.cmp(&b.service_display))
+                    .then(a.client.cmp(&b.client));
+                if filter.reverse {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+
+        Ok(MeasuredList {
+            entries,
+            failures,
+            counts,
+            warnings,
+        })
+    }
+
+    /// Look up the entry for an exact service/client pair across the targeted DBs
+    pub fn check(&self, service: &str, client: &str) -> Result<Option<TccEntry>, TccError> {
+        let svc = Service::try_from(service)?;
+        let filter = ListFilter::default();
+        let mut entries = self.list_iter(&filter)?;
+        let found = entries.find(|e| e.service_raw == svc.key() && e.client == client);
+        for warning in entries.take_warnings() {
+            self.warn(warning);
+        }
+        Ok(found)
+    }
+
+    /// Like `check`, but a missing pair is a `NotFound` error
+    pub fn get(&self, service: &str, client: &str) -> Result<TccEntry, TccError> {
+        let svc = Service::try_from(service)?;
+        self.check(svc.key(), client)?
+            .ok_or_else(|| TccError::NotFound {
+                service: svc.display_name().to_string(),
+                client: client.to_string(),
+            })
+    }
+
+    pub fn resolve_service_name(&self, input: &str) -> Result<String, TccError> {
+        Service::try_from(input).map(|service| service.key().to_string())
+    }
+
+    /// Determine the target DB path for a write operation
+    fn write_db_path(&self, service: Service) -> (&Path, bool) {
+        let (path, _, is_system) = self.target_paths(Op::Write(service))[0];
+        (path, is_system)
+    }
+
+    /// Read the row a write for this service/client would touch, from the
+    /// DB writes go to. `Ok(None)` when there is no such row.
+    pub fn read_access_row(
+        &self,
+        service: &str,
+        client: &str,
+        target: Option<&str>,
+    ) -> Result<Option<AccessRow>, TccError> {
+        let svc = Service::try_from(service)?;
+        let (path, is_system) = self.write_db_path(svc);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let conn =
+            Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|e| {
+                TccError::DbOpen {
+                    path: path.to_path_buf(),
+                    source: e.to_string(),
+                }
+            })?;
+        let target_match = if target.is_some() {
+            " AND indirect_object_identifier = ?3"
+        } else {
+            " AND ?3 IS NULL"
+        };
+        let row = conn.query_row(
+            &format!(
+                "SELECT service, client, client_type, auth_value, auth_reason, auth_version, \
+                 flags, COALESCE(last_modified, 0) FROM access WHERE service = ?1 AND client = ?2{}",
+                target_match
+            ),
+            rusqlite::params![svc.key(), client, target],
+            |row| {
+                Ok(AccessRow {
+                    service_raw: row.get(0)?,
+                    client: row.get(1)?,
+                    client_type: row.get(2)?,
+                    auth_value: row.get(3)?,
+                    auth_reason: row.get(4)?,
+                    auth_version: row.get(5)?,
+                    flags: row.get(6)?,
+                    last_modified: row.get(7)?,
+                    is_system,
+                })
+            },
+        );
+        match row {
+            Ok(row) => Ok(Some(row)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(TccError::QueryFailed(format!(
+                "Query failed on {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /// Whether this session could write to the DB at `path` right now:
+    /// root for the system DB, and a successful write-open of the file
+    /// (which fails without Full Disk Access or file permissions).
+    fn can_write_db(path: &Path, is_system: bool) -> bool {
+        if is_system && !nix_is_root() {
+            return false;
+        }
+        std::fs::OpenOptions::new().write(true).open(path).is_ok()
+    }
+
+    /// Whether writing the DB at `path` needs root this session: always for
+    /// the system DB, and for an explicit `--db` file only when it exists
+    /// but cannot be opened for writing as is
+    fn needs_root(&self, path: &Path, is_system: bool) -> bool {
+        if nix_is_root() {
+            return false;
+        }
+        match self.target {
+            DbTarget::File => path.exists() && !Self::can_write_db(path, false),
+            DbTarget::Default | DbTarget::User => is_system,
+        }
+    }
+
+    /// The `NeedsRoot` message for a write to `path`; the system DB guidance
+    /// in `message` does not apply to an explicit file
+    fn root_message(&self, path: &Path, message: String) -> String {
+        match self.target {
+            DbTarget::File => format!(
+                "{} is not writable by this user.\n\
+                 Run with sudo, or work on a copy you own.",
+                path.display()
+            ),
+            DbTarget::Default | DbTarget::User => message,
+        }
+    }
+
+    /// Check if root is needed and we don't have it
+    fn check_root_for_write(
+        &self,
+        service: Service,
+        action: &str,
+        service_input: &str,
+        client: &str,
+    ) -> Result<(), TccError> {
+        let (path, is_system) = self.write_db_path(service);
+        if self.needs_root(path, is_system) {
+            return Err(TccError::NeedsRoot {
+                message: self.root_message(
+                    path,
+                    format!(
+                        "Service '{}' requires the system TCC database.\n\
+                     Run with sudo: sudo tcc {} {} {}",
+                        service.display_name(),
+                        action,
+                        service_input,
+                        client
+                    ),
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Validate the DB schema before writing according to the schema policy.
+    /// Returns Ok with an optional warning.
+    fn validate_schema(&self, path: &Path, conn: &Connection) -> Result<Option<String>, TccError> {
+        if self.schema_policy == SchemaPolicy::AssumeKnown {
+            return Ok(None);
+        }
+
+        if let Some(short) = self.schemas.get(path, conn).digest {
+            let short = short.as_str();
+            if KNOWN_DIGESTS.contains(&short) {
+                Ok(macos_version().and_then(|v| digest_version_mismatch(short, &v)))
+            } else if self.schema_policy == SchemaPolicy::Strict {
+                Err(TccError::SchemaInvalid(format!(
+                    "Unknown TCC database schema (digest: {}). Refusing to write with --assume-schema strict.",
+                    short
+                )))
+            } else {
+                Ok(Some(format!(
+                    "Unknown TCC database schema (digest: {}). Proceeding anyway — results may vary.",
+                    short
+                )))
+            }
+        } else {
+            Err(TccError::SchemaInvalid(
+                "Could not read TCC database schema. The access table may not exist.".to_string(),
+            ))
+        }
+    }
+
+    /// `DbLocked` when SQLite reports the user DB busy or locked; any other
+    /// error (and any error on the system DB) goes through `other`
+    fn locked_or(
+        &self,
+        service: Service,
+        e: rusqlite::Error,
+        other: impl FnOnce(rusqlite::Error) -> TccError,
+    ) -> TccError {
+        let (path, is_system) = self.write_db_path(service);
+        if !is_system && is_lock_error(&e) {
+            TccError::DbLocked(path.to_path_buf())
+        } else {
+            other(e)
+        }
+    }
+
+    /// Run a single-row write, repeating it up to `lock_retries` times while
+    /// it fails on a locked user DB
+    fn retry_locked<T>(
+        &self,
+        mut write: impl FnMut() -> Result<T, TccError>,
+    ) -> Result<T, TccError> {
+        let mut attempt = 0;
+        loop {
+            match write() {
+                Err(TccError::DbLocked(path)) if attempt < self.lock_retries => {
+                    attempt += 1;
+                    self.warn(format!(
+                        "{} is locked by tccd, retrying ({}/{})",
+                        path.display(),
+                        attempt,
+                        self.lock_retries
+                    ));
+                    std::thread::sleep(LOCK_RETRY_PAUSE);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Open a writable connection with schema validation
+    fn open_writable(&self, service: Service) -> Result<(Connection, Option<String>), TccError> {
+        let (db_path, is_system) = self.write_db_path(service);
+        self.open_writable_at(db_path, is_system)
+    }
+
+    /// `open_writable` for an explicit DB rather than the one a service's
+    /// writes go to
+    fn open_writable_at(
+        &self,
+        db_path: &Path,
+        is_system: bool,
+    ) -> Result<(Connection, Option<String>), TccError> {
+        check_not_broken_symlink(db_path)?;
+        let conn = Connection::open(db_path).map_err(|e| TccError::DbOpen {
+            path: db_path.to_path_buf(),
+            source: e.to_string(),
+        })?;
+        conn.busy_timeout(self.busy_timeout)
+            .map_err(|e| TccError::DbOpen {
+                path: db_path.to_path_buf(),
+                source: e.to_string(),
+            })?;
+        // An exclusive lock also blocks the schema read, which would
+        // otherwise surface as an unreadable schema
+        if let Err(e) = conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+            && is_lock_error(&e)
+        {
+            return Err(if is_system {
+                TccError::QueryFailed(e.to_string())
+            } else {
+                TccError::DbLocked(db_path.to_path_buf())
+            });
+        }
+        let warning = self.validate_schema(db_path, &conn)?;
+        Ok((conn, warning))
+    }
+
+    pub fn grant(&self, service: &str, client: &str) -> Result<String, TccError> {
+        self.grant_counted(service, client, None)
+            .map(|(message, _)| message)
+    }
+
+    /// Like `grant`, also returning how many rows the write touched. `target`
+    /// is the app an Automation client may script; it keys the entry along
+    /// with the client.
+    pub fn grant_counted(
+        &self,
+        service: &str,
+        client: &str,
+        target: Option<&str>,
+    ) -> Result<(String, usize), TccError> {
+        self.retry_locked(|| self.grant_once(service, client, target))
+    }
+
+    fn grant_once(
+        &self,
+        service: &str,
+        client: &str,
+        target: Option<&str>,
+    ) -> Result<(String, usize), TccError> {
+        let svc = Service::try_from(service)?;
+        self.check_root_for_write(svc, "grant", service, client)?;
+
+        let (mut conn, warning) = self.open_writable(svc)?;
+        if let Some(w) = warning {
+            self.warn(w);
+        }
+        if svc.needs_indirect_object() && target.is_none() {
+            self.warn(format!(
+                "{} entries are normally scoped to a target app; this one is written without a target and macOS may ignore it.",
+                svc.display_name()
+            ));
+        }
+
+        let now = chrono::Utc::now().timestamp() - 978_307_200;
+        let write_err = |e: rusqlite::Error| {
+            self.locked_or(svc, e, |e| {
+                TccError::WriteFailed(format!(
+                    "Failed to grant: {}. Note: SIP may prevent TCC.db writes on macOS 10.14+",
+                    e
+                ))
+            })
+        };
+
+        // Update the existing row in place, so its csreq, flags and other
+        // columns survive; insert only when there is none. The lookup skips
+        // client_type: a row stored under another type than the inferred one
+        // is still this client's, and must not get a duplicate.
+        let has_indirect = has_indirect_object_column(&conn);
+        if target.is_some() && !has_indirect {
+            return Err(no_target_column(self.write_db_path(svc).0));
+        }
+        let key = if has_indirect {
+            "service = ?1 AND client = ?2 AND indirect_object_identifier = COALESCE(?3, 'UNUSED')"
+        } else {
+            "service = ?1 AND client = ?2 AND ?3 IS NULL"
+        };
+        let tx = conn.transaction().map_err(write_err)?;
+        let existing = match tx.query_row(
+            &format!(
+                "SELECT rowid, auth_value, client_type FROM access WHERE {} LIMIT 1",
+                key
+            ),
+            rusqlite::params![svc.key(), client, target],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i32>(1)?,
+                    row.get::<_, i32>(2)?,
+                ))
+            },
+        ) {
+            Ok(found) => Some(found),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(write_err(e)),
+        };
+        // A denied or limited entry was most likely set that way on purpose
+        if let Some((_, auth_value, _)) = existing.filter(|&(_, v, _)| v != 2 && !self.force_grant)
+        {
+            return Err(TccError::EntryExists {
+                service: svc.labelled(),
+                client: client.to_string(),
+                auth_value,
+            });
+        }
+        let (changed, client_type) = match existing {
+            Some((rowid, _, client_type)) => (
+                tx.execute(
+                    "UPDATE access SET auth_value = 2, last_modified = ?1 WHERE rowid = ?2",
+                    rusqlite::params![now, rowid],
+                )
+                .map_err(write_err)?,
+                client_type,
+            ),
+            None => {
+                let client_type = client_type_of(client);
+                let inserted = match target {
+                    Some(target) => tx.execute(
+                        "INSERT INTO access \
+                         (service, client, client_type, auth_value, auth_reason, auth_version, flags, last_modified, \
+                          indirect_object_identifier_type, indirect_object_identifier) \
+                         VALUES (?1, ?2, ?3, 2, 0, 1, 0, ?4, ?5, ?6)",
+                        rusqlite::params![
+                            svc.key(),
+                            client,
+                            client_type,
+                            now,
+                            client_type_of(target),
+                            target
+                        ],
+                    ),
+                    None => tx.execute(
+                        "INSERT INTO access \
+                         (service, client, client_type, auth_value, auth_reason, auth_version, flags, last_modified) \
+                         VALUES (?1, ?2, ?3, 2, 0, 1, 0, ?4)",
+                        rusqlite::params![svc.key(), client, client_type, now],
+                    ),
+                }
+                .map_err(write_err)?;
+                (inserted, client_type)
+            }
+        };
+        let merged_from = if self.merge_csreq {
+            merge_csreq_from_sibling(&tx, svc, client, client_type).map_err(write_err)?
+        } else {
+            None
+        };
+        tx.commit().map_err(write_err)?;
+
+        let mut message = format!(
+            "Granted {} access for '{}'{}",
+            svc.labelled(),
+            client,
+            target_note(target)
+        );
+        if let Some(source) = merged_from {
+            message.push_str(&format!(
+                ", reusing the csreq stored for {}",
+                TccDb::service_display_name(&source)
+            ));
+        }
+        Ok((message, changed))
+    }
+
+    /// Grant only when the write DB has no entry for this service/client
+    /// yet, leaving an existing row (granted or not, possibly carrying a
+    /// csreq) untouched. Returns the message and whether a row was created.
+    pub fn grant_if_missing(
+        &self,
+        service: &str,
+        client: &str,
+        target: Option<&str>,
+    ) -> Result<(String, bool), TccError> {
+        let svc = Service::try_from(service)?;
+        if self.read_access_row(service, client, target)?.is_some() {
+            return Ok((
+                format!(
+                    "{} entry for '{}'{} already exists, skipped",
+                    svc.labelled(),
+                    client,
+                    target_note(target)
+                ),
+                false,
+            ));
+        }
+        self.grant_counted(service, client, target)
+            .map(|(message, _)| (message, true))
+    }
+
+    pub fn revoke(&self, service: &str, client: &str) -> Result<String, TccError> {
+        self.revoke_capturing(service, client, None)
+            .map(|(message, _)| message)
+    }
+
+    /// Like `revoke`, also returning the deleted rows as they were. With a
+    /// `target`, only the Automation entry for that target app is deleted;
+    /// without one, every entry of the pair is.
+    pub fn revoke_capturing(
+        &self,
+        service: &str,
+        client: &str,
+        target: Option<&str>,
+    ) -> Result<(String, Vec<RawRow>), TccError> {
+        self.retry_locked(|| self.revoke_once(service, client, target))
+    }
+
+    fn revoke_once(
+        &self,
+        service: &str,
+        client: &str,
+        target: Option<&str>,
+    ) -> Result<(String, Vec<RawRow>), TccError> {
+        let svc = Service::try_from(service)?;
+        self.check_root_for_write(svc, "revoke", service, client)?;
+
+        let (mut conn, warning) = self.open_writable(svc)?;
+        if let Some(w) = warning {
+            self.warn(w);
+        }
+
+        let write_err = |e: rusqlite::Error| {
+            self.locked_or(svc, e, |e| {
+                TccError::WriteFailed(format!(
+                    "Failed to revoke: {}. Note: SIP may prevent TCC.db writes.",
+                    e
+                ))
+            })
+        };
+        let (path, is_system) = self.write_db_path(svc);
+        if target.is_some() && !has_indirect_object_column(&conn) {
+            return Err(no_target_column(path));
+        }
+        let tx = conn.transaction().map_err(write_err)?;
+        let deleted = match target {
+            Some(target) => delete_capturing(
+                &tx,
+                SourceLabel::of(is_system),
+                "service = ?1 AND client = ?2 AND indirect_object_identifier = ?3",
+                rusqlite::params![svc.key(), client, target],
+            ),
+            None => delete_capturing(
+                &tx,
+                SourceLabel::of(is_system),
+                "service = ?1 AND client = ?2",
+                rusqlite::params![svc.key(), client],
+            ),
+        }
+        .map_err(write_err)?;
+        tx.commit().map_err(write_err)?;
+
+        if deleted.is_empty() {
+            Err(TccError::NotFound {
+                service: svc.display_name().to_string(),
+                client: match target {
+                    Some(target) => format!("{} (target {})", client, target),
+                    None => client.to_string(),
+                },
+            })
+        } else {
+            Ok((
+                format!(
+                    "Revoked {} access for '{}'{}{}",
+                    svc.labelled(),
+                    client,
+                    target_note(target),
+                    rows_note(deleted.len())
+                ),
+                deleted,
+            ))
+        }
+    }
+
+    /// Read-only check that every row for this service/client already has
+    /// `auth_value`, so an enable/disable can skip opening the DB for
+    /// writing. Any read problem answers `false` and leaves the decision,
+    /// and its error reporting, to the write path.
+    fn already_at(&self, svc: Service, client: &str, auth_value: i32) -> bool {
+        let (path, _) = self.write_db_path(svc);
+        if !path.exists() {
+            return false;
+        }
+        let Ok(conn) = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) else {
+            return false;
+        };
+        conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(auth_value != ?3), 0) FROM access \
+             WHERE service = ?1 AND client = ?2",
+            rusqlite::params![svc.key(), client, auth_value],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .is_ok_and(|(rows, differing)| rows > 0 && differing == 0)
+    }
+
+    pub fn enable(&self, service: &str, client: &str) -> Result<String, TccError> {
+        self.set(service, client, 2)
+    }
+
+    /// Like `enable`, also returning how many rows changed (0 for a no-op)
+    pub fn enable_counted(&self, service: &str, client: &str) -> Result<(String, usize), TccError> {
+        self.set_counted(service, client, 2)
+    }
+
+    pub fn disable(&self, service: &str, client: &str) -> Result<String, TccError> {
+        self.set(service, client, 0)
+    }
+
+    /// Like `disable`, also returning how many rows changed (0 for a no-op)
+    pub fn disable_counted(
+        &self,
+        service: &str,
+        client: &str,
+    ) -> Result<(String, usize), TccError> {
+        self.set_counted(service, client, 0)
+    }
+
+    /// Write `auth_value` as given to an existing entry. Whether the value is
+    /// sensible is the caller's concern.
+    pub fn set(&self, service: &str, client: &str, auth_value: i32) -> Result<String, TccError> {
+        self.set_counted(service, client, auth_value)
+            .map(|(message, _)| message)
+    }
+
+    fn set_counted(
+        &self,
+        service: &str,
+        client: &str,
+        auth_value: i32,
+    ) -> Result<(String, usize), TccError> {
+        self.retry_locked(|| self.set_once(service, client, auth_value))
+    }
+
+    /// The one write path behind enable, disable and set. Granted and denied
+    /// are worded as enable/disable, whichever command asked for them.
+    fn set_once(
+        &self,
+        service: &str,
+        client: &str,
+        auth_value: i32,
+    ) -> Result<(String, usize), TccError> {
+        let svc = Service::try_from(service)?;
+        let (action, state) = match auth_value {
+            2 => ("enable", "enabled".to_string()),
+            0 => ("disable", "disabled".to_string()),
+            _ => ("set", auth_value_display(auth_value)),
+        };
+        if self.already_at(svc, client, auth_value) {
+            return Ok((
+                format!(
+                    "{} access for '{}' is already {}, nothing changed",
+                    svc.labelled(),
+                    client,
+                    state
+                ),
+                0,
+            ));
+        }
+        self.check_root_for_write(svc, action, service, client)?;
+
+        let (conn, warning) = self.open_writable(svc)?;
+        if let Some(w) = warning {
+            self.warn(w);
+        }
+
+        let now = chrono::Utc::now().timestamp() - 978_307_200;
+        let updated = conn
+            .execute(
+                "UPDATE access SET auth_value = ?3, last_modified = ?4 WHERE service = ?1 AND client = ?2",
+                rusqlite::params![svc.key(), client, auth_value, now],
+            )
+            .map_err(|e| {
+                self.locked_or(svc, e, |e| {
+                    TccError::WriteFailed(format!(
+                        "Failed to {}: {}. Note: SIP may prevent TCC.db writes.",
+                        action, e
+                    ))
+                })
+            })?;
+
+        if updated == 0 {
+            return Err(TccError::NotFound {
+                service: format!(
+                    "{}. Use `tcc grant` to insert a new entry",
+                    svc.display_name()
+                ),
+                client: client.to_string(),
+            });
+        }
+        let message = match auth_value {
+            2 => format!("Enabled {} access for '{}'", svc.labelled(), client),
+            0 => format!("Disabled {} access for '{}'", svc.labelled(), client),
+            _ => format!(
+                "Set {} access for '{}' to {} ({})",
+                svc.labelled(),
+                client,
+                auth_value,
+                state
+            ),
+        };
+        Ok((format!("{}{}", message, rows_note(updated)), updated))
+    }
+
+    /// Flip an existing entry: granted becomes denied, denied or limited
+    /// becomes granted. Any other status is refused with `NotToggleable`.
+    /// Returns the message and the auth values before and after.
+    pub fn toggle(&self, service: &str, client: &str) -> Result<(String, i32, i32), TccError> {
+        self.retry_locked(|| self.toggle_once(service, client))
+    }
+
+    fn toggle_once(&self, service: &str, client: &str) -> Result<(String, i32, i32), TccError> {
+        let svc = Service::try_from(service)?;
+        self.check_root_for_write(svc, "toggle", service, client)?;
+
+        let (mut conn, warning) = self.open_writable(svc)?;
+        if let Some(w) = warning {
+            self.warn(w);
+        }
+
+        let write_err = |e: rusqlite::Error| {
+            self.locked_or(svc, e, |e| {
+                TccError::WriteFailed(format!(
+                    "Failed to toggle: {}. Note: SIP may prevent TCC.db writes.",
+                    e
+                ))
+            })
+        };
+        // Read and write in one transaction so the flip is based on what is replaced
+        let tx = conn.transaction().map_err(write_err)?;
+        let before: i32 = match tx.query_row(
+            "SELECT auth_value FROM access WHERE service = ?1 AND client = ?2 LIMIT 1",
+            rusqlite::params![svc.key(), client],
+            |row| row.get(0),
+        ) {
+            Ok(value) => value,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(TccError::NotFound {
+                    service: format!(
+                        "{}. Use `tcc grant` to insert a new entry",
+                        svc.display_name()
+                    ),
+                    client: client.to_string(),
+                });
+            }
+            Err(e) => return Err(write_err(e)),
+        };
+        let after = match before {
+            2 => 0,
+            0 | 3 => 2,
+            _ => {
+                return Err(TccError::NotToggleable {
+                    service: svc.display_name().to_string(),
+                    client: client.to_string(),
+                    auth_value: before,
+                });
+            }
+        };
+        let now = chrono::Utc::now().timestamp() - 978_307_200;
+        let updated = tx
+            .execute(
+                "UPDATE access SET auth_value = ?3, last_modified = ?4 WHERE service = ?1 AND client = ?2",
+                rusqlite::params![svc.key(), client, after, now],
+            )
+            .map_err(write_err)?;
+        tx.commit().map_err(write_err)?;
+
+        Ok((
+            format!(
+                "Toggled {} access for '{}': {} → {}{}",
+                svc.labelled(),
+                client,
+                auth_value_display(before),
+                auth_value_display(after),
+                rows_note(updated)
+            ),
+            before,
+            after,
+        ))
+    }
+
+    /// Set auth_value on every entry of a service in one transaction.
+    /// `action` is "enable" or "disable"; returns the message and how many
+    /// rows changed.
+    pub fn set_all_clients(
+        &self,
+        service: &str,
+        action: &str,
+        auth_value: i32,
+    ) -> Result<(String, usize), TccError> {
+        let svc = Service::try_from(service)?;
+        let (path, is_system) = self.write_db_path(svc);
+        if self.needs_root(path, is_system) {
+            return Err(TccError::NeedsRoot {
+                message: self.root_message(
+                    path,
+                    format!(
+                        "Service '{}' requires the system TCC database.\n\
+                     Run with sudo: sudo tcc {} {} --all-clients --yes",
+                        svc.display_name(),
+                        action,
+                        service
+                    ),
+                ),
+            });
+        }
+
+        let (mut conn, warning) = self.open_writable(svc)?;
+        if let Some(w) = warning {
+            self.warn(w);
+        }
+
+        let write_err =
+            |e: rusqlite::Error| TccError::WriteFailed(format!("Failed to {}: {}", action, e));
+        let now = chrono::Utc::now().timestamp() - 978_307_200;
+        let tx = conn.transaction().map_err(write_err)?;
+        let updated = tx
+            .execute(
+                "UPDATE access SET auth_value = ?2, last_modified = ?3 \
+                 WHERE service = ?1 AND auth_value != ?2",
+                rusqlite::params![svc.key(), auth_value, now],
+            )
+            .map_err(write_err)?;
+        tx.commit().map_err(write_err)?;
+
+        let verb = if auth_value == 2 {
+            "Enabled"
+        } else {
+            "Disabled"
+        };
+        Ok((
+            format!(
+                "{} {} access for all clients ({} changed)",
+                verb,
+                svc.labelled(),
+                updated
+            ),
+            updated,
+        ))
+    }
+
+    pub fn reset(&self, service: &str, client: Option<&str>) -> Result<ResetOutcome, TccError> {
+        let svc = Service::try_from(service)?;
+
+        if let Some(c) = client {
+            // Delete specific client entry
+            self.check_root_for_write(svc, "reset", service, c)?;
+
+            let (mut conn, warning) = self.open_writable(svc)?;
+            if let Some(w) = warning {
+                self.warn(w);
+            }
+
+            let write_err =
+                |e: rusqlite::Error| TccError::WriteFailed(format!("Failed to reset: {}", e));
+            let (_, is_system) = self.write_db_path(svc);
+            let tx = conn.transaction().map_err(write_err)?;
+            let deleted = delete_capturing(
+                &tx,
+                SourceLabel::of(is_system),
+                "service = ?1 AND client = ?2",
+                rusqlite::params![svc.key(), c],
+            )
+            .map_err(write_err)?;
+            tx.commit().map_err(write_err)?;
+
+            if deleted.is_empty() {
+                Err(TccError::NotFound {
+                    service: svc.display_name().to_string(),
+                    client: c.to_string(),
+                })
+            } else {
+                let mut outcome = ResetOutcome::new(
+                    format!("Reset {} entry for '{}'", svc.labelled(), c),
+                    deleted.len(),
+                    1,
+                );
+                outcome.deleted_rows = deleted;
+                Ok(outcome)
+            }
+        } else {
+            // Delete all entries for this service
+            // For default target, try to reset in both DBs
+            let mut total_deleted = 0usize;
+            let mut dbs = 0usize;
+            let mut errors = Vec::new();
+            let mut deleted_rows = Vec::new();
+
+            for (db_path, source, is_system) in self.target_paths(Op::Reset) {
+                let label = source.as_str();
+                if !db_path.exists() {
+                    continue;
+                }
+                // Check root for system DB writes
+                if self.needs_root(db_path, is_system) {
+                    return Err(TccError::NeedsRoot {
+                        message: self.root_message(
+                            db_path,
+                            format!(
+                                "Resetting all '{}' entries requires the system TCC database.\n\
+                             Run with sudo: sudo tcc reset {}",
+                                svc.display_name(),
+                                service
+                            ),
+                        ),
+                    });
+                }
+                match Connection::open(db_path) {
+                    Ok(mut conn) => {
+                        if let Err(e) = self.validate_schema(db_path, &conn) {
+                            errors.push(format!("{} DB: {}", label, e));
+                            continue;
+                        }
+                        let deleted = conn.transaction().and_then(|tx| {
+                            let rows = delete_capturing(
+                                &tx,
+                                source,
+                                "service = ?1",
+                                rusqlite::params![svc.key()],
+                            )?;
+                            tx.commit()?;
+                            Ok(rows)
+                        });
+                        match deleted {
+                            Ok(mut rows) => {
+                                total_deleted += rows.len();
+                                dbs += usize::from(!rows.is_empty());
+                                deleted_rows.append(&mut rows);
+                            }
+                            Err(e) => errors.push(format!("{} DB: {}", label, e)),
+                        }
+                    }
+                    Err(e) => errors.push(format!("{} DB: {}", label, e)),
+                }
+            }
+
+            if total_deleted == 0 && !errors.is_empty() {
+                Err(TccError::WriteFailed(format!(
+                    "Failed to reset: {}",
+                    errors.join("; ")
+                )))
+            } else {
+                let mut outcome = ResetOutcome::new(
+                    format!(
+                        "Reset all {} entries ({} deleted)",
+                        svc.labelled(),
+                        total_deleted
+                    ),
+                    total_deleted,
+                    dbs,
+                );
+                outcome.warnings = errors;
+                outcome.deleted_rows = deleted_rows;
+                Ok(outcome)
+            }
+        }
+    }
+
+    /// Delete every entry of every known service in `family`, in one
+    /// transaction per targeted DB, reporting the count per service.
+    pub fn reset_family(&self, family: ServiceFamily) -> Result<ResetOutcome, TccError> {
+        let services = family.services();
+        let mut per_service: Vec<(Service, usize)> = services.iter().map(|&s| (s, 0)).collect();
+        let mut total_deleted = 0usize;
+        let mut dbs = 0usize;
+        let mut errors = Vec::new();
+        let mut deleted_rows = Vec::new();
+
+        for (db_path, source, is_system) in self.target_paths(Op::Reset) {
+            let label = source.as_str();
+            if !db_path.exists() {
+                continue;
+            }
+            if self.needs_root(db_path, is_system) {
+                return Err(TccError::NeedsRoot {
+                    message: self.root_message(
+                        db_path,
+                        format!(
+                            "Resetting the {} family requires the system TCC database.\n\
+                         Run with sudo: sudo tcc reset --family {} --yes",
+                            family.label(),
+                            family.as_str().replace('_', "-")
+                        ),
+                    ),
+                });
+            }
+            match self.delete_services(db_path, source, &services) {
+                Ok((counts, mut rows)) => {
+                    deleted_rows.append(&mut rows);
+                    let deleted: usize = counts.iter().sum();
+                    for ((_, total), n) in per_service.iter_mut().zip(counts) {
+                        *total += n;
+                    }
+                    total_deleted += deleted;
+                    dbs += usize::from(deleted > 0);
+                }
+                Err(e) => errors.push(format!("{} DB: {}", label, e)),
+            }
+        }
+
+        if total_deleted == 0 && !errors.is_empty() {
+            return Err(TccError::WriteFailed(format!(
+                "Failed to reset: {}",
+                errors.join("; ")
+            )));
+        }
+
+        per_service.retain(|(_, n)| *n > 0);
+        let mut message = format!(
+            "Reset all {} family entries ({} deleted)",
+            family.label(),
+            total_deleted
+        );
+        for (svc, n) in &per_service {
+            message.push_str(&format!("\n  {}: {}", svc.labelled(), n));
+        }
+        let mut outcome = ResetOutcome::new(message, total_deleted, dbs);
+        outcome.per_service = per_service;
+        outcome.warnings = errors;
+        outcome.deleted_rows = deleted_rows;
+        Ok(outcome)
+    }
+
+    /// Delete all rows of each service in one transaction, returning the
+    /// count per service in the same order and the deleted rows.
+    fn delete_services(
+        &self,
+        db_path: &Path,
+        source: SourceLabel,
+        services: &[Service],
+    ) -> Result<(Vec<usize>, Vec<RawRow>), TccError> {
+        let mut conn = Connection::open(db_path).map_err(|e| TccError::DbOpen {
+            path: db_path.to_path_buf(),
+            source: e.to_string(),
+        })?;
+        self.validate_schema(db_path, &conn)?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?;
+        let mut counts = Vec::with_capacity(services.len());
+        let mut deleted = Vec::new();
+        for svc in services {
+            let mut rows =
+                delete_capturing(&tx, source, "service = ?1", rusqlite::params![svc.key()])
+                    .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?;
+            counts.push(rows.len());
+            deleted.append(&mut rows);
+        }
+        tx.commit()
+            .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?;
+        Ok((counts, deleted))
+    }
+
+    /// Delete all entries for a service except the given clients, in every
+    /// targeted DB. Root is checked for every DB before the first delete.
+    pub fn reset_except(&self, service: &str, keep: &[String]) -> Result<ResetOutcome, TccError> {
+        let svc = Service::try_from(service)?;
+
+        let mut total_deleted = 0usize;
+        let mut total_kept = 0usize;
+        let mut dbs = 0usize;
+        let mut errors = Vec::new();
+        let mut deleted_rows = Vec::new();
+
+        let paths: Vec<_> = self
+            .target_paths(Op::Reset)
+            .into_iter()
+            .filter(|(db_path, _, _)| db_path.exists())
+            .collect();
+        for &(db_path, _, is_system) in &paths {
+            if self.needs_root(db_path, is_system) {
+                return Err(TccError::NeedsRoot {
+                    message: self.root_message(
+                        db_path,
+                        format!(
+                            "Resetting '{}' entries requires the system TCC database.\n\
+                         Run with sudo: sudo tcc reset {} --keep ...",
+                            svc.display_name(),
+                            service
+                        ),
+                    ),
+                });
+            }
+        }
+        for (db_path, source, _) in paths {
+            let label = source.as_str();
+            match self.delete_matching(db_path, source, svc, |c| !keep.iter().any(|k| k == c)) {
+                Ok((mut rows, kept)) => {
+                    let deleted = rows.len();
+                    deleted_rows.append(&mut rows);
+                    total_deleted += deleted;
+                    total_kept += kept;
+                    dbs += usize::from(deleted > 0);
+                }
+                Err(e) => errors.push(format!("{} DB: {}", label, e)),
+            }
+        }
+
+        if total_deleted == 0 && total_kept == 0 && !errors.is_empty() {
+            return Err(TccError::WriteFailed(format!(
+                "Failed to reset: {}",
+                errors.join("; ")
+            )));
+        }
+
+        let mut outcome = ResetOutcome::new(
+            format!(
+                "Reset {} entries ({} deleted, {} kept)",
+                svc.labelled(),
+                total_deleted,
+                total_kept
+            ),
+            total_deleted,
+            dbs,
+        );
+        outcome.warnings = errors;
+        outcome.deleted_rows = deleted_rows;
+        Ok(outcome)
+    }
+
+    /// Delete exactly the given entries of a service (as picked from a
+    /// previous `list`), each from the DB it was read from.
+    pub fn reset_selected(
+        &self,
+        service: &str,
+        selected: &[&TccEntry],
+    ) -> Result<ResetOutcome, TccError> {
+        let svc = Service::try_from(service)?;
+
+        let mut total_deleted = 0usize;
+        let mut dbs = 0usize;
+        let mut errors = Vec::new();
+        let mut deleted_rows = Vec::new();
+
+        for (db_path, source, is_system) in self.target_paths(Op::Reset) {
+            let clients: Vec<&str> = selected
+                .iter()
+                .filter(|e| e.is_system == is_system && e.service_raw == svc.key())
+                .map(|e| e.client.as_str())
+                .collect();
+            if clients.is_empty() || !db_path.exists() {
+                continue;
+            }
+            if self.needs_root(db_path, is_system) {
+                return Err(TccError::NeedsRoot {
+                    message: self.root_message(
+                        db_path,
+                        format!(
+                            "Resetting '{}' entries requires the system TCC database.\n\
+                         Run with sudo: sudo tcc reset {} --confirm-each",
+                            svc.display_name(),
+                            service
+                        ),
+                    ),
+                });
+            }
+            match self.delete_matching(db_path, source, svc, |c| clients.contains(&c)) {
+                Ok((mut rows, _)) => {
+                    total_deleted += rows.len();
+                    dbs += usize::from(!rows.is_empty());
+                    deleted_rows.append(&mut rows);
+                }
+                Err(e) => errors.push(format!("{} DB: {}", source.as_str(), e)),
+            }
+        }
+
+        if total_deleted == 0 && !errors.is_empty() {
+            return Err(TccError::WriteFailed(format!(
+                "Failed to reset: {}",
+                errors.join("; ")
+            )));
+        }
+
+        let mut outcome = ResetOutcome::new(
+            format!(
+                "Reset {} entries ({} deleted)",
+                svc.labelled(),
+                total_deleted
+            ),
+            total_deleted,
+            dbs,
+        );
+        outcome.warnings = errors;
+        outcome.deleted_rows = deleted_rows;
+        Ok(outcome)
+    }
+
+    /// Delete rows for `service` whose client `delete` accepts, in one
+    /// transaction, returning the deleted rows and how many were kept.
+    fn delete_matching(
+        &self,
+        db_path: &Path,
+        source: SourceLabel,
+        service: Service,
+        delete: impl Fn(&str) -> bool,
+    ) -> Result<(Vec<RawRow>, usize), TccError> {
+        let mut conn = Connection::open(db_path).map_err(|e| TccError::DbOpen {
+            path: db_path.to_path_buf(),
+            source: e.to_string(),
+        })?;
+        self.validate_schema(db_path, &conn)?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?;
+        let clients: Vec<String> = {
+            let mut stmt = tx
+                .prepare("SELECT client FROM access WHERE service = ?1")
+                .map_err(|e| TccError::QueryFailed(format!("Failed to reset: {}", e)))?;
+            stmt.query_map(rusqlite::params![service.key()], |row| row.get(0))
+                .and_then(|rows| rows.collect())
+                .map_err(|e| TccError::QueryFailed(format!("Failed to reset: {}", e)))?
+        };
+
+        let mut deleted = Vec::new();
+        let mut kept = 0usize;
+        for client in clients {
+            if !delete(&client) {
+                kept += 1;
+                continue;
+            }
+            deleted.append(
+                &mut delete_capturing(
+                    &tx,
+                    source,
+                    "service = ?1 AND client = ?2",
+                    rusqlite::params![service.key(), client],
+                )
+                .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?,
+            );
+        }
+        tx.commit()
+            .map_err(|e| TccError::WriteFailed(format!("Failed to reset: {}", e)))?;
+
+        Ok((deleted, kept))
+    }
+
+    /// Find rows sharing the access table's composite primary key (service,
+    /// client, client_type and, where the column exists,
+    /// indirect_object_identifier) in every targeted DB. With `fix`, keep the
+    /// most recently modified row of each group and delete the rest, in one
+    /// transaction per DB. A DB that cannot be opened or read is skipped
+    /// with a warning, like `list` does.
+    pub fn find_duplicates(&self, fix: bool) -> Result<Vec<DuplicateGroup>, TccError> {
+        let mut groups = Vec::new();
+        for (path, source, is_system) in self.target_paths(Op::Reset) {
+            if !path.exists() {
+                continue;
+            }
+            if fix && self.needs_root(path, is_system) {
+                return Err(TccError::NeedsRoot {
+                    message: self.root_message(
+                        path,
+                        "Repairing the system TCC database requires root.\n\
+                              Run with sudo: sudo tcc repair --fix-duplicates"
+                            .to_string(),
+                    ),
+                });
+            }
+            match self.duplicates_in(path, source, fix) {
+                Ok(found) => groups.extend(found),
+                Err(e @ (TccError::DbOpen { .. } | TccError::QueryFailed(_))) => {
+                    self.warn(e.to_string())
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(groups)
+    }
+
+    fn duplicates_in(
+        &self,
+        path: &Path,
+        source: SourceLabel,
+        fix: bool,
+    ) -> Result<Vec<DuplicateGroup>, TccError> {
+        check_not_broken_symlink(path)?;
+        let flags = if fix {
+            OpenFlags::default()
+        } else {
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+        };
+        let mut conn = Connection::open_with_flags(path, flags).map_err(|e| TccError::DbOpen {
+            path: path.to_path_buf(),
+            source: e.to_string(),
+        })?;
+        if fix && let Some(w) = self.validate_schema(path, &conn)? {
+            self.warn(w);
+        }
+
+        let has_indirect = has_indirect_object_column(&conn);
+        let key_cols = if has_indirect {
+            "service, client, client_type, indirect_object_identifier"
+        } else {
+            "service, client, client_type"
+        };
+        let key_match = if has_indirect {
+            "service IS ?1 AND client IS ?2 AND client_type IS ?3 \
+             AND indirect_object_identifier IS ?4"
+        } else {
+            "service IS ?1 AND client IS ?2 AND client_type IS ?3"
+        };
+
+        let query_err = |e: rusqlite::Error| {
+            TccError::QueryFailed(format!(
+                "Failed to find duplicates in {}: {}",
+                path.display(),
+                e
+            ))
+        };
+        let write_err =
+            |e: rusqlite::Error| TccError::WriteFailed(format!("Failed to repair: {}", e));
+
+        let tx = conn.transaction().map_err(query_err)?;
+        let mut groups: Vec<DuplicateGroup> = {
+            let sql = format!(
+                "SELECT {cols}, COUNT(*) FROM access GROUP BY {cols} HAVING COUNT(*) > 1 \
+                 ORDER BY service, client",
+                cols = key_cols
+            );
+            let mut stmt = tx.prepare(&sql).map_err(query_err)?;
+            stmt.query_map([], |row| {
+                let (indirect_object, count_idx) = if has_indirect {
+                    (row.get::<_, Option<String>>(3)?, 4)
+                } else {
+                    (None, 3)
+                };
+                Ok(DuplicateGroup {
+                    source,
+                    service_raw: row.get(0)?,
+                    client: row.get(1)?,
+                    client_type: row.get(2)?,
+                    indirect_object,
+                    rows: row.get::<_, i64>(count_idx)? as usize,
+                    removed: 0,
+                })
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(query_err)?
+        };
+
+        if fix {
+            let sql = format!(
+                "DELETE FROM access WHERE {m} AND rowid != \
+                 (SELECT rowid FROM access WHERE {m} \
+                  ORDER BY COALESCE(last_modified, 0) DESC, rowid DESC LIMIT 1)",
+                m = key_match
+            );
+            for group in &mut groups {
+                let mut params: Vec<rusqlite::types::Value> = vec![
+                    group.service_raw.clone().into(),
+                    group.client.clone().into(),
+                    group.client_type.into(),
+                ];
+                if has_indirect {
+                    params.push(group.indirect_object.clone().into());
+                }
+                group.removed = tx
+                    .execute(&sql, rusqlite::params_from_iter(params))
+                    .map_err(write_err)?;
+            }
+            tx.commit().map_err(write_err)?;
+        }
+
+        Ok(groups)
+    }
+
+    /// The `info` lines along with the journal state of each existing DB
+    pub fn info(&self) -> InfoReport {
+        let mut lines = Vec::new();
+
+        let macos_ver = macos_version();
+        lines.push(format!(
+            "macOS version: {}",
+            macos_ver.as_deref().unwrap_or("unknown")
+        ));
+
+        let sip = sip_status().unwrap_or_else(|| "unknown (csrutil not available)".to_string());
+        lines.push(format!("SIP status: {}", sip));
+
+        lines.push(String::new());
+
+        // DB info
+        let mut journals = Vec::new();
+        for (path, source, is_system) in self.target_paths(Op::Info) {
+            let label = match source {
+                SourceLabel::User => "User DB",
+                SourceLabel::System => "System DB",
+            };
+            lines.push(format!("{}: {}", label, path.display()));
+            if let Some((link, target)) = broken_symlink_target(path) {
+                if link == path {
+                    lines.push(format!("  Broken symlink -> {}", target.display()));
+                } else {
+                    lines.push(format!(
+                        "  Broken symlink: {} -> {}",
+                        link.display(),
+                        target.display()
+                    ));
+                }
+            } else if let Ok(real) = std::fs::canonicalize(path)
+                && real != path
+            {
+                lines.push(format!("  Resolved path: {}", real.display()));
+            }
+            if path.exists() {
+                // Probe sidecars before opening any connection, since opening a
+                // WAL-mode DB creates the -shm file. A large lingering WAL means
+                // uncheckpointed changes that readers may not see yet.
+                let [wal_bytes, shm_bytes] = ["-wal", "-shm"].map(|suffix| {
+                    let mut sidecar = path.as_os_str().to_owned();
+                    sidecar.push(suffix);
+                    std::fs::metadata(&sidecar).ok().map(|meta| meta.len())
+                });
+
+                let readable =
+                    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).is_ok();
+                let writable =
+                    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE).is_ok();
+                lines.push(format!(
+                    "  Readable: {}",
+                    if readable { "yes" } else { "no" }
+                ));
+                lines.push(format!(
+                    "  Writable: {}",
+                    if writable { "yes" } else { "no" }
+                ));
+
+                // Schema digest
+                if readable
+                    && let Ok(conn) =
+                        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    && let Some(short) = self.schemas.get(path, &conn).digest
+                {
+                    let known = if KNOWN_DIGESTS.contains(&short.as_str()) {
+                        "known"
+                    } else {
+                        "UNKNOWN"
+                    };
+                    lines.push(format!("  Schema digest: {} ({})", short, known));
+                    if let Some(note) = macos_ver
+                        .as_deref()
+                        .and_then(|v| digest_version_mismatch(&short, v))
+                    {
+                        lines.push(format!("  Note: {}", note));
+                    }
+                }
+
+                // Journal mode
+                let journal_mode = readable
+                    .then(|| Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY))
+                    .and_then(Result::ok)
+                    .and_then(|conn| {
+                        conn.query_row("PRAGMA journal_mode", [], |row| row.get::<_, String>(0))
+                            .ok()
+                    });
+                if let Some(mode) = &journal_mode {
+                    lines.push(format!("  Journal mode: {}", mode));
+                }
+                for (name, bytes) in [("WAL file", wal_bytes), ("SHM file", shm_bytes)] {
+                    match bytes {
+                        Some(n) => lines.push(format!("  {}: {} bytes", name, n)),
+                        None => lines.push(format!("  {}: none", name)),
+                    }
+                }
+                journals.push(DbJournal {
+                    path: path.to_path_buf(),
+                    is_system,
+                    journal_mode,
+                    wal_bytes,
+                    shm_bytes,
+                });
+            } else {
+                lines.push("  Not found".to_string());
+            }
+            lines.push(String::new());
+        }
+
+        InfoReport { lines, journals }
+    }
+
+    /// Entries per service family across the readable DBs, in
+    /// `ServiceFamily::ALL` order with empty families included.
+    pub fn family_counts(&self) -> Result<Vec<(ServiceFamily, usize)>, TccError> {
+        let entries = self.list_filtered(&ListFilter::default())?;
+        Ok(ServiceFamily::ALL
+            .iter()
+            .map(|&family| {
+                let n = entries
+                    .iter()
+                    .filter(|e| ServiceFamily::of(&e.service_raw) == family)
+                    .count();
+                (family, n)
+            })
+            .collect())
+    }
+
+    /// For every known service, the DB its writes route to and whether that
+    /// DB is writable right now, sorted by display name.
+    pub fn service_access(&self) -> Vec<ServiceAccess> {
+        let mut blocked: HashMap<PathBuf, Option<&'static str>> = HashMap::new();
+        let mut services: Vec<Service> = SERVICE_MAP
+            .keys()
+            .filter_map(|key| Service::try_from(*key).ok())
+            .collect();
+        services.sort_by_key(|svc| svc.display_name());
+        services
+            .into_iter()
+            .map(|svc| {
+                let (path, is_system) = self.write_db_path(svc);
+                let blocked_by = *blocked.entry(path.to_path_buf()).or_insert_with(|| {
+                    if !path.exists() {
+                        Some("DB not found")
+                    } else if self.needs_root(path, is_system) {
+                        Some("needs sudo")
+                    } else if !Self::can_write_db(path, is_system) {
+                        Some("no write access (Full Disk Access or file permissions)")
+                    } else {
+                        None
+                    }
+                });
+                ServiceAccess {
+                    service: svc,
+                    path: path.to_path_buf(),
+                    is_system,
+                    blocked_by,
+                }
+            })
+            .collect()
+    }
+
+    /// Check what stands between this session and reading/writing the
+    /// targeted DBs: root, SIP, Full Disk Access, schema, and a write probe.
+    /// Always returns the same checks in the same order.
+    pub fn doctor(&self) -> Vec<DoctorCheck> {
+        let dbs: Vec<_> = self
+            .target_paths(Op::Read)
+            .into_iter()
+            .filter(|(path, _, _)| path.exists())
+            .collect();
+
+        let mut root = DoctorCheck::new("root", "Root privileges");
+        if nix_is_root() {
+            root.note(CheckStatus::Ok, "Running as root");
+        } else if dbs
+            .iter()
+            .any(|(path, _, is_system)| self.needs_root(path, *is_system))
+        {
+            root.note(
+                CheckStatus::Warn,
+                "Not running as root; system DB services cannot be changed",
+            );
+            root.remediation =
+                Some("Run with sudo, or pass --user to work on the user DB only".to_string());
+        } else {
+            root.note(CheckStatus::Ok, "Root is not needed for the user DB");
+        }
+
+        let mut sip = DoctorCheck::new("sip", "System Integrity Protection");
+        match sip_status() {
+            Some(status) if !status.is_empty() => sip.note(CheckStatus::Ok, &status),
+            _ => sip.note(
+                CheckStatus::Warn,
+                "Could not determine SIP status (csrutil not available)",
+            ),
+        }
+
+        let mut fda = DoctorCheck::new("fda", "Full Disk Access");
+        let mut schema = DoctorCheck::new("schema", "Database schema");
+        let mut write = DoctorCheck::new("write_probe", "Write access");
+        if dbs.is_empty() {
+            for check in [&mut fda, &mut schema, &mut write] {
+                check.note(CheckStatus::Warn, "No TCC database found");
+            }
+        }
+        let mut any_writable = false;
+        for (path, source, is_system) in &dbs {
+            let label = source.as_str();
+            let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY);
+            // Opening is lazy; only a query hits the TCC file protection
+            let read = conn.as_ref().map_err(|e| e.to_string()).and_then(|c| {
+                c.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .map_err(|e| e.to_string())
+            });
+            match read {
+                Ok(_) => fda.note(CheckStatus::Ok, &format!("{} DB readable", label)),
+                Err(e) => {
+                    fda.note(
+                        CheckStatus::Fail,
+                        &format!("{} DB not readable: {}", label, e),
+                    );
+                    if tcc_open_access_denied_hint(path, &e).is_some() {
+                        fda.remediation = Some(
+                            "Grant Full Disk Access to the app running this command, then fully quit and reopen it"
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+
+            match conn.ok().as_ref().and_then(access_schema_digest) {
+                Some(digest) if KNOWN_DIGESTS.contains(&digest.as_str()) => schema.note(
+                    CheckStatus::Ok,
+                    &format!("{} DB schema {} (known)", label, digest),
+                ),
+                Some(digest) => {
+                    schema.note(
+                        CheckStatus::Warn,
+                        &format!("{} DB schema {} (unknown)", label, digest),
+                    );
+                    schema.remediation = Some(
+                        "Writes proceed with a warning; use --assume-schema strict to refuse them"
+                            .to_string(),
+                    );
+                }
+                None => schema.note(
+                    CheckStatus::Fail,
+                    &format!("{} DB has no readable access table", label),
+                ),
+            }
+
+            if Self::can_write_db(path, *is_system) {
+                any_writable = true;
+                write.note(CheckStatus::Ok, &format!("{} DB writable", label));
+            } else {
+                write.note(CheckStatus::Warn, &format!("{} DB not writable", label));
+                write.remediation = Some(if *is_system && !nix_is_root() {
+                    "Run with sudo to write the system DB".to_string()
+                } else {
+                    "Check Full Disk Access and the file's permissions".to_string()
+                });
+            }
+        }
+        // Nothing writable at all is a failure, some is a warning
+        if !dbs.is_empty() && !any_writable {
+            write.status = CheckStatus::Fail;
+        }
+
+        vec![root, sip, fda, schema, write]
+    }
+
+    /// `doctor --fix`: the safe, reversible remediations. Checkpoints a
+    /// lingering WAL into each writable DB (after a backup copy next to
+    /// it) and only suggests SIP changes.
+    /// Nothing is deleted or rewritten beyond the checkpoint.
+    pub fn doctor_fix(&self) -> Vec<DoctorFix> {
+        let mut fixes = Vec::new();
+        for (path, source, is_system) in self.target_paths(Op::Read) {
+            if !path.exists() {
+                continue;
+            }
+            if wal_has_pending_frames(path) {
+                fixes.push(DoctorFix {
+                    id: "wal_checkpoint",
+                    source: Some(source),
+                    result: Self::checkpoint_wal(path, is_system),
+                });
+            }
+        }
+        if sip_status().is_some_and(|s| s.contains("enabled") && !s.contains("disabled")) {
+            fixes.push(DoctorFix {
+                id: "sip",
+                source: None,
+                result: Ok("Left enabled. Writing the system DB may need `csrutil disable` from Recovery; this is never done automatically".to_string()),
+            });
+        }
+        fixes
+    }
+
+    /// Back the DB up with `VACUUM INTO` (which includes the WAL's
+    /// contents), then fold the WAL into the main file and truncate it
+    fn checkpoint_wal(path: &Path, is_system: bool) -> Result<String, String> {
+        if !Self::can_write_db(path, is_system) {
+            return Err(if is_system && !nix_is_root() {
+                "Not writable, skipped; run with sudo".to_string()
+            } else {
+                "Not writable, skipped".to_string()
+            });
+        }
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        let backup = PathBuf::from(format!(
+            "{}.doctor-{}.bak",
+            path.display(),
+            chrono::Utc::now().timestamp()
+        ));
+        conn.execute("VACUUM INTO ?1", [backup.to_string_lossy().as_ref()])
+            .map_err(|e| format!("Backup failed, WAL left alone: {}", e))?;
+        let (busy, frames): (i64, i64) = conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                Ok((row.get(0)?, row.get(2)?))
+            })
+            .map_err(|e| format!("Checkpoint failed: {}; backup at {}", e, backup.display()))?;
+        if busy != 0 {
+            return Err(format!(
+                "Checkpoint blocked by another connection (likely tccd); backup at {}",
+                backup.display()
+            ));
+        }
+        Ok(format!(
+            "Checkpointed {} WAL frames; backup at {}",
+            frames,
+            backup.display()
+        ))
+    }
+}
+
+/// Whether the DB's `-wal` file holds frames not yet checkpointed into the
+/// main file. A WAL-mode DB keeps a non-empty WAL after checkpoints, so
+/// this reads the wal-index in `-shm` instead: two copies of a 48-byte
+/// header with `mxFrame` at offset 16, then `nBackfill`, all native-endian.
+/// Without a readable wal-index, any non-empty WAL counts as pending.
+fn wal_has_pending_frames(path: &Path) -> bool {
+    let sidecar = |suffix: &str| {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        PathBuf::from(sidecar)
+    };
+    if !std::fs::metadata(sidecar("-wal")).is_ok_and(|meta| meta.len() > 0) {
+        return false;
+    }
+    let Some(index) = std::fs::read(sidecar("-shm"))
+        .ok()
+        .filter(|index| index.len() >= 100)
+    else {
+        return true;
+    };
+    let word = |at: usize| u32::from_ne_bytes(index[at..at + 4].try_into().unwrap());
+    let (max_frame, backfilled) = (word(16), word(96));
+    max_frame > backfilled
+}
+
+/// One remediation `doctor --fix` attempted
+#[derive(Debug)]
+pub struct DoctorFix {
+    pub id: &'static str,
+    /// The DB it applied to; `None` for machine-wide suggestions
+    pub source: Option<SourceLabel>,
+    /// What was done, or why it could not be
+    pub result: Result<String, String>,
+}
+
+/// Severity of a doctor check, ordered so the worst wins with `max`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+/// What `info` reports: the printable lines plus the journal state of each
+/// DB that exists, for structured output
+#[derive(Debug)]
+pub struct InfoReport {
+    pub lines: Vec<String>,
+    pub journals: Vec<DbJournal>,
+}
+
+/// Journal mode and `-wal`/`-shm` sidecar sizes of one DB. A large WAL
+/// holds changes not yet checkpointed into the main file.
+#[derive(Debug)]
+pub struct DbJournal {
+    pub path: PathBuf,
+    pub is_system: bool,
+    /// `None` when the DB could not be read
+    pub journal_mode: Option<String>,
+    /// Sidecar sizes in bytes; `None` when the file does not exist
+    pub wal_bytes: Option<u64>,
+    pub shm_bytes: Option<u64>,
+}
+
+/// Where writes for one service go and whether this session can make them
+#[derive(Debug)]
+pub struct ServiceAccess {
+    pub service: Service,
+    pub path: PathBuf,
+    pub is_system: bool,
+    /// Why the DB is not writable; `None` when it is
+    pub blocked_by: Option<&'static str>,
+}
+
+/// One environment check of `doctor`, with a stable `id` for tooling
+#[derive(Debug)]
+pub struct DoctorCheck {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub remediation: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(id: &'static str, label: &'static str) -> Self {
+        DoctorCheck {
+            id,
+            label,
+            status: CheckStatus::Ok,
+            detail: String::new(),
+            remediation: None,
+        }
+    }
+
+    /// Append a finding, raising the status to at least `status`
+    fn note(&mut self, status: CheckStatus, detail: &str) {
+        self.status = self.status.max(status);
+        if !self.detail.is_empty() {
+            self.detail.push_str("; ");
+        }
+        self.detail.push_str(detail);
+    }
+}
+
+/// Outcome of one step of the runtime self-test
+#[derive(Debug)]
+pub struct SelfTestCheck {
+    pub name: &'static str,
+    pub result: Result<(), String>,
+}
+
+/// Schema used for the self-test's throwaway DB (mirrors the Sonoma access table columns)
+const SELF_TEST_SCHEMA: &str = "CREATE TABLE access (
+    service TEXT NOT NULL,
+    client TEXT NOT NULL,
+    client_type INTEGER NOT NULL,
+    auth_value INTEGER NOT NULL DEFAULT 0,
+    auth_reason INTEGER NOT NULL DEFAULT 0,
+    auth_version INTEGER NOT NULL DEFAULT 1,
+    csreq BLOB,
+    policy_id INTEGER,
+    indirect_object_identifier_type INTEGER,
+    indirect_object_identifier TEXT NOT NULL DEFAULT 'UNUSED',
+    indirect_object_code_identity BLOB,
+    flags INTEGER,
+    last_modified INTEGER NOT NULL DEFAULT (CAST(strftime('%s','now') AS INTEGER)),
+    pid INTEGER,
+    pid_version INTEGER,
+    boot_uuid TEXT NOT NULL DEFAULT 'UNUSED',
+    last_reminded INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (service, client, client_type, indirect_object_identifier)
+);";
+
+/// Run grant/enable/disable/revoke/reset against a throwaway DB in the temp dir.
+/// Never touches the real TCC databases.
+pub fn self_test() -> Vec<SelfTestCheck> {
+    self_test_in(&std::env::temp_dir())
+}
+
+/// `self_test` with its temp dir created under `parent`
+fn self_test_in(parent: &Path) -> Vec<SelfTestCheck> {
+    // A fresh dir with a random name, so nothing can be planted there
+    // beforehand; it is removed when `dir` drops, whatever the checks do
+    match tempfile::Builder::new()
+        .prefix("tccutil-rs-self-test-")
+        .tempdir_in(parent)
+    {
+        Ok(dir) => run_self_test(dir.path()),
+        Err(e) => vec![SelfTestCheck {
+            name: "create temp DB",
+            result: Err(e.to_string()),
+        }],
+    }
+}
+
+/// Runs under the default schema policy, like a real write. What the
+/// throwaway DB warns about is held on it and dropped with it, since it
+/// is not the user's concern
+fn run_self_test(home: &Path) -> Vec<SelfTestCheck> {
+    let mut checks = Vec::new();
+    let db = TccDb::with_home(DbTarget::User, home);
+
+    let create = (|| {
+        let db_dir = db.user_db_path.parent().ok_or("invalid temp path")?;
+        std::fs::create_dir_all(db_dir).map_err(|e| e.to_string())?;
+        let conn = Connection::open(&db.user_db_path).map_err(|e| e.to_string())?;
+        conn.execute_batch(SELF_TEST_SCHEMA)
+            .map_err(|e| e.to_string())
+    })();
+    let created = create.is_ok();
+    checks.push(SelfTestCheck {
+        name: "create temp DB",
+        result: create,
+    });
+    if !created {
+        return checks;
+    }
+
+    let client = "com.example.tccutil-rs.self-test";
+    let expect_auth = |expected: Option<i32>| -> Result<(), String> {
+        let entries = db.list(Some(client), None).map_err(|e| e.to_string())?;
+        let actual = entries.first().map(|e| e.auth_value);
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected auth_value {:?}, found {:?}",
+                expected, actual
+            ))
+        }
+    };
+
+    let mut run = |name, result: Result<String, TccError>, expected| {
+        let result = result
+            .map_err(|e| e.to_string())
+            .and_then(|_| expect_auth(expected));
+        checks.push(SelfTestCheck { name, result });
+    };
+    run("grant", db.grant("Camera", client), Some(2));
+    run("disable", db.disable("Camera", client), Some(0));
+    run("enable", db.enable("Camera", client), Some(2));
+    run("revoke", db.revoke("Camera", client), None);
+    let reset = db
+        .grant("Camera", client)
+        .and_then(|_| db.reset("Camera", None))
+        .map(|outcome| outcome.to_string());
+    run("reset", reset, None);
+
+    checks
+}
+
+/// Resolve the TCC client for a running process: its app bundle ID when the
+/// executable lives inside a `.app`, otherwise the executable path.
+pub fn resolve_pid_client(pid: i32) -> Result<String, TccError> {
+    resolve_pid_client_with(pid, pid_executable_path, app_bundle_id)
+}
+
+fn resolve_pid_client_with(
+    pid: i32,
+    executable_path: impl Fn(i32) -> Option<PathBuf>,
+    bundle_id: impl Fn(&Path) -> Option<String>,
+) -> Result<String, TccError> {
+    if pid <= 0 {
+        return Err(TccError::PidNotResolved {
+            pid,
+            reason: "PID must be positive".to_string(),
+        });
+    }
+    let exe = executable_path(pid).ok_or_else(|| TccError::PidNotResolved {
+        pid,
+        reason: "no such process, or its executable path is not accessible".to_string(),
+    })?;
+
+    let bundle = exe
+        .ancestors()
+        .find(|p| p.extension().is_some_and(|ext| ext == "app"));
+    if let Some(id) = bundle.and_then(&bundle_id) {
+        return Ok(id);
+    }
+    Ok(exe.to_string_lossy().to_string())
+}
+
+/// Read a client identifier stored in a file, trimming surrounding whitespace
+pub fn read_client_file(path: &Path) -> Result<String, TccError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| TccError::ClientFile {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    let client = contents.trim();
+    if client.is_empty() {
+        return Err(TccError::ClientFile {
+            path: path.to_path_buf(),
+            reason: "file is empty".to_string(),
+        });
+    }
+    Ok(client.to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn pid_executable_path(pid: i32) -> Option<PathBuf> {
+    let mut buf = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+    let len = unsafe { libc::proc_pidpath(pid, buf.as_mut_ptr().cast(), buf.len() as u32) };
+    if len <= 0 {
+        return None;
+    }
+    buf.truncate(len as usize);
+    String::from_utf8(buf).ok().map(PathBuf::from)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn pid_executable_path(pid: i32) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/{}/exe", pid)).ok()
+}
+
+/// Read CFBundleIdentifier from an app bundle's Info.plist
+fn app_bundle_id(bundle: &Path) -> Option<String> {
+    let info = bundle.join("Contents/Info");
+    let output = Command::new("/usr/bin/defaults")
+        .arg("read")
+        .arg(&info)
+        .arg("CFBundleIdentifier")
+        .output()
+        .ok()?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !id.is_empty()).then_some(id)
+}
+
+/// What macOS reports it enforces for a service, as opposed to the DB value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EffectiveAuth {
+    /// A framework preflight call answered for this process
+    Probed(bool),
+    /// Nothing to compare against; the reason says why
+    DbOnly(&'static str),
+}
+
+/// Ask the OS for the effective authorization of `service` for `client`.
+/// The preflight APIs only answer for the calling process, so a probe is
+/// made only when `client` is this process; everything else is DB-only.
+pub fn effective_authorization(service: Service, client: &str) -> EffectiveAuth {
+    let own_client = resolve_pid_client(std::process::id() as i32).ok();
+    effective_authorization_with(service, client, own_client.as_deref(), preflight)
+}
+
+fn effective_authorization_with(
+    service: Service,
+    client: &str,
+    own_client: Option<&str>,
+    probe: impl Fn(Service) -> Option<bool>,
+) -> EffectiveAuth {
+    if !has_preflight(service) {
+        return EffectiveAuth::DbOnly("no runtime probe for this service");
+    }
+    if own_client != Some(client) {
+        return EffectiveAuth::DbOnly("runtime probe only reports for this process");
+    }
+    match probe(service) {
+        Some(granted) => EffectiveAuth::Probed(granted),
+        None => EffectiveAuth::DbOnly("runtime probe unavailable on this platform"),
+    }
+}
+
+fn has_preflight(service: Service) -> bool {
+    matches!(
+        service.key(),
+        "kTCCServiceAccessibility"
+            | "kTCCServiceScreenCapture"
+            | "kTCCServiceListenEvent"
+            | "kTCCServicePostEvent"
+    )
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+unsafe extern "C" {
+    fn AXIsProcessTrusted() -> u8;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGPreflightListenEventAccess() -> bool;
+    fn CGPreflightPostEventAccess() -> bool;
+}
+
+// The only FFI besides libc: argument-less, non-prompting queries about the
+// calling process, each behind a safe wrapper so `preflight` has no `unsafe`.
+
+#[cfg(target_os = "macos")]
+fn ax_is_process_trusted() -> bool {
+    // SAFETY: takes no arguments and returns a Boolean (u8); reads this
+    // process's Accessibility trust without prompting or side effects
+    unsafe { AXIsProcessTrusted() != 0 }
+}
+
+#[cfg(target_os = "macos")]
+fn cg_preflight_screen_capture() -> bool {
+    // SAFETY: takes no arguments and returns a bool; a preflight never prompts
+    unsafe { CGPreflightScreenCaptureAccess() }
+}
+
+#[cfg(target_os = "macos")]
+fn cg_preflight_listen_event() -> bool {
+    // SAFETY: takes no arguments and returns a bool; a preflight never prompts
+    unsafe { CGPreflightListenEventAccess() }
+}
+
+#[cfg(target_os = "macos")]
+fn cg_preflight_post_event() -> bool {
+    // SAFETY: takes no arguments and returns a bool; a preflight never prompts
+    unsafe { CGPreflightPostEventAccess() }
+}
+
+/// Non-prompting preflight for the calling process
+#[cfg(target_os = "macos")]
+fn preflight(service: Service) -> Option<bool> {
+    match service.key() {
+        "kTCCServiceAccessibility" => Some(ax_is_process_trusted()),
+        "kTCCServiceScreenCapture" => Some(cg_preflight_screen_capture()),
+        "kTCCServiceListenEvent" => Some(cg_preflight_listen_event()),
+        "kTCCServicePostEvent" => Some(cg_preflight_post_event()),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn preflight(_service: Service) -> Option<bool> {
+    None
+}
+
+/// The current boot session UUID (`kern.bootsessionuuid`), as TCC stores
+/// it in `boot_uuid`
+pub fn current_boot_uuid() -> Option<String> {
+    let output = Command::new("/usr/sbin/sysctl")
+        .args(["-n", "kern.bootsessionuuid"])
+        .output()
+        .ok()?;
+    let uuid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !uuid.is_empty()).then_some(uuid)
+}
+
+pub fn nix_is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Truncate a client path to its last `depth` components: just the binary
+/// name at depth 1, `.../MacOS/Safari` at depth 2. Bundle IDs and paths no
+/// deeper than `depth` are returned unchanged.
+pub fn compact_client(client: &str, depth: usize) -> String {
+    if !client.starts_with('/') {
+        return client.to_string();
+    }
+    let parts: Vec<_> = std::path::Path::new(client)
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    match parts.last() {
+        None => client.to_string(),
+        Some(name) if depth <= 1 => name.to_string(),
+        Some(_) if parts.len() <= depth => client.to_string(),
+        Some(_) => format!(".../{}", parts[parts.len() - depth..].join("/")),
+    }
+}
+
+/// SHA1 of an entry set in canonical form: one line per entry with its
+/// source, service key, client, client type, Automation target, auth value,
+/// modification time and csreq, sorted, so the same rows hash the same
+/// whatever order they were read in
+pub fn entries_checksum(entries: &[TccEntry]) -> String {
+    checksum_of_lines(
+        entries
+            .iter()
+            .map(|e| {
+                ChecksumRow {
+                    source: SourceLabel::of(e.is_system).as_str(),
+                    service_raw: &e.service_raw,
+                    client: &e.client,
+                    client_type: e.client_type,
+                    indirect_object: e.indirect_object.as_deref(),
+                    auth_value: e.auth_value,
+                    last_modified_ts: e.last_modified_ts,
+                    csreq: e.csreq.as_deref(),
+                }
+                .line()
+            })
+            .collect(),
+    )
+}
+
+/// The fields of one entry that `entries_checksum` covers
+struct ChecksumRow<'a> {
+    source: &'a str,
+    service_raw: &'a str,
+    client: &'a str,
+    client_type: i32,
+    indirect_object: Option<&'a str>,
+    auth_value: i32,
+    last_modified_ts: i64,
+    csreq: Option<&'a [u8]>,
+}
+
+impl ChecksumRow<'_> {
+    /// The row's line of the canonical form
+    fn line(&self) -> String {
+        format!(
+            "{}\0{}\0{}\0{}\0{}\0{}\0{}\0{}",
+            self.source,
+            self.service_raw,
+            self.client,
+            self.client_type,
+            self.indirect_object.unwrap_or(""),
+            self.auth_value,
+            self.last_modified_ts,
+            self.csreq.map_or(String::new(), base64)
+        )
+    }
+}
+
+fn checksum_of_lines(mut lines: Vec<String>) -> String {
+    lines.sort();
+    let mut hasher = sha1_smol::Sha1::new();
+    for line in &lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.digest().to_string()
+}
+
+/// Collapse entries that share a (service, client) pair across the user and
+/// system DBs, keeping the system DB's row. Returns how many rows were dropped.
+pub fn dedupe_entries(entries: &mut Vec<TccEntry>) -> usize {
+    let key = |e: &TccEntry| {
+        (
+            e.service_raw.clone(),
+            e.client.clone(),
+            e.indirect_object.clone(),
+        )
+    };
+    let system_keys: std::collections::HashSet<_> =
+        entries.iter().filter(|e| e.is_system).map(key).collect();
+    let before = entries.len();
+    entries.retain(|e| e.is_system || !system_keys.contains(&key(e)));
+    before - entries.len()
+}
+
+/// `" to control 'target'"` for Automation messages, or nothing
+fn target_note(target: Option<&str>) -> String {
+    target.map_or(String::new(), |t| format!(" to control '{}'", t))
+}
+
+/// Older schemas have no indirect object column
+fn has_indirect_object_column(conn: &Connection) -> bool {
+    conn.prepare("SELECT indirect_object_identifier FROM access LIMIT 0")
+        .is_ok()
+}
+
+fn no_target_column(path: &Path) -> TccError {
+    TccError::SchemaInvalid(format!(
+        "{} has no indirect_object_identifier column, so it cannot store an Automation target",
+        path.display()
+    ))
+}
+
+/// The access table's key also includes client_type and, on newer
+/// schemas, the target of the permission, so one service/client pair can
+/// have several rows. Writes by service and client touch all of them; say
+/// so when there was more than one.
+fn rows_note(rows: usize) -> String {
+    if rows > 1 {
+        format!(
+            " ({} rows: this pair has one per client type or target)",
+            rows
+        )
+    } else {
+        String::new()
+    }
+}
+
+/// Delete the access rows matching `condition` (a WHERE clause over
+/// `params`) and return them as they were. Run it inside a transaction so
+/// the rows read are exactly the rows deleted.
+fn delete_capturing(
+    conn: &Connection,
+    source: SourceLabel,
+    condition: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> rusqlite::Result<Vec<RawRow>> {
+    let rows = select_raw_rows(conn, source, condition, params)?;
+    conn.execute(&format!("DELETE FROM access WHERE {}", condition), params)?;
+    Ok(rows)
+}
+
+/// Read the JSON array `dump` prints back into rows. String values stay
+/// text here; `restore` decodes the ones bound for BLOB columns.
+/// The rows of a `dump` file, each with the `source` it names, if any
+fn read_dump(path: &Path) -> Result<Vec<(Option<SourceLabel>, RawColumns)>, TccError> {
+    let invalid = |reason: String| TccError::DumpInvalid {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let json: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| invalid(format!("not JSON: {}", e)))?;
+    let items = json
+        .as_array()
+        .ok_or_else(|| invalid("expected the JSON array `dump` prints".to_string()))?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            dump_row(item).map_err(|reason| invalid(format!("row {}: {}", i + 1, reason)))
+        })
+        .collect()
+}
+
+fn dump_row(item: &serde_json::Value) -> Result<(Option<SourceLabel>, RawColumns), String> {
+    use rusqlite::types::Value;
+    let source = match &item["source"] {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) if s == "user" => Some(SourceLabel::User),
+        serde_json::Value::String(s) if s == "system" => Some(SourceLabel::System),
+        _ => return Err("`source` must be \"user\" or \"system\" when present".to_string()),
+    };
+    let row = item["row"].as_object().ok_or("missing `row` object")?;
+    let columns = row
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::Null => Value::Null,
+                serde_json::Value::String(s) => Value::Text(s.clone()),
+                serde_json::Value::Number(n) => match n.as_i64() {
+                    Some(i) => Value::Integer(i),
+                    None => Value::Real(n.as_f64().unwrap_or_default()),
+                },
+                _ => return Err(format!("column `{}` is not a string, number or null", name)),
+            };
+            Ok((name.clone(), value))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok((source, columns))
+}
+
+/// Columns of the access table declared as BLOB
+fn blob_columns(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT name FROM pragma_table_info('access') WHERE upper(type) = 'BLOB'")?;
+    stmt.query_map([], |row| row.get(0))?.collect()
+}
+
+/// `row` ready to insert into a table with `columns`, its base64 strings
+/// decoded for the BLOB columns; `Err` with the reason to skip it
+fn restorable_row(
+    mut row: RawRow,
+    columns: &[String],
+    blob_columns: &[String],
+) -> Result<RawRow, String> {
+    use rusqlite::types::Value;
+    for (name, value) in &mut row.columns {
+        if !columns.contains(name) {
+            return Err(format!(
+                "column `{}` is not in the target access table",
+                name
+            ));
+        }
+        if let Value::Text(text) = value
+            && blob_columns.contains(name)
+        {
+            let bytes =
+                base64_decode(text).ok_or_else(|| format!("column `{}` is not base64", name))?;
+            *value = Value::Blob(bytes);
+        }
+    }
+    Ok(row)
+}
+
+/// The access rows matching `condition` with every column, untouched
+fn select_raw_rows(
+    conn: &Connection,
+    source: SourceLabel,
+    condition: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> rusqlite::Result<Vec<RawRow>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM access WHERE {}", condition))?;
+    let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+    stmt.query_map(params, |row| {
+        let columns = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Ok((name.clone(), row.get(i)?)))
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(RawRow { source, columns })
+    })?
+    .collect()
+}
+
+/// Copy the most recently modified csreq stored for another service of
+/// this client into its `service` row, if that row has none. Returns the
+/// service the blob came from; `None` when nothing was copied, including on
+/// schemas without a csreq column.
+fn merge_csreq_from_sibling(
+    conn: &Connection,
+    svc: Service,
+    client: &str,
+    client_type: i32,
+) -> rusqlite::Result<Option<String>> {
+    if conn.prepare("SELECT csreq FROM access LIMIT 0").is_err() {
+        return Ok(None);
+    }
+    let sibling = conn.query_row(
+        "SELECT service, csreq FROM access \
+             WHERE client = ?1 AND client_type = ?2 AND service != ?3 AND csreq IS NOT NULL \
+             ORDER BY COALESCE(last_modified, 0) DESC LIMIT 1",
+        rusqlite::params![client, client_type, svc.key()],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)),
+    );
+    let (source, csreq) = match sibling {
+        Ok(found) => found,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let updated = conn.execute(
+        "UPDATE access SET csreq = ?4 \
+         WHERE service = ?1 AND client = ?2 AND client_type = ?3 AND csreq IS NULL",
+        rusqlite::params![svc.key(), client, client_type, csreq],
+    )?;
+    Ok((updated > 0).then_some(source))
+}
+
+/// Append the attached `src.access` rows to `conn`'s `access` table, tagged
+/// with `source`. The first call creates the table from `src`'s columns and
+/// records them in `columns`; later calls copy only the columns both share.
+/// An SQLite URI that opens `path` read-only, for ATTACH. `%`, `?` and `#`
+/// in the path are escaped so they stay part of the file name.
+fn read_only_uri(path: &Path) -> String {
+    let mut uri = String::from("file:");
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '%' => uri.push_str("%25"),
+            '?' => uri.push_str("%3f"),
+            '#' => uri.push_str("%23"),
+            c => uri.push(c),
+        }
+    }
+    uri.push_str("?mode=ro");
+    uri
+}
+
+/// `entries_checksum` of the rows in the exported `access` table, read the
+/// way `list` reads them; a row `list` would skip as malformed is left out
+fn exported_checksum(conn: &Connection, columns: &[String]) -> rusqlite::Result<String> {
+    let optional = |name: &'static str| {
+        if columns.iter().any(|c| c == name) {
+            name
+        } else {
+            "NULL"
+        }
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT source, service, client, {}, {}, auth_value, {}, {} FROM access",
+        optional("client_type"),
+        optional("indirect_object_identifier"),
+        optional("last_modified"),
+        optional("csreq")
+    ))?;
+    let lines = stmt
+        .query_map([], |row| {
+            let client: String = row.get(2)?;
+            let client_type: Option<i32> = row.get(3)?;
+            let indirect_object: Option<String> = row.get(4)?;
+            let modified: Option<i64> = row.get(6)?;
+            let csreq: Option<Vec<u8>> = row.get(7)?;
+            Ok(ChecksumRow {
+                source: &row.get::<_, String>(0)?,
+                service_raw: &row.get::<_, String>(1)?,
+                client: &client,
+                client_type: client_type.unwrap_or_else(|| client_type_of(&client)),
+                indirect_object: indirect_object.as_deref().filter(|t| *t != "UNUSED"),
+                auth_value: row.get(5)?,
+                last_modified_ts: TccDb::normalize_timestamp(modified.unwrap_or(0)),
+                csreq: csreq.as_deref(),
+            }
+            .line())
+        })?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(checksum_of_lines(lines))
+}
+
+/// Redact the exported clients and Automation targets in place (not the
+/// `UNUSED` placeholder) and clear the code-signing blobs, which name them
+fn redact_exported_access(
+    conn: &Connection,
+    columns: &[String],
+    redactor: &Redactor,
+) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for column in ["client", "indirect_object_identifier"] {
+        if !columns.iter().any(|c| c == column) {
+            continue;
+        }
+        let values: Vec<(i64, String)> = tx
+            .prepare(&format!(
+                "SELECT rowid, {column} FROM access \
+                 WHERE {column} IS NOT NULL AND {column} != 'UNUSED'"
+            ))?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        for (rowid, value) in values {
+            tx.execute(
+                &format!("UPDATE access SET {column} = ?1 WHERE rowid = ?2"),
+                rusqlite::params![redactor.redact(&value), rowid],
+            )?;
+        }
+    }
+    for column in ["csreq", "indirect_object_code_identity"] {
+        if columns.iter().any(|c| c == column) {
+            tx.execute(&format!("UPDATE access SET {column} = NULL"), [])?;
+        }
+    }
+    tx.commit()
+}
+
+fn copy_attached_access(
+    conn: &Connection,
+    source: SourceLabel,
+    columns: &mut Option<Vec<String>>,
+) -> rusqlite::Result<usize> {
+    let source_columns: Vec<String> = conn
+        .prepare("SELECT name FROM pragma_table_info('access', 'src')")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let quoted = |cols: &[String]| {
+        cols.iter()
+            .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let shared = match columns {
+        None => {
+            conn.execute_batch(&format!(
+                "CREATE TABLE access AS SELECT '' AS source, {} FROM src.access WHERE 0",
+                quoted(&source_columns)
+            ))?;
+            *columns = Some(source_columns.clone());
+            source_columns
+        }
+        Some(existing) => source_columns
+            .into_iter()
+            .filter(|c| existing.contains(c))
+            .collect(),
+    };
+    let cols = quoted(&shared);
+    conn.execute(
+        &format!("INSERT INTO access (source, {cols}) SELECT ?1, {cols} FROM src.access"),
+        [source.as_str()],
+    )
+}
+
+/// Keep only the entries modified after the row with the same service and
+/// client in `other`, or that `other` lacks entirely. Returns, aligned with
+/// the kept entries, the counterpart's `last_modified_ts` (`None` if absent).
+pub fn retain_newer_than(entries: &mut Vec<TccEntry>, other: &[TccEntry]) -> Vec<Option<i64>> {
+    let mut theirs: HashMap<(&str, &str), i64> = HashMap::new();
+    for e in other {
+        let ts = theirs
+            .entry((e.service_raw.as_str(), e.client.as_str()))
+            .or_insert(e.last_modified_ts);
+        *ts = (*ts).max(e.last_modified_ts);
+    }
+    let mut other_ts = Vec::new();
+    entries.retain(|e| {
+        let counterpart = theirs
+            .get(&(e.service_raw.as_str(), e.client.as_str()))
+            .copied();
+        let newer = counterpart.is_none_or(|ts| e.last_modified_ts > ts);
+        if newer {
+            other_ts.push(counterpart);
+        }
+        newer
+    });
+    other_ts
+}
+
+/// Period size for grouping entries by modification time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HistoryBucket {
+    Day,
+    Month,
+}
+
+/// Count entries per period of their `last_modified` time, oldest first.
+/// Entries without a timestamp are returned separately as the second value.
+pub fn bucket_history(
+    entries: &[TccEntry],
+    bucket: HistoryBucket,
+) -> (Vec<(String, usize)>, usize) {
+    let format = match bucket {
+        HistoryBucket::Day => "%Y-%m-%d",
+        HistoryBucket::Month => "%Y-%m",
+    };
+    let mut counts: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+    let mut unknown = 0usize;
+    for entry in entries {
+        match Local.timestamp_opt(entry.last_modified_ts, 0) {
+            chrono::LocalResult::Single(dt) if entry.last_modified_ts != 0 => {
+                *counts.entry(dt.format(format).to_string()).or_default() += 1;
+            }
+            _ => unknown += 1,
+        }
+    }
+    (counts.into_iter().collect(), unknown)
+}
+
+/// Replaces client identifiers with short salted SHA1 tokens for shareable output.
+/// The salt is fixed per instance, so a client maps to the same token within a run
+/// but tokens cannot be matched against hashes of well-known bundle IDs.
+pub struct Redactor {
+    salt: String,
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        Self::with_salt(&format!("{}:{}", std::process::id(), nanos))
+    }
+
+    pub fn with_salt(salt: &str) -> Self {
+        Self {
+            salt: salt.to_string(),
+        }
+    }
+
+    /// First 8 hex chars of SHA1(salt + client)
+    pub fn redact(&self, client: &str) -> String {
+        let mut hasher = sha1_smol::Sha1::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(client.as_bytes());
+        hasher.digest().to_string()[..8].to_string()
+    }
+
+    /// Redact every identifier `entries` carry: the client and the
+    /// Automation target
+    pub fn redact_entries(&self, entries: &mut [TccEntry]) {
+        for entry in entries {
+            entry.client = self.redact(&entry.client);
+            entry.indirect_object = entry.indirect_object.as_deref().map(|t| self.redact(t));
+        }
+    }
+}
+
+/// The status an `auth_value` stands for, as `auth_value_display` names it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthStatus {
+    Denied,
+    Granted,
+    Limited,
+    /// Any value other than 0, 2 and 3
+    Unknown,
+}
+
+impl AuthStatus {
+    pub fn of(auth_value: i32) -> Self {
+        match auth_value {
+            0 => AuthStatus::Denied,
+            2 => AuthStatus::Granted,
+            3 => AuthStatus::Limited,
+            _ => AuthStatus::Unknown,
+        }
+    }
+}
+
+/// Standard base64 (RFC 4648) with `=` padding
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Inverse of `base64`; `None` on anything but padded standard base64
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let sextet = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let chunks = text.as_bytes().chunks(4);
+    let last = chunks.len().saturating_sub(1);
+    for (i, chunk) in chunks.enumerate() {
+        let pad = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if pad > 2 || (pad > 0 && i != last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - pad] {
+            n = n << 6 | sextet(c)? as u32;
+        }
+        n <<= 6 * pad;
+        bytes.extend_from_slice(&n.to_be_bytes()[1..4 - pad]);
+    }
+    Some(bytes)
+}
+
+/// The `client_type` TCC stores for a client: 0 for a path, 1 for a bundle ID
+pub fn client_type_of(client: &str) -> i32 {
+    if client.starts_with('/') { 0 } else { 1 }
+}
+
+/// `path`, `bundle`, or the raw number for a `client_type` TCC does not document
+pub fn client_type_display(client_type: i32) -> String {
+    match client_type {
+        0 => "path".to_string(),
+        1 => "bundle".to_string(),
+        t => t.to_string(),
+    }
+}
+
+/// Map auth_value to a display string
 pub fn auth_value_display(value: i32) -> String {
     match value {
         0 => "denied".to_string(),
@@ -809,497 +4507,3272 @@ pub fn auth_value_display(value: i32) -> String {
         3 => "limited".to_string(),
         v => format!("unknown({})", v),
     }
-}
+}
+
+/// Map auth_reason to a display string
+pub fn auth_reason_display(value: i32) -> String {
+    match value {
+        0 => "none".to_string(),
+        1 => "error".to_string(),
+        2 => "user consent".to_string(),
+        3 => "user set".to_string(),
+        4 => "system set".to_string(),
+        5 => "service policy".to_string(),
+        6 => "MDM policy".to_string(),
+        7 => "override policy".to_string(),
+        8 => "missing usage string".to_string(),
+        9 => "prompt timeout".to_string(),
+        10 => "preflight unknown".to_string(),
+        11 => "entitled".to_string(),
+        12 => "app type policy".to_string(),
+        v => format!("unknown({})", v),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── Service name mapping ──────────────────────────────────────────
+
+    #[test]
+    fn known_service_keys_resolve_to_human_names() {
+        assert_eq!(
+            TccDb::service_display_name("kTCCServiceAccessibility"),
+            "Accessibility"
+        );
+        assert_eq!(
+            TccDb::service_display_name("kTCCServiceScreenCapture"),
+            "Screen Recording"
+        );
+        assert_eq!(TccDb::service_display_name("kTCCServiceCamera"), "Camera");
+        assert_eq!(
+            TccDb::service_display_name("kTCCServiceMicrophone"),
+            "Microphone"
+        );
+        assert_eq!(
+            TccDb::service_display_name("kTCCServiceSystemPolicyAllFiles"),
+            "Full Disk Access"
+        );
+        assert_eq!(TccDb::service_display_name("kTCCServicePhotos"), "Photos");
+    }
+
+    #[test]
+    fn unknown_service_key_with_prefix_strips_prefix() {
+        // Unknown key with kTCCService prefix should strip the prefix
+        assert_eq!(
+            TccDb::service_display_name("kTCCServiceSomethingNew"),
+            "SomethingNew"
+        );
+    }
+
+    #[test]
+    fn unknown_service_key_without_prefix_returns_raw() {
+        // Key without the standard prefix returns as-is
+        assert_eq!(
+            TccDb::service_display_name("com.example.custom"),
+            "com.example.custom"
+        );
+        assert_eq!(TccDb::service_display_name("FooBar"), "FooBar");
+    }
+
+    // ── History buckets ───────────────────────────────────────────────
+
+    fn entry_at(ts: i64) -> TccEntry {
+        let mut entry = make_entry("kTCCServiceCamera", "com.app.a", 2);
+        entry.last_modified_ts = ts;
+        entry
+    }
+
+    fn local_ts(y: i32, m: u32, d: u32) -> i64 {
+        Local
+            .with_ymd_and_hms(y, m, d, 12, 0, 0)
+            .single()
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn history_groups_by_day_oldest_first() {
+        let entries = [
+            entry_at(local_ts(2026, 2, 3)),
+            entry_at(local_ts(2026, 2, 1)),
+            entry_at(local_ts(2026, 2, 3)),
+            entry_at(local_ts(2026, 3, 9)),
+            entry_at(0),
+        ];
+        let (periods, unknown) = bucket_history(&entries, HistoryBucket::Day);
+        assert_eq!(
+            periods,
+            vec![
+                ("2026-02-01".to_string(), 1),
+                ("2026-02-03".to_string(), 2),
+                ("2026-03-09".to_string(), 1),
+            ]
+        );
+        assert_eq!(unknown, 1);
+    }
+
+    #[test]
+    fn history_groups_by_month() {
+        let entries = [
+            entry_at(local_ts(2026, 2, 3)),
+            entry_at(local_ts(2026, 2, 1)),
+            entry_at(local_ts(2026, 3, 9)),
+        ];
+        let (periods, unknown) = bucket_history(&entries, HistoryBucket::Month);
+        assert_eq!(
+            periods,
+            vec![("2026-02".to_string(), 2), ("2026-03".to_string(), 1)]
+        );
+        assert_eq!(unknown, 0);
+    }
+
+    #[test]
+    fn normalize_timestamp_converts_coredata() {
+        assert_eq!(TccDb::normalize_timestamp(0), 0);
+        assert_eq!(TccDb::normalize_timestamp(700_000_000), 1_678_307_200);
+        assert_eq!(TccDb::normalize_timestamp(1_700_000_000), 1_700_000_000);
+    }
+
+    // ── Client redaction ──────────────────────────────────────────────
+
+    #[test]
+    fn redact_is_stable_for_same_client() {
+        let redactor = Redactor::with_salt("salt");
+        let a = redactor.redact("com.example.app");
+        assert_eq!(a, redactor.redact("com.example.app"));
+        assert_eq!(a.len(), 8);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn redact_differs_across_clients_and_salts() {
+        let redactor = Redactor::with_salt("salt");
+        assert_ne!(
+            redactor.redact("com.example.app"),
+            redactor.redact("com.example.other")
+        );
+        assert_ne!(
+            redactor.redact("com.example.app"),
+            Redactor::with_salt("pepper").redact("com.example.app")
+        );
+    }
+
+    // ── Auth value display ────────────────────────────────────────────
+
+    #[test]
+    fn auth_value_denied() {
+        assert_eq!(auth_value_display(0), "denied");
+    }
+
+    #[test]
+    fn auth_value_granted() {
+        assert_eq!(auth_value_display(2), "granted");
+    }
+
+    #[test]
+    fn auth_value_limited() {
+        assert_eq!(auth_value_display(3), "limited");
+    }
+
+    #[test]
+    fn auth_reason_known_and_unknown_values() {
+        assert_eq!(auth_reason_display(0), "none");
+        assert_eq!(auth_reason_display(2), "user consent");
+        assert_eq!(auth_reason_display(6), "MDM policy");
+        assert_eq!(auth_reason_display(12), "app type policy");
+        assert_eq!(auth_reason_display(13), "unknown(13)");
+    }
+
+    #[test]
+    fn auth_value_unknown_values() {
+        assert_eq!(auth_value_display(1), "unknown(1)");
+        assert_eq!(auth_value_display(99), "unknown(99)");
+        assert_eq!(auth_value_display(-1), "unknown(-1)");
+    }
+
+    // ── DB open authorization hint mapping ───────────────────────────
+
+    #[test]
+    fn db_open_auth_denied_on_user_tcc_db_includes_fda_hint() {
+        let err = TccError::DbOpen {
+            path: PathBuf::from("/Users/test/Library/Application Support/com.apple.TCC/TCC.db"),
+            source: "opening database: authorization denied".to_string(),
+        };
+
+        let rendered = err.to_string();
+        assert!(rendered.contains("Failed to open"));
+        assert!(rendered.contains("Full Disk Access"));
+        assert!(rendered.contains("Terminal, iTerm, Ghostty, VS Code"));
+        assert!(rendered.contains("fully quit and reopen"));
+        assert!(rendered.contains("`sudo` does not bypass TCC"));
+    }
+
+    #[test]
+    fn db_open_auth_denied_on_system_tcc_db_includes_fda_hint() {
+        let err = TccError::DbOpen {
+            path: PathBuf::from("/Library/Application Support/com.apple.TCC/TCC.db"),
+            source: "Open authorization denied".to_string(),
+        };
+
+        let rendered = err.to_string();
+        assert!(rendered.contains("Full Disk Access"));
+    }
+
+    #[test]
+    fn db_open_auth_denied_on_non_tcc_path_does_not_include_hint() {
+        let err = TccError::DbOpen {
+            path: PathBuf::from("/tmp/not-tcc.db"),
+            source: "opening database: authorization denied".to_string(),
+        };
+
+        let rendered = err.to_string();
+        assert!(!rendered.contains("Full Disk Access"));
+        assert!(!rendered.contains("`sudo` does not bypass TCC"));
+    }
+
+    #[test]
+    fn db_open_non_auth_error_on_tcc_path_does_not_include_hint() {
+        let err = TccError::DbOpen {
+            path: PathBuf::from("/Library/Application Support/com.apple.TCC/TCC.db"),
+            source: "unable to open database file".to_string(),
+        };
+
+        let rendered = err.to_string();
+        assert!(!rendered.contains("Full Disk Access"));
+    }
+
+    // ── Compact path display ──────────────────────────────────────────
+
+    #[test]
+    fn compact_client_extracts_binary_name_from_path() {
+        assert_eq!(compact_client("/usr/local/bin/my-tool", 1), "my-tool");
+        assert_eq!(
+            compact_client("/Applications/Safari.app/Contents/MacOS/Safari", 1),
+            "Safari"
+        );
+        assert_eq!(compact_client("/my-tool", 1), "my-tool");
+    }
+
+    #[test]
+    fn compact_client_keeps_last_components_at_depth() {
+        let safari = "/Applications/Safari.app/Contents/MacOS/Safari";
+        assert_eq!(compact_client(safari, 2), ".../MacOS/Safari");
+        assert_eq!(compact_client(safari, 3), ".../Contents/MacOS/Safari");
+    }
+
+    #[test]
+    fn compact_client_path_shorter_than_depth_unchanged() {
+        assert_eq!(compact_client("/usr/bin/tool", 3), "/usr/bin/tool");
+        assert_eq!(compact_client("/usr/bin/tool", 5), "/usr/bin/tool");
+    }
+
+    #[test]
+    fn compact_client_returns_bundle_id_unchanged() {
+        assert_eq!(
+            compact_client("com.apple.Terminal", 1),
+            "com.apple.Terminal"
+        );
+        assert_eq!(
+            compact_client("org.mozilla.firefox", 2),
+            "org.mozilla.firefox"
+        );
+    }
+
+    #[test]
+    fn compact_client_root_path() {
+        // Edge case: root path "/"
+        assert_eq!(compact_client("/", 1), "/");
+    }
+
+    // ── Client/service filtering (partial match) ──────────────────────
+
+    #[test]
+    fn client_filter_partial_match() {
+        let entries = vec![
+            make_entry("kTCCServiceCamera", "com.apple.Terminal", 2),
+            make_entry("kTCCServiceMicrophone", "com.google.Chrome", 0),
+            make_entry("kTCCServiceCamera", "com.apple.Safari", 2),
+        ];
+
+        let filtered = filter_entries(entries, Some("apple"), None);
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.iter().all(|e| e.client.contains("apple")));
+    }
+
+    #[test]
+    fn service_filter_partial_match_display_name() {
+        let entries = vec![
+            make_entry("kTCCServiceCamera", "com.app.a", 2),
+            make_entry("kTCCServiceMicrophone", "com.app.b", 0),
+            make_entry("kTCCServiceScreenCapture", "com.app.c", 2),
+        ];
+
+        // Matches "Camera" display name
+        let filtered = filter_entries(entries, None, Some("Camer"));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].service_raw, "kTCCServiceCamera");
+    }
+
+    #[test]
+    fn service_filter_partial_match_raw_key() {
+        let entries = vec![
+            make_entry("kTCCServiceCamera", "com.app.a", 2),
+            make_entry("kTCCServiceMicrophone", "com.app.b", 0),
+        ];
+
+        // Matches raw key
+        let filtered = filter_entries(entries, None, Some("kTCCServiceMicro"));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].service_raw, "kTCCServiceMicrophone");
+    }
+
+    #[test]
+    fn filter_case_insensitive() {
+        let entries = vec![make_entry("kTCCServiceCamera", "com.Apple.Terminal", 2)];
+
+        let filtered = filter_entries(entries, Some("APPLE"), None);
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[test]
+    fn filter_no_match_returns_empty() {
+        let entries = vec![make_entry("kTCCServiceCamera", "com.apple.Terminal", 2)];
+
+        let filtered = filter_entries(entries, Some("nonexistent"), None);
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn service_key_filter_is_exact() {
+        let entries = [
+            make_entry("kTCCServicePhotos", "com.app.a", 2),
+            make_entry("kTCCServicePhotosAdd", "com.app.b", 2),
+        ];
+        let filter = ListFilter {
+            service_key: Some("kTCCServicePhotos"),
+            ..ListFilter::default()
+        };
+        let matched: Vec<_> = entries.iter().filter(|e| filter.matches(e)).collect();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].client, "com.app.a");
+
+        // The fuzzy filter catches both
+        let fuzzy = ListFilter {
+            service: Some("Photos"),
+            ..ListFilter::default()
+        };
+        assert_eq!(entries.iter().filter(|e| fuzzy.matches(e)).count(), 2);
+    }
+
+    #[test]
+    fn service_key_filter_is_case_sensitive_and_not_display_name() {
+        let entry = make_entry("kTCCServiceCamera", "com.app.a", 2);
+        for key in ["Camera", "ktccservicecamera", "kTCCServiceCam"] {
+            let filter = ListFilter {
+                service_key: Some(key),
+                ..ListFilter::default()
+            };
+            assert!(!filter.matches(&entry), "{} should not match", key);
+        }
+    }
+
+    #[test]
+    fn only_filter_matches_exactly_the_resolved_set() {
+        let entries = [
+            make_entry("kTCCServiceCamera", "com.app.a", 2),
+            make_entry("kTCCServiceMicrophone", "com.app.b", 2),
+            make_entry("kTCCServicePhotos", "com.app.c", 2),
+            make_entry("kTCCServicePhotosAdd", "com.app.d", 2),
+        ];
+        let only: Vec<Service> = ["Camera", "kTCCServicePhotos"]
+            .into_iter()
+            .map(|s| Service::try_from(s).unwrap())
+            .collect();
+        let filter = ListFilter {
+            only: Some(&only),
+            ..ListFilter::default()
+        };
+        let matched: Vec<_> = entries
+            .iter()
+            .filter(|e| filter.matches(e))
+            .map(|e| e.client.as_str())
+            .collect();
+        // Photos (Add Only) shares a substring but is a different key
+        assert_eq!(matched, vec!["com.app.a", "com.app.c"]);
+
+        // Combines with the other filters
+        let filter = ListFilter {
+            only: Some(&only),
+            client: Some("app.c"),
+            ..ListFilter::default()
+        };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 1);
+
+        let filter = ListFilter {
+            only: Some(&[]),
+            ..ListFilter::default()
+        };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 0);
+    }
+
+    #[test]
+    fn regex_filters_match_client_and_service() {
+        let entries = [
+            make_entry("kTCCServiceCamera", "com.apple.Terminal", 2),
+            make_entry("kTCCServiceCamera", "com.google.Chrome", 2),
+            make_entry("kTCCServiceMicrophone", "com.applesauce.app", 2),
+            make_entry("kTCCServicePhotos", "/usr/bin/com.apple.x", 2),
+        ];
+        let client_re = Regex::new(r"^com\.(apple|google)\.").unwrap();
+        let filter = ListFilter {
+            client_regex: Some(&client_re),
+            ..ListFilter::default()
+        };
+        let matched: Vec<_> = entries
+            .iter()
+            .filter(|e| filter.matches(e))
+            .map(|e| e.client.as_str())
+            .collect();
+        assert_eq!(matched, vec!["com.apple.Terminal", "com.google.Chrome"]);
+
+        // Matches either the display name or the raw key
+        let service_re = Regex::new(r"^(Camera|kTCCServicePhotos)$").unwrap();
+        let filter = ListFilter {
+            service_regex: Some(&service_re),
+            ..ListFilter::default()
+        };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 3);
+
+        let filter = ListFilter {
+            client_regex: Some(&client_re),
+            service_regex: Some(&service_re),
+            ..ListFilter::default()
+        };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 2);
+    }
+
+    #[test]
+    fn since_filter_keeps_recent_rows_only() {
+        let mut old = make_entry("kTCCServiceCamera", "com.app.old", 2);
+        old.last_modified_ts = 1_700_000_000;
+        let mut recent = make_entry("kTCCServiceCamera", "com.app.recent", 2);
+        recent.last_modified_ts = 1_800_000_000;
+        let mut unknown = make_entry("kTCCServiceCamera", "com.app.unknown", 2);
+        unknown.last_modified_ts = 0;
+        let entries = [old, recent, unknown];
+
+        let filter = ListFilter {
+            since: Some(1_750_000_000),
+            ..ListFilter::default()
+        };
+        let matched: Vec<_> = entries.iter().filter(|e| filter.matches(e)).collect();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].client, "com.app.recent");
+
+        // The boundary is inclusive
+        let filter = ListFilter {
+            since: Some(1_800_000_000),
+            ..ListFilter::default()
+        };
+        assert!(filter.matches(&entries[1]));
+
+        // N/A rows are excluded even from a delta since the epoch
+        let filter = ListFilter {
+            since: Some(0),
+            ..ListFilter::default()
+        };
+        assert!(!filter.matches(&entries[2]));
+    }
+
+    #[test]
+    fn until_filter_closes_the_window_inclusively_and_drops_na_rows() {
+        let mut old = make_entry("kTCCServiceCamera", "com.app.old", 2);
+        old.last_modified_ts = 1_700_000_000;
+        let mut recent = make_entry("kTCCServiceCamera", "com.app.recent", 2);
+        recent.last_modified_ts = 1_800_000_000;
+        let mut unknown = make_entry("kTCCServiceCamera", "com.app.unknown", 2);
+        unknown.last_modified_ts = 0;
+        let entries = [old, recent, unknown];
+
+        let matched = |since: Option<i64>, until: Option<i64>| -> Vec<&str> {
+            let filter = ListFilter {
+                since,
+                until,
+                ..ListFilter::default()
+            };
+            entries
+                .iter()
+                .filter(|e| filter.matches(e))
+                .map(|e| e.client.as_str())
+                .collect()
+        };
+        assert_eq!(matched(None, Some(1_750_000_000)), ["com.app.old"]);
+        assert_eq!(
+            matched(None, Some(1_800_000_000)),
+            ["com.app.old", "com.app.recent"]
+        );
+        assert_eq!(
+            matched(Some(1_700_000_000), Some(1_700_000_000)),
+            ["com.app.old"]
+        );
+        assert!(matched(Some(1_750_000_000), Some(1_760_000_000)).is_empty());
+    }
+
+    #[test]
+    fn list_filtered_by_service_key() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("kTCCServicePhotos", "com.example.a").unwrap();
+        db.grant("kTCCServicePhotosAdd", "com.example.b").unwrap();
+
+        let entries = db
+            .list_filtered(&ListFilter {
+                service_key: Some("kTCCServicePhotosAdd"),
+                ..ListFilter::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].client, "com.example.b");
+    }
+
+    // ── SERVICE_MAP sanity ────────────────────────────────────────────
+
+    #[test]
+    fn service_map_contains_expected_entries() {
+        assert!(SERVICE_MAP.contains_key("kTCCServiceAccessibility"));
+        assert!(SERVICE_MAP.contains_key("kTCCServiceCamera"));
+        assert!(SERVICE_MAP.contains_key("kTCCServiceMicrophone"));
+        assert!(SERVICE_MAP.contains_key("kTCCServiceScreenCapture"));
+        assert!(SERVICE_MAP.len() > 20);
+    }
+
+    // ── Format timestamp ──────────────────────────────────────────────
+
+    #[test]
+    fn format_timestamp_zero_returns_na() {
+        assert_eq!(TccDb::format_timestamp(0), "N/A");
+    }
+
+    #[test]
+    fn format_timestamp_large_unix_value() {
+        // A recent Unix timestamp should produce a valid date
+        let result = TccDb::format_timestamp(1_700_000_000);
+        assert!(result.contains("2023"), "Expected 2023 in: {}", result);
+    }
+
+    #[test]
+    fn format_timestamp_coredata_value() {
+        // CoreData timestamp (seconds since 2001-01-01) — small value
+        // 700_000_000 + 978_307_200 = 1_678_307_200 → 2023
+        let result = TccDb::format_timestamp(700_000_000);
+        assert!(
+            result.contains("2023") || result.contains("2024"),
+            "Got: {}",
+            result
+        );
+    }
+
+    // ── Helpers ───────────────────────────────────────────────────────
+
+    fn make_entry(service_raw: &str, client: &str, auth_value: i32) -> TccEntry {
+        TccEntry {
+            service_raw: service_raw.to_string(),
+            service_display: TccDb::service_display_name(service_raw),
+            client: client.to_string(),
+            client_type: client_type_of(client),
+            auth_value,
+            auth_reason: 0,
+            last_modified: "2024-01-01 00:00:00".to_string(),
+            last_modified_ts: 1_704_067_200,
+            is_system: false,
+            boot_uuid: None,
+            pid: None,
+            last_reminded: None,
+            csreq: None,
+            indirect_object: None,
+        }
+    }
+
+    /// Applies the same filtering logic as TccDb::list
+    fn filter_entries(
+        mut entries: Vec<TccEntry>,
+        client_filter: Option<&str>,
+        service_filter: Option<&str>,
+    ) -> Vec<TccEntry> {
+        if let Some(cf) = client_filter {
+            let cf_lower = cf.to_lowercase();
+            entries.retain(|e| e.client.to_lowercase().contains(&cf_lower));
+        }
+        if let Some(sf) = service_filter {
+            let sf_lower = sf.to_lowercase();
+            entries.retain(|e| {
+                e.service_display.to_lowercase().contains(&sf_lower)
+                    || e.service_raw.to_lowercase().contains(&sf_lower)
+            });
+        }
+        entries
+    }
+
+    // ── Resolve service name ──────────────────────────────────────────
+
+    fn make_test_db() -> TccDb {
+        TccDb::with_paths(
+            PathBuf::from("/nonexistent/user.db"),
+            PathBuf::from("/nonexistent/system.db"),
+            DbTarget::User,
+        )
+    }
+
+    #[test]
+    fn service_try_from_accepts_cli_spellings() {
+        for input in ["kTCCServiceCamera", "Camera", "camera", "CAMERA"] {
+            let service = Service::try_from(input).unwrap();
+            assert_eq!(service.key(), "kTCCServiceCamera");
+            assert_eq!(service.display_name(), "Camera");
+            assert_eq!(service.to_string(), "kTCCServiceCamera");
+        }
+        assert_eq!(
+            Service::try_from("BluetoothAlways").unwrap().key(),
+            "kTCCServiceBluetoothAlways"
+        );
+        assert!(matches!(
+            Service::try_from("Photo").unwrap_err(),
+            TccError::AmbiguousService { .. }
+        ));
+        assert!(matches!(
+            Service::try_from("NoSuchThing").unwrap_err(),
+            TccError::UnknownService(_)
+        ));
+    }
+
+    #[test]
+    fn service_properties() {
+        let accessibility = Service::try_from("Accessibility").unwrap();
+        assert!(accessibility.is_system());
+        assert!(!accessibility.needs_indirect_object());
+
+        let camera = Service::try_from("Camera").unwrap();
+        assert!(!camera.is_system());
+
+        let automation = Service::try_from("kTCCServiceAppleEvents").unwrap();
+        assert!(!automation.is_system());
+        assert!(automation.needs_indirect_object());
+    }
+
+    #[test]
+    fn every_system_service_is_in_service_map() {
+        for key in [
+            "kTCCServiceAccessibility",
+            "kTCCServiceScreenCapture",
+            "kTCCServiceListenEvent",
+            "kTCCServicePostEvent",
+            "kTCCServiceEndpointSecurityClient",
+            "kTCCServiceDeveloperTool",
+        ] {
+            assert!(Service::try_from(key).unwrap().is_system(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn resolve_exact_key() {
+        let db = make_test_db();
+        assert_eq!(
+            db.resolve_service_name("kTCCServiceCamera").unwrap(),
+            "kTCCServiceCamera"
+        );
+    }
+
+    #[test]
+    fn resolve_display_name() {
+        let db = make_test_db();
+        assert_eq!(
+            db.resolve_service_name("Camera").unwrap(),
+            "kTCCServiceCamera"
+        );
+    }
+
+    #[test]
+    fn resolve_case_insensitive() {
+        let db = make_test_db();
+        assert_eq!(
+            db.resolve_service_name("camera").unwrap(),
+            "kTCCServiceCamera"
+        );
+    }
+
+    #[test]
+    fn resolve_ambiguous_errors() {
+        let db = make_test_db();
+        // "Photo" matches both "Photos" and "Photos (Add Only)"
+        let err = db.resolve_service_name("Photo").unwrap_err();
+        assert!(
+            matches!(err, TccError::AmbiguousService { .. }),
+            "Expected AmbiguousService, got: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn resolve_unknown_errors() {
+        let db = make_test_db();
+        let err = db.resolve_service_name("NonexistentService").unwrap_err();
+        assert!(matches!(err, TccError::UnknownService(_)));
+    }
+
+    #[test]
+    fn resolve_short_name_via_prefix() {
+        let db = make_test_db();
+        assert_eq!(
+            db.resolve_service_name("BluetoothAlways").unwrap(),
+            "kTCCServiceBluetoothAlways"
+        );
+    }
+
+    // ── Write operation tests (temp DB) ───────────────────────────────
+
+    /// `access_schema_digest` of the table `make_temp_tcc_db` creates
+    const TEMP_DB_DIGEST: &str = "bfa7f9bc38";
+
+    fn make_temp_tcc_db() -> (tempfile::TempDir, TccDb) {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let db_path = dir.path().join("TCC.db");
+
+        let conn = Connection::open(&db_path).expect("failed to create temp db");
+        conn.execute_batch(
+            "CREATE TABLE access (
+                service TEXT NOT NULL,
+                client TEXT NOT NULL,
+                client_type INTEGER NOT NULL,
+                auth_value INTEGER NOT NULL DEFAULT 0,
+                auth_reason INTEGER NOT NULL DEFAULT 0,
+                auth_version INTEGER NOT NULL DEFAULT 1,
+                flags INTEGER NOT NULL DEFAULT 0,
+                last_modified INTEGER DEFAULT 0,
+                PRIMARY KEY (service, client, client_type)
+            );",
+        )
+        .expect("failed to create table");
+        drop(conn);
+
+        let db = TccDb::with_paths(db_path, dir.path().join("system_TCC.db"), DbTarget::User);
+
+        (dir, db)
+    }
+
+    #[test]
+    fn grant_inserts_entry() {
+        let (_dir, db) = make_temp_tcc_db();
+        let result = db.grant("Camera", "com.example.app");
+        assert!(result.is_ok(), "grant failed: {:?}", result.err());
+        assert!(result.unwrap().contains("Granted"));
+
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].service_raw, "kTCCServiceCamera");
+        assert_eq!(entries[0].client, "com.example.app");
+        assert_eq!(entries[0].auth_value, 2);
+    }
+
+    #[test]
+    fn grant_sets_client_type_for_path() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "/usr/bin/test").unwrap();
+
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        let client_type: i32 = conn
+            .query_row(
+                "SELECT client_type FROM access WHERE client = '/usr/bin/test'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(client_type, 0, "Path client should have client_type 0");
+    }
+
+    #[test]
+    fn list_reads_client_type_and_auth_reason_or_defaults_them() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "/usr/bin/test").unwrap();
+        db.grant("Camera", "com.example.app").unwrap();
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value) \
+             VALUES ('kTCCServiceMicrophone', 'com.example.odd', 7, 2)",
+            [],
+        )
+        .unwrap();
+        let types: Vec<(String, i32)> = db
+            .list(None, None)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.client, e.client_type))
+            .collect();
+        assert!(types.contains(&("/usr/bin/test".to_string(), 0)));
+        assert!(types.contains(&("com.example.app".to_string(), 1)));
+        assert!(types.contains(&("com.example.odd".to_string(), 7)));
+        conn.execute(
+            "UPDATE access SET auth_reason = 6 WHERE client = '/usr/bin/test'",
+            [],
+        )
+        .unwrap();
+        let reasons: Vec<(String, i32)> = db
+            .list(None, None)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.client, e.auth_reason))
+            .collect();
+        assert!(reasons.contains(&("/usr/bin/test".to_string(), 6)));
+
+        // A bare schema without client_type or last_modified takes the
+        // fallback query and infers the type from the client
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE access (service TEXT, client TEXT, auth_value INTEGER);
+                 INSERT INTO access VALUES ('kTCCServiceCamera', '/bin/sh', 2);",
+            )
+            .unwrap();
+        let bare = TccDb::with_paths(path, dir.path().join("none.db"), DbTarget::User);
+        let entries = bare.list(None, None).unwrap();
+        assert_eq!(entries[0].client_type, 0);
+        assert_eq!(entries[0].auth_reason, 0);
+        assert_eq!(entries[0].last_modified_ts, 0);
+    }
+
+    #[test]
+    fn dump_keeps_every_column_and_null_as_stored() {
+        use rusqlite::types::Value;
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+        Connection::open(&db.user_db_path)
+            .unwrap()
+            .execute(
+                "INSERT INTO access (service, client, client_type, auth_value, last_modified) \
+                 VALUES ('kTCCServiceMicrophone', '/bin/sh', 0, 0, NULL)",
+                [],
+            )
+            .unwrap();
+
+        let rows = db.dump().unwrap();
+        assert_eq!(rows.len(), 2);
+        let names: Vec<&str> = rows[0].columns.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "service",
+                "client",
+                "client_type",
+                "auth_value",
+                "auth_reason",
+                "auth_version",
+                "flags",
+                "last_modified"
+            ]
+        );
+        let mic = rows
+            .iter()
+            .find(|r| r.columns[1].1 == Value::Text("/bin/sh".to_string()))
+            .unwrap();
+        assert_eq!(mic.source, SourceLabel::User);
+        assert_eq!(
+            mic.columns[0].1,
+            Value::Text("kTCCServiceMicrophone".to_string())
+        );
+        assert_eq!(mic.columns[7].1, Value::Null);
+    }
+
+    #[test]
+    fn dump_skips_an_unreadable_db_with_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+        Connection::open(&user)
+            .unwrap()
+            .execute_batch(SELF_TEST_SCHEMA)
+            .unwrap();
+        let system = dir.path().join("system.db");
+        std::fs::write(&system, "not a database").unwrap();
+        let db = TccDb::with_paths(user, system.clone(), DbTarget::Default);
+        db.grant("Camera", "com.example.app").unwrap();
+
+        assert_eq!(db.dump().unwrap().len(), 1);
+        let warnings = db.take_warnings();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains(&system.display().to_string())),
+            "{:?}",
+            warnings
+        );
+    }
+
+    #[test]
+    fn base64_decode_inverts_base64() {
+        for bytes in [&b""[..], b"f", b"fo", b"foo", b"\xfa\xde\x0c\x00\xff"] {
+            assert_eq!(base64_decode(&base64(bytes)).as_deref(), Some(bytes));
+        }
+        for bad in ["Zg=", "Z===", "Zg==Zg==", "Zm9v!A=="] {
+            assert_eq!(base64_decode(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn restore_reinserts_dump_rows_and_skips_what_does_not_fit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(SELF_TEST_SCHEMA)
+            .unwrap();
+        let db = TccDb::with_paths(path.clone(), dir.path().join("none.db"), DbTarget::User);
+        let dump = dir.path().join("dump.json");
+        std::fs::write(
+            &dump,
+            r#"[
+              {"source":"user","row":{"service":"kTCCServiceCamera","client":"com.a","client_type":1,
+               "auth_value":2,"auth_reason":2,"auth_version":1,"csreq":"+t4MAA==","flags":null,"last_modified":1700000000}},
+              {"source":"user","row":{"service":"kTCCServiceNoSuchThing","client":"com.b","client_type":1,"auth_value":2}},
+              {"source":"user","row":{"service":"kTCCServiceMicrophone","client":"com.c","client_type":1,"auth_value":0,"bogus":1}}
+            ]"#,
+        )
+        .unwrap();
+
+        let dry = db.restore(&dump, true).unwrap();
+        assert_eq!(
+            dry,
+            RestoreOutcome {
+                restored: 1,
+                skipped: 2
+            }
+        );
+        assert!(db.list(None, None).unwrap().is_empty());
+
+        assert_eq!(db.restore(&dump, false).unwrap(), dry);
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].client, "com.a");
+        assert_eq!(
+            entries[0].csreq.as_deref(),
+            Some(&[0xfa, 0xde, 0x0c, 0x00][..])
+        );
+        assert_eq!(
+            entries[0].last_modified_ts,
+            TccDb::normalize_timestamp(1_700_000_000)
+        );
+        // INSERT OR REPLACE: restoring twice leaves one row
+        db.restore(&dump, false).unwrap();
+        assert_eq!(db.list(None, None).unwrap().len(), 1);
+
+        std::fs::write(&dump, "[{\"source\":\"user\"").unwrap();
+        assert!(matches!(
+            db.restore(&dump, false).unwrap_err(),
+            TccError::DumpInvalid { .. }
+        ));
+        std::fs::write(&dump, "{\"rows\":[]}").unwrap();
+        match db.restore(&dump, false).unwrap_err() {
+            TccError::DumpInvalid { reason, .. } => assert!(reason.contains("array"), "{}", reason),
+            e => panic!("expected DumpInvalid, got {}", e),
+        }
+    }
+
+    #[test]
+    fn restore_routes_rows_by_their_dump_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let (user, system) = (dir.path().join("user.db"), dir.path().join("system.db"));
+        for path in [&user, &system] {
+            Connection::open(path)
+                .unwrap()
+                .execute_batch(SELF_TEST_SCHEMA)
+                .unwrap();
+        }
+        let db = TccDb::with_paths(user.clone(), system.clone(), DbTarget::Default);
+        let dump = dir.path().join("dump.json");
+        // Full Disk Access writes go to the user DB, but this row came from the system DB;
+        // the row without a source goes where a grant of its service would
+        std::fs::write(
+            &dump,
+            r#"[
+              {"source":"system","row":{"service":"kTCCServiceSystemPolicyAllFiles","client":"com.a","client_type":1,"auth_value":2}},
+              {"row":{"service":"kTCCServiceCamera","client":"com.b","client_type":1,"auth_value":2}}
+            ]"#,
+        )
+        .unwrap();
+        if nix_is_root() {
+            db.restore(&dump, false).unwrap();
+            let clients = |path: &Path| -> Vec<String> {
+                let conn = Connection::open(path).unwrap();
+                let mut stmt = conn.prepare("SELECT client FROM access").unwrap();
+                stmt.query_map([], |row| row.get(0))
+                    .unwrap()
+                    .map(|c| c.unwrap())
+                    .collect()
+            };
+            assert_eq!(clients(&system), ["com.a"]);
+            assert_eq!(clients(&user), ["com.b"]);
+        } else {
+            // The system row needs root, so nothing is written at all
+            assert!(matches!(
+                db.restore(&dump, false).unwrap_err(),
+                TccError::NeedsRoot { .. }
+            ));
+            assert!(db.list(None, None).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn grant_sets_client_type_for_bundle_id() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        let client_type: i32 = conn
+            .query_row(
+                "SELECT client_type FROM access WHERE client = 'com.example.app'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(client_type, 1, "Bundle ID should have client_type 1");
+    }
+
+    #[test]
+    fn check_finds_exact_entry() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+        db.grant("Camera", "com.example.app.helper").unwrap();
+
+        let entry = db.check("Camera", "com.example.app").unwrap().unwrap();
+        assert_eq!(entry.client, "com.example.app");
+        assert_eq!(entry.auth_value, 2);
+        assert!(db.check("Camera", "com.example").unwrap().is_none());
+        assert!(db.check("Microphone", "com.example.app").unwrap().is_none());
+    }
+
+    #[test]
+    fn read_access_row_after_grant() {
+        let (_dir, db) = make_temp_tcc_db();
+        assert!(
+            db.read_access_row("Camera", "/usr/bin/test", None)
+                .unwrap()
+                .is_none()
+        );
+
+        db.grant("Camera", "/usr/bin/test").unwrap();
+        let row = db
+            .read_access_row("Camera", "/usr/bin/test", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.service_raw, "kTCCServiceCamera");
+        assert_eq!(row.client_type, 0);
+        assert_eq!(row.auth_value, 2);
+        assert_eq!(row.auth_version, 1);
+        assert!(row.last_modified > 0);
+        assert!(!row.is_system);
+    }
+
+    #[test]
+    fn grant_updates_a_row_stored_under_another_client_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        // A bundle ID would be inferred as client_type 1; macOS stored it as 0
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value, csreq) \
+             VALUES ('kTCCServiceCamera', 'com.example.app', 0, 2, X'FADE0C00')",
+            [],
+        )
+        .unwrap();
+        let db = TccDb::with_paths(path.clone(), dir.path().join("none.db"), DbTarget::User);
+
+        db.grant("Camera", "com.example.app").unwrap();
+        let (count, client_type, csreq): (i64, i32, Option<Vec<u8>>) = conn
+            .query_row(
+                "SELECT COUNT(*), client_type, csreq FROM access WHERE client = 'com.example.app'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 1, "the existing row is updated, not duplicated");
+        assert_eq!(client_type, 0);
+        assert_eq!(csreq, Some(vec![0xFA, 0xDE, 0x0C, 0x00]));
+    }
+
+    #[test]
+    fn grant_twice_preserves_existing_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        drop(conn);
+        let mut db = TccDb::with_paths(path.clone(), dir.path().join("none.db"), DbTarget::User);
+
+        db.grant("Camera", "com.example.app").unwrap();
+        // A managed entry: code requirement, flags and reason set by macOS
+        let conn = Connection::open(&path).unwrap();
+        conn.execute(
+            "UPDATE access SET csreq = X'FADE0C00', flags = 7, auth_reason = 4, \
+             auth_version = 3, auth_value = 0 WHERE client = 'com.example.app'",
+            [],
+        )
+        .unwrap();
+
+        // Denied on purpose: a plain grant leaves it alone
+        match db.grant("Camera", "com.example.app").unwrap_err() {
+            TccError::EntryExists { auth_value, .. } => assert_eq!(auth_value, 0),
+            e => panic!("expected EntryExists, got {}", e),
+        }
+        let still: i32 = conn
+            .query_row(
+                "SELECT auth_value FROM access WHERE client = 'com.example.app'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(still, 0);
+
+        db.set_force_grant(true);
+        db.grant("Camera", "com.example.app").unwrap();
+        let (count, auth_value, csreq, flags, reason, version): (
+            i64,
+            i32,
+            Option<Vec<u8>>,
+            i32,
+            i32,
+            i32,
+        ) = conn
+            .query_row(
+                "SELECT COUNT(*), auth_value, csreq, flags, auth_reason, auth_version \
+                 FROM access WHERE client = 'com.example.app'",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(auth_value, 2);
+        assert_eq!(csreq, Some(vec![0xFA, 0xDE, 0x0C, 0x00]));
+        assert_eq!((flags, reason, version), (7, 4, 3));
+    }
+
+    #[test]
+    fn grant_if_missing_creates_then_skips() {
+        let (_dir, db) = make_temp_tcc_db();
+        let (message, created) = db
+            .grant_if_missing("Camera", "com.example.a", None)
+            .unwrap();
+        assert!(created);
+        assert!(message.starts_with("Granted"), "got: {}", message);
+
+        // An existing denied row is left alone
+        db.disable("Camera", "com.example.a").unwrap();
+        let (message, created) = db
+            .grant_if_missing("Camera", "com.example.a", None)
+            .unwrap();
+        assert!(!created);
+        assert!(message.contains("already exists"), "got: {}", message);
+        let row = db
+            .read_access_row("Camera", "com.example.a", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.auth_value, 0);
+    }
+
+    #[test]
+    fn revoke_removes_entry() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+
+        let result = db.revoke("Camera", "com.example.app");
+        assert!(result.is_ok());
+
+        let entries = db.list(None, None).unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn revoke_nonexistent_returns_not_found() {
+        let (_dir, db) = make_temp_tcc_db();
+        let result = db.revoke("Camera", "com.nonexistent.app");
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), TccError::NotFound { .. }));
+    }
+
+    #[test]
+    fn enable_sets_auth_value_to_granted() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+        db.disable("Camera", "com.example.app").unwrap();
+
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries[0].auth_value, 0);
+
+        db.enable("Camera", "com.example.app").unwrap();
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries[0].auth_value, 2);
+    }
+
+    #[test]
+    fn disable_sets_auth_value_to_denied() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+
+        db.disable("Camera", "com.example.app").unwrap();
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries[0].auth_value, 0);
+    }
+
+    #[test]
+    fn set_all_clients_flips_only_that_service() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+        db.grant("Camera", "com.example.c").unwrap();
+        db.disable("Camera", "com.example.c").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
+
+        let (message, changed) = db.set_all_clients("Camera", "disable", 0).unwrap();
+        assert_eq!(changed, 2, "already-disabled rows are not counted");
+        assert!(
+            message
+                .contains("Disabled Camera [kTCCServiceCamera] access for all clients (2 changed)")
+        );
+
+        let entries = db.list(None, None).unwrap();
+        for entry in &entries {
+            let expected = if entry.service_raw == "kTCCServiceCamera" {
+                0
+            } else {
+                2
+            };
+            assert_eq!(entry.auth_value, expected, "{}", entry.client);
+        }
+
+        let (_, changed) = db.set_all_clients("Camera", "enable", 2).unwrap();
+        assert_eq!(changed, 3);
+    }
+
+    #[test]
+    fn get_matches_the_client_exactly() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+
+        let entry = db.get("Camera", "com.example.app").unwrap();
+        assert_eq!(entry.service_raw, "kTCCServiceCamera");
+        assert_eq!(entry.client, "com.example.app");
+        for client in ["com.example", "com.example.app.helper", "COM.EXAMPLE.APP"] {
+            assert!(matches!(
+                db.get("Camera", client),
+                Err(TccError::NotFound { .. })
+            ));
+        }
+        assert!(matches!(
+            db.get("Microphone", "com.example.app"),
+            Err(TccError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn counted_writes_report_zero_for_a_no_op() {
+        let (_dir, db) = make_temp_tcc_db();
+        assert_eq!(
+            db.grant_counted("Camera", "com.example.app", None)
+                .unwrap()
+                .1,
+            1
+        );
+        // Granting again still bumps the modification time
+        assert_eq!(
+            db.grant_counted("Camera", "com.example.app", None)
+                .unwrap()
+                .1,
+            1
+        );
+        assert_eq!(db.enable_counted("Camera", "com.example.app").unwrap().1, 0);
+        assert_eq!(
+            db.disable_counted("Camera", "com.example.app").unwrap().1,
+            1
+        );
+        assert_eq!(
+            db.disable_counted("Camera", "com.example.app").unwrap().1,
+            0
+        );
+    }
+
+    #[test]
+    fn noop_enable_skips_the_write() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        conn.execute("UPDATE access SET last_modified = 1", [])
+            .unwrap();
+        drop(conn);
+
+        // Not writable (except to root, where the unchanged timestamp
+        // still shows no write happened)
+        std::fs::set_permissions(&db.user_db_path, std::fs::Permissions::from_mode(0o444)).unwrap();
+        let message = db.enable("Camera", "com.example.app").unwrap();
+        assert!(message.contains("already enabled"), "got: {}", message);
+        let row = db
+            .read_access_row("Camera", "com.example.app", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.last_modified, 1);
+        std::fs::set_permissions(&db.user_db_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        // A real change still goes through the write path
+        let message = db.disable("Camera", "com.example.app").unwrap();
+        assert!(message.starts_with("Disabled"), "got: {}", message);
+    }
+
+    #[test]
+    fn enable_nonexistent_returns_not_found() {
+        let (_dir, db) = make_temp_tcc_db();
+        let result = db.enable("Camera", "com.nonexistent.app");
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), TccError::NotFound { .. }));
+    }
+
+    #[test]
+    fn reset_specific_client() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+
+        db.reset("Camera", Some("com.example.a")).unwrap();
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].client, "com.example.b");
+    }
+
+    #[test]
+    fn reset_all_entries_for_service() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
+
+        let result = db.reset("Camera", None).unwrap();
+        assert!(result.to_string().contains("2 deleted"));
+        assert_eq!((result.deleted, result.dbs), (2, 1));
+
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].service_raw, "kTCCServiceMicrophone");
+    }
+
+    #[test]
+    fn schema_policy_warn_proceeds_on_unknown_digest() {
+        let (_dir, db) = make_temp_tcc_db();
+        let (_, warning) = db
+            .open_writable(Service::try_from("kTCCServiceCamera").unwrap())
+            .unwrap();
+        assert!(warning.unwrap().contains("Unknown TCC database schema"));
+    }
+
+    #[test]
+    fn schema_policy_assume_known_skips_validation() {
+        let (_dir, mut db) = make_temp_tcc_db();
+        db.set_schema_policy(SchemaPolicy::AssumeKnown);
+        let (_, warning) = db
+            .open_writable(Service::try_from("kTCCServiceCamera").unwrap())
+            .unwrap();
+        assert!(warning.is_none());
+        assert!(db.grant("Camera", "com.example.app").is_ok());
+    }
+
+    #[test]
+    fn schema_policy_strict_rejects_unknown_digest() {
+        let (_dir, mut db) = make_temp_tcc_db();
+        db.set_schema_policy(SchemaPolicy::Strict);
+        let err = db.grant("Camera", "com.example.app").unwrap_err();
+        assert!(matches!(err, TccError::SchemaInvalid(_)), "got: {}", err);
+
+        db.set_schema_policy(SchemaPolicy::AssumeKnown);
+        assert!(db.list(None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reset_except_keeps_listed_clients() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+        db.grant("Camera", "com.example.keep").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
+
+        let result = db
+            .reset_except("Camera", &["com.example.keep".to_string()])
+            .unwrap()
+            .to_string();
+        assert!(result.contains("2 deleted"), "got: {}", result);
+        assert!(result.contains("1 kept"), "got: {}", result);
+
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            entries
+                .iter()
+                .any(|e| e.service_raw == "kTCCServiceCamera" && e.client == "com.example.keep")
+        );
+        assert!(
+            entries
+                .iter()
+                .any(|e| e.service_raw == "kTCCServiceMicrophone" && e.client == "com.example.a")
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn reset_selected_deletes_only_picked_entries() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
 
-    // ── Service name mapping ──────────────────────────────────────────
+        let listed = db.list(Some("com.example.a"), None).unwrap();
+        let picked: Vec<&TccEntry> = listed.iter().collect();
+        assert_eq!(picked.len(), 2);
+
+        // Only the Camera row of the picked set belongs to this service
+        let result = db.reset_selected("Camera", &picked).unwrap().to_string();
+        assert!(result.contains("1 deleted"), "got: {}", result);
+
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            !entries
+                .iter()
+                .any(|e| e.service_raw == "kTCCServiceCamera" && e.client == "com.example.a")
+        );
+    }
+
+    // ── Doctor ────────────────────────────────────────────────────────
 
     #[test]
-    fn known_service_keys_resolve_to_human_names() {
+    fn doctor_returns_fixed_check_ids() {
+        let (_dir, db) = make_temp_tcc_db();
+        let checks = db.doctor();
+        let ids: Vec<_> = checks.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["root", "sip", "fda", "schema", "write_probe"]);
+
+        let by_id = |id| checks.iter().find(|c| c.id == id).unwrap();
+        assert_eq!(by_id("fda").status, CheckStatus::Ok);
+        assert_eq!(by_id("write_probe").status, CheckStatus::Ok);
+        // The test schema is not one of the known macOS digests
+        assert_eq!(by_id("schema").status, CheckStatus::Warn);
+        assert!(by_id("schema").remediation.is_some());
+    }
+
+    #[test]
+    fn service_access_routes_and_reports_writability() {
+        let (dir, user_db) = make_temp_tcc_db();
+        // System DB path is never created, so system services are blocked
+        let db = TccDb::with_paths(
+            user_db.user_db_path.clone(),
+            dir.path().join("system_TCC.db"),
+            DbTarget::Default,
+        );
+        let access = db.service_access();
+        assert_eq!(access.len(), SERVICE_MAP.len());
+
+        let by_key = |key| access.iter().find(|a| a.service.key() == key).unwrap();
+        let camera = by_key("kTCCServiceCamera");
+        assert!(!camera.is_system);
+        assert_eq!(camera.path, db.user_db_path);
+        assert_eq!(camera.blocked_by, None);
+
+        let ax = by_key("kTCCServiceAccessibility");
+        assert!(ax.is_system);
+        assert_eq!(ax.path, db.system_db_path);
+        assert_eq!(ax.blocked_by, Some("DB not found"));
+
+        // --user routes everything to the user DB
+        assert!(
+            user_db
+                .service_access()
+                .iter()
+                .all(|a| !a.is_system && a.blocked_by.is_none())
+        );
+    }
+
+    #[test]
+    fn doctor_fix_checkpoints_a_lingering_wal() {
+        let (dir, db) = make_temp_tcc_db();
+        let path = dir.path().join("TCC.db");
+        // Keep the writer open, as tccd would, so its WAL is not folded in on close
+        let writer = Connection::open(&path).unwrap();
+        writer
+            .execute_batch("PRAGMA journal_mode=WAL; PRAGMA wal_autocheckpoint=0;")
+            .unwrap();
+        writer
+            .execute(
+                "INSERT INTO access (service, client, client_type, auth_value) \
+                 VALUES ('kTCCServiceCamera', 'com.example.app', 1, 2)",
+                [],
+            )
+            .unwrap();
+        let wal = dir.path().join("TCC.db-wal");
+        assert!(std::fs::metadata(&wal).unwrap().len() > 0);
+
+        let fixes = db.doctor_fix();
+        let ids: Vec<_> = fixes.iter().map(|f| f.id).collect();
+        assert_eq!(ids, ["wal_checkpoint"]);
+        let message = fixes[0].result.as_ref().unwrap();
+        assert!(message.starts_with("Checkpointed"), "{}", message);
+        assert_eq!(std::fs::metadata(&wal).unwrap().len(), 0);
+
+        // The backup holds the row that was only in the WAL
+        let backup = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.to_string_lossy().ends_with(".bak"))
+            .expect("backup written");
+        let count: i64 = Connection::open(&backup)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM access", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        drop(writer);
+
+        // Nothing left to checkpoint: no fix, no backup
+        assert!(db.doctor_fix().is_empty());
+    }
+
+    #[test]
+    fn doctor_fix_leaves_a_checkpointed_wal_alone() {
+        let (dir, db) = make_temp_tcc_db();
+        let path = dir.path().join("TCC.db");
+        let writer = Connection::open(&path).unwrap();
+        writer
+            .execute_batch("PRAGMA journal_mode=WAL; PRAGMA wal_autocheckpoint=0;")
+            .unwrap();
+        writer
+            .execute(
+                "INSERT INTO access (service, client, client_type, auth_value) \
+                 VALUES ('kTCCServiceCamera', 'com.example.app', 1, 2)",
+                [],
+            )
+            .unwrap();
+        assert!(wal_has_pending_frames(&path));
+
+        // Folded in, but the WAL keeps its frames as it does under tccd
+        writer
+            .query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))
+            .unwrap();
+        let wal = dir.path().join("TCC.db-wal");
+        assert!(std::fs::metadata(&wal).unwrap().len() > 0);
+        assert!(!wal_has_pending_frames(&path));
+
+        assert!(db.doctor_fix().is_empty());
+        let backups = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .path()
+                    .to_string_lossy()
+                    .ends_with(".bak")
+            })
+            .count();
+        assert_eq!(backups, 0);
+    }
+
+    #[test]
+    fn doctor_without_db_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = TccDb::with_home(DbTarget::User, dir.path());
+        let checks = db.doctor();
+        assert_eq!(checks.len(), 5);
+        for id in ["fda", "schema", "write_probe"] {
+            let check = checks.iter().find(|c| c.id == id).unwrap();
+            assert_eq!(check.status, CheckStatus::Warn, "{}", id);
+            assert_eq!(check.detail, "No TCC database found");
+        }
+    }
+
+    #[test]
+    fn doctor_fails_on_missing_access_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE other (x INTEGER);")
+            .unwrap();
+        let db = TccDb::with_paths(path, dir.path().join("system.db"), DbTarget::User);
+        let checks = db.doctor();
+        let schema = checks.iter().find(|c| c.id == "schema").unwrap();
+        assert_eq!(schema.status, CheckStatus::Fail);
+        // The file itself is readable
+        let fda = checks.iter().find(|c| c.id == "fda").unwrap();
+        assert_eq!(fda.status, CheckStatus::Ok);
+    }
+
+    // ── Duplicate repair ──────────────────────────────────────────────
+
+    /// A user DB whose access table lacks the primary key, so duplicates can exist
+    fn make_dup_db() -> (tempfile::TempDir, TccDb) {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let db_path = dir.path().join("TCC.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE access (
+                service TEXT NOT NULL,
+                client TEXT NOT NULL,
+                client_type INTEGER NOT NULL,
+                auth_value INTEGER NOT NULL DEFAULT 0,
+                auth_reason INTEGER NOT NULL DEFAULT 0,
+                auth_version INTEGER NOT NULL DEFAULT 1,
+                flags INTEGER,
+                last_modified INTEGER DEFAULT 0,
+                indirect_object_identifier TEXT NOT NULL DEFAULT 'UNUSED'
+            );
+            INSERT INTO access (service, client, client_type, auth_value, last_modified) VALUES
+                ('kTCCServiceCamera', 'com.dup', 1, 0, 100),
+                ('kTCCServiceCamera', 'com.dup', 1, 2, 300),
+                ('kTCCServiceCamera', 'com.dup', 1, 0, 200),
+                ('kTCCServiceCamera', 'com.single', 1, 2, 100),
+                ('kTCCServiceCamera', 'com.dup', 0, 2, 100);
+            INSERT INTO access (service, client, client_type, last_modified, indirect_object_identifier)
+            VALUES
+                ('kTCCServiceAppleEvents', 'com.script', 1, 100, 'com.apple.finder'),
+                ('kTCCServiceAppleEvents', 'com.script', 1, 100, 'com.apple.mail');",
+        )
+        .unwrap();
+        drop(conn);
+        let db = TccDb::with_paths(db_path, dir.path().join("system_TCC.db"), DbTarget::User);
+        (dir, db)
+    }
+
+    fn access_rows(db: &TccDb) -> Vec<(String, i32, i64)> {
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT client, client_type, last_modified FROM access ORDER BY rowid")
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn find_duplicates_reports_without_changing_db() {
+        let (_dir, db) = make_dup_db();
+        let groups = db.find_duplicates(false).unwrap();
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.service_raw, "kTCCServiceCamera");
+        assert_eq!(group.client, "com.dup");
+        assert_eq!(group.client_type, 1);
+        assert_eq!(group.indirect_object.as_deref(), Some("UNUSED"));
+        assert_eq!(group.rows, 3);
+        assert_eq!(group.removed, 0);
+        assert_eq!(access_rows(&db).len(), 7);
+    }
+
+    #[test]
+    fn fix_duplicates_keeps_most_recent_row() {
+        let (_dir, db) = make_dup_db();
+        let groups = db.find_duplicates(true).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].removed, 2);
+
+        let rows = access_rows(&db);
+        assert_eq!(rows.len(), 5);
+        let dup: Vec<_> = rows
+            .iter()
+            .filter(|(client, client_type, _)| client == "com.dup" && *client_type == 1)
+            .collect();
+        assert_eq!(dup, vec![&("com.dup".to_string(), 1, 300)]);
+
+        // Nothing left to fix
+        assert!(db.find_duplicates(false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn find_duplicates_skips_an_unreadable_db_with_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+        Connection::open(&user)
+            .unwrap()
+            .execute_batch(SELF_TEST_SCHEMA)
+            .unwrap();
+        let system = dir.path().join("system.db");
+        std::fs::write(&system, "not a database").unwrap();
+        let db = TccDb::with_paths(user, system.clone(), DbTarget::Default);
+        db.grant("Camera", "com.example.app").unwrap();
+
+        assert!(db.find_duplicates(false).unwrap().is_empty());
+        let warnings = db.take_warnings();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains(&system.display().to_string())),
+            "{:?}",
+            warnings
+        );
+    }
+
+    #[test]
+    fn find_duplicates_without_indirect_column() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        let groups = db.find_duplicates(false).unwrap();
+        assert!(groups.is_empty());
+    }
+
+    // ── Target path selection ─────────────────────────────────────────
+
+    fn make_path_db(target: DbTarget) -> TccDb {
+        TccDb::with_paths(
+            PathBuf::from("/tmp/user.db"),
+            PathBuf::from("/tmp/system.db"),
+            target,
+        )
+    }
+
+    fn labels(paths: Vec<(&Path, SourceLabel, bool)>) -> Vec<SourceLabel> {
+        paths.into_iter().map(|(_, label, _)| label).collect()
+    }
+
+    #[test]
+    fn target_paths_default_target() {
+        let db = make_path_db(DbTarget::Default);
+        let both = vec![SourceLabel::User, SourceLabel::System];
+        assert_eq!(labels(db.target_paths(Op::Read)), both);
+        assert_eq!(labels(db.target_paths(Op::Reset)), both);
+        assert_eq!(labels(db.target_paths(Op::Info)), both);
         assert_eq!(
-            TccDb::service_display_name("kTCCServiceAccessibility"),
-            "Accessibility"
+            labels(db.target_paths(Op::Write(
+                Service::try_from("kTCCServiceAccessibility").unwrap()
+            ))),
+            vec![SourceLabel::System]
         );
         assert_eq!(
-            TccDb::service_display_name("kTCCServiceScreenCapture"),
-            "Screen Recording"
+            labels(db.target_paths(Op::Write(Service::try_from("kTCCServiceCamera").unwrap()))),
+            vec![SourceLabel::User]
         );
-        assert_eq!(TccDb::service_display_name("kTCCServiceCamera"), "Camera");
+    }
+
+    #[test]
+    fn target_paths_user_target() {
+        let db = make_path_db(DbTarget::User);
+        let user = vec![SourceLabel::User];
+        assert_eq!(labels(db.target_paths(Op::Read)), user);
+        assert_eq!(labels(db.target_paths(Op::Reset)), user);
         assert_eq!(
-            TccDb::service_display_name("kTCCServiceMicrophone"),
-            "Microphone"
+            labels(db.target_paths(Op::Write(
+                Service::try_from("kTCCServiceAccessibility").unwrap()
+            ))),
+            user
         );
         assert_eq!(
-            TccDb::service_display_name("kTCCServiceSystemPolicyAllFiles"),
-            "Full Disk Access"
+            labels(db.target_paths(Op::Write(Service::try_from("kTCCServiceCamera").unwrap()))),
+            user
+        );
+        assert_eq!(
+            labels(db.target_paths(Op::Info)),
+            vec![SourceLabel::User, SourceLabel::System]
         );
-        assert_eq!(TccDb::service_display_name("kTCCServicePhotos"), "Photos");
     }
 
     #[test]
-    fn unknown_service_key_with_prefix_strips_prefix() {
-        // Unknown key with kTCCService prefix should strip the prefix
-        assert_eq!(
-            TccDb::service_display_name("kTCCServiceSomethingNew"),
-            "SomethingNew"
+    fn target_paths_file_target() {
+        let db = TccDb::with_file(PathBuf::from("/tmp/copy.db"));
+        for op in [
+            Op::Read,
+            Op::Reset,
+            Op::Info,
+            Op::Write(Service::try_from("kTCCServiceAccessibility").unwrap()),
+        ] {
+            assert_eq!(
+                db.target_paths(op),
+                vec![(Path::new("/tmp/copy.db"), SourceLabel::User, false)]
+            );
+        }
+    }
+
+    #[test]
+    fn file_target_needs_root_only_when_the_file_is_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dir, db) = make_temp_tcc_db();
+        let file = TccDb::with_file(db.user_db_path.clone());
+        // A system DB service goes to the file, with no root check
+        file.grant("Accessibility", "com.example.app").unwrap();
+        assert!(
+            !file
+                .get("Accessibility", "com.example.app")
+                .unwrap()
+                .is_system
         );
+
+        std::fs::set_permissions(&db.user_db_path, std::fs::Permissions::from_mode(0o444)).unwrap();
+        let result = file.revoke("Accessibility", "com.example.app");
+        std::fs::set_permissions(&db.user_db_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        if nix_is_root() {
+            assert!(result.is_ok());
+        } else {
+            match result {
+                Err(TccError::NeedsRoot { message }) => {
+                    assert!(message.contains("is not writable"), "got: {}", message)
+                }
+                other => panic!("expected NeedsRoot, got {:?}", other),
+            }
+        }
     }
 
     #[test]
-    fn unknown_service_key_without_prefix_returns_raw() {
-        // Key without the standard prefix returns as-is
+    fn target_paths_is_system_flag_matches_path() {
+        let db = make_path_db(DbTarget::Default);
+        for (path, label, is_system) in db.target_paths(Op::Read) {
+            assert_eq!(is_system, label == SourceLabel::System);
+            let expected = if is_system {
+                "/tmp/system.db"
+            } else {
+                "/tmp/user.db"
+            };
+            assert_eq!(path, Path::new(expected));
+        }
+    }
+
+    #[test]
+    fn self_test_passes_every_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let checks = run_self_test(dir.path());
+        assert_eq!(checks.len(), 6);
+        for check in &checks {
+            assert!(
+                check.result.is_ok(),
+                "{} failed: {:?}",
+                check.name,
+                check.result
+            );
+        }
+    }
+
+    #[test]
+    fn self_test_cleans_up_its_temp_dir() {
+        // Its own parent, so other runs sharing the temp dir cannot interfere
+        let parent = tempfile::tempdir().unwrap();
+        let checks = self_test_in(parent.path());
+        assert!(checks.iter().all(|c| c.result.is_ok()), "{:?}", checks);
+        let leftover: Vec<_> = std::fs::read_dir(parent.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name())
+            .collect();
+        assert!(leftover.is_empty(), "self-test left {:?} behind", leftover);
+    }
+
+    // ── PID client resolution ─────────────────────────────────────────
+
+    #[test]
+    fn pid_resolves_to_bundle_id_inside_app() {
+        let client = resolve_pid_client_with(
+            42,
+            |_| Some(PathBuf::from("/Applications/Foo.app/Contents/MacOS/Foo")),
+            |bundle| {
+                assert_eq!(bundle, Path::new("/Applications/Foo.app"));
+                Some("com.example.foo".to_string())
+            },
+        )
+        .unwrap();
+        assert_eq!(client, "com.example.foo");
+    }
+
+    #[test]
+    fn pid_resolves_to_path_for_bare_binary() {
+        let client = resolve_pid_client_with(
+            42,
+            |_| Some(PathBuf::from("/usr/local/bin/my-tool")),
+            |_| panic!("no bundle lookup expected"),
+        )
+        .unwrap();
+        assert_eq!(client, "/usr/local/bin/my-tool");
+    }
+
+    #[test]
+    fn pid_falls_back_to_path_when_bundle_id_unreadable() {
+        let client = resolve_pid_client_with(
+            42,
+            |_| Some(PathBuf::from("/Applications/Foo.app/Contents/MacOS/Foo")),
+            |_| None,
+        )
+        .unwrap();
+        assert_eq!(client, "/Applications/Foo.app/Contents/MacOS/Foo");
+    }
+
+    #[test]
+    fn client_file_is_trimmed_and_must_not_be_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("client");
+        std::fs::write(&path, "  /Applications/My App.app/Contents/MacOS/My App\n").unwrap();
         assert_eq!(
-            TccDb::service_display_name("com.example.custom"),
-            "com.example.custom"
+            read_client_file(&path).unwrap(),
+            "/Applications/My App.app/Contents/MacOS/My App"
         );
-        assert_eq!(TccDb::service_display_name("FooBar"), "FooBar");
+
+        std::fs::write(&path, " \n").unwrap();
+        let err = read_client_file(&path).unwrap_err();
+        assert!(err.to_string().contains("file is empty"), "{}", err);
+        let err = read_client_file(&dir.path().join("missing")).unwrap_err();
+        assert!(matches!(err, TccError::ClientFile { .. }));
     }
 
-    // ── Auth value display ────────────────────────────────────────────
+    #[test]
+    fn pid_missing_process_errors() {
+        let err = resolve_pid_client_with(42, |_| None, |_| None).unwrap_err();
+        assert!(matches!(err, TccError::PidNotResolved { pid: 42, .. }));
+        let err = resolve_pid_client_with(0, |_| None, |_| None).unwrap_err();
+        assert!(matches!(err, TccError::PidNotResolved { pid: 0, .. }));
+    }
+
+    // ── Effective authorization ───────────────────────────────────────
+
+    #[test]
+    fn effective_probes_only_supported_services_for_this_process() {
+        let ax = Service::try_from("Accessibility").unwrap();
+        let camera = Service::try_from("Camera").unwrap();
+        let me = Some("/usr/local/bin/tcc");
+
+        let probed = effective_authorization_with(ax, "/usr/local/bin/tcc", me, |_| Some(false));
+        assert_eq!(probed, EffectiveAuth::Probed(false));
+
+        assert!(matches!(
+            effective_authorization_with(camera, "/usr/local/bin/tcc", me, |_| Some(true)),
+            EffectiveAuth::DbOnly(reason) if reason.contains("no runtime probe")
+        ));
+        assert!(matches!(
+            effective_authorization_with(ax, "com.other.app", me, |_| Some(true)),
+            EffectiveAuth::DbOnly(reason) if reason.contains("this process")
+        ));
+        assert!(matches!(
+            effective_authorization_with(ax, "/usr/local/bin/tcc", me, |_| None),
+            EffectiveAuth::DbOnly(reason) if reason.contains("unavailable")
+        ));
+    }
+
+    // ── Modifiable filter ─────────────────────────────────────────────
+
+    #[test]
+    fn only_modifiable_drops_entries_in_unwritable_system_db() {
+        let (dir, user_db) = make_temp_tcc_db();
+        let user = Connection::open(&user_db.user_db_path).unwrap();
+        user.execute_batch(
+            "INSERT INTO access (service, client, client_type, auth_value) VALUES
+                ('kTCCServiceCamera', 'com.example.app', 1, 2),
+                ('kTCCServiceAccessibility', 'com.example.app', 1, 2);",
+        )
+        .unwrap();
+
+        // System path does not exist, so it can never be written
+        let db = TccDb::with_paths(
+            user_db.user_db_path.clone(),
+            dir.path().join("missing/system.db"),
+            DbTarget::Default,
+        );
+        assert_eq!(db.list(None, None).unwrap().len(), 2);
+
+        let entries = db
+            .list_filtered(&ListFilter {
+                only_modifiable: true,
+                ..ListFilter::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].service_raw, "kTCCServiceCamera");
+    }
+
+    #[test]
+    fn source_filter_keeps_rows_of_one_db() {
+        let (dir, user_db) = make_temp_tcc_db();
+        let system_path = dir.path().join("system_TCC.db");
+        std::fs::copy(&user_db.user_db_path, &system_path).unwrap();
+        Connection::open(&user_db.user_db_path)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO access (service, client, client_type, auth_value) VALUES
+                    ('kTCCServiceCamera', 'com.example.user', 1, 2),
+                    ('kTCCServiceMicrophone', 'com.example.user', 1, 0);",
+            )
+            .unwrap();
+        Connection::open(&system_path)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO access (service, client, client_type, auth_value) VALUES
+                    ('kTCCServiceAccessibility', 'com.example.system', 1, 2);",
+            )
+            .unwrap();
+
+        let db = TccDb::with_paths(user_db.user_db_path.clone(), system_path, DbTarget::Default);
+        assert_eq!(db.list_filtered(&ListFilter::default()).unwrap().len(), 3);
+        for (source, expected) in [
+            (
+                SourceLabel::User,
+                vec!["com.example.user", "com.example.user"],
+            ),
+            (SourceLabel::System, vec!["com.example.system"]),
+        ] {
+            let filter = ListFilter {
+                source: Some(source),
+                ..ListFilter::default()
+            };
+            let MeasuredList {
+                entries, counts, ..
+            } = db.list_measured(&filter).unwrap();
+            let clients: Vec<_> = entries.iter().map(|e| e.client.as_str()).collect();
+            assert_eq!(clients, expected);
+            // Both DBs were still read
+            assert_eq!(counts.iter().map(|c| c.rows).sum::<usize>(), 3);
+        }
+    }
+
+    // ── Cross-DB dedupe ───────────────────────────────────────────────
+
+    #[test]
+    fn dedupe_prefers_system_row() {
+        let (dir, user_db) = make_temp_tcc_db();
+        let system_path = dir.path().join("system_TCC.db");
+        std::fs::copy(&user_db.user_db_path, &system_path).unwrap();
+
+        let user = Connection::open(&user_db.user_db_path).unwrap();
+        user.execute_batch(
+            "INSERT INTO access (service, client, client_type, auth_value) VALUES
+                ('kTCCServiceCamera', 'com.example.app', 1, 0),
+                ('kTCCServiceCamera', 'com.example.user-only', 1, 2);",
+        )
+        .unwrap();
+        let system = Connection::open(&system_path).unwrap();
+        system
+            .execute_batch(
+                "INSERT INTO access (service, client, client_type, auth_value) VALUES
+                    ('kTCCServiceCamera', 'com.example.app', 1, 2);",
+            )
+            .unwrap();
+
+        let db = TccDb::with_paths(user_db.user_db_path.clone(), system_path, DbTarget::Default);
+        let mut entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 3, "both copies are listed by default");
+
+        assert_eq!(dedupe_entries(&mut entries), 1);
+        assert_eq!(entries.len(), 2);
+        let app = entries
+            .iter()
+            .find(|e| e.client == "com.example.app")
+            .unwrap();
+        assert!(app.is_system);
+        assert_eq!(app.auth_value, 2);
+        assert!(entries.iter().any(|e| e.client == "com.example.user-only"));
+    }
+
+    // ── Info sidecar probes ───────────────────────────────────────────
+
+    #[test]
+    fn info_reports_wal_and_shm_sidecars() {
+        let (dir, db) = make_temp_tcc_db();
+        let InfoReport { lines, journals } = db.info();
+        assert!(lines.contains(&"  Journal mode: delete".to_string()));
+        assert!(lines.contains(&"  WAL file: none".to_string()));
+        assert!(lines.contains(&"  SHM file: none".to_string()));
+        assert_eq!(journals.len(), 1);
+        assert_eq!(journals[0].path, dir.path().join("TCC.db"));
+        assert!(!journals[0].is_system);
+        assert_eq!(journals[0].journal_mode.as_deref(), Some("delete"));
+        assert_eq!((journals[0].wal_bytes, journals[0].shm_bytes), (None, None));
+
+        std::fs::write(dir.path().join("TCC.db-wal"), vec![0u8; 4096]).unwrap();
+        std::fs::write(dir.path().join("TCC.db-shm"), vec![0u8; 32 * 1024]).unwrap();
+        let InfoReport { lines, journals } = db.info();
+        assert!(lines.contains(&"  WAL file: 4096 bytes".to_string()));
+        assert!(lines.contains(&"  SHM file: 32768 bytes".to_string()));
+        assert_eq!(journals[0].wal_bytes, Some(4096));
+        assert_eq!(journals[0].shm_bytes, Some(32 * 1024));
+    }
+
+    // ── Partial reads ─────────────────────────────────────────────────
+
+    #[test]
+    fn unreadable_db_yields_partial_results_and_diagnostic() {
+        let (dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+
+        let bogus = dir.path().join("bogus.db");
+        std::fs::write(
+            &bogus,
+            "this is not a sqlite database at all, just some text",
+        )
+        .unwrap();
+        let db = TccDb::with_paths(db.user_db_path.clone(), bogus.clone(), DbTarget::Default);
+
+        let MeasuredList {
+            entries, failures, ..
+        } = db.list_measured(&ListFilter::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].client, "com.example.app");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].source, SourceLabel::System);
+        assert_eq!(failures[0].path, bogus);
+        assert!(!failures[0].message.is_empty());
+
+        // The plain listing still succeeds with the readable rows
+        assert_eq!(db.list(None, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn warnings_are_held_on_their_own_db() {
+        let (_dir, db) = make_temp_tcc_db();
+        let (_other_dir, other) = make_temp_tcc_db();
+        Connection::open(&db.user_db_path)
+            .unwrap()
+            .execute(
+                "INSERT INTO access (service, client, client_type, auth_value)
+                 VALUES ('kTCCServiceCamera', 'com.example.bad', 1, 'granted')",
+                [],
+            )
+            .unwrap();
 
-    #[test]
-    fn auth_value_denied() {
-        assert_eq!(auth_value_display(0), "denied");
+        assert!(db.list(None, None).unwrap().is_empty());
+        assert!(other.list(None, None).unwrap().is_empty());
+        assert!(other.take_warnings().is_empty());
+        let warnings = db.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("skipping malformed row"));
+        assert!(db.take_warnings().is_empty());
     }
 
     #[test]
-    fn auth_value_granted() {
-        assert_eq!(auth_value_display(2), "granted");
+    fn list_entries_returns_skipped_rows_and_unreadable_dbs_as_warnings() {
+        let (dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+        Connection::open(&db.user_db_path)
+            .unwrap()
+            .execute(
+                "INSERT INTO access (service, client, client_type, auth_value)
+                 VALUES ('kTCCServiceMicrophone', 'com.example.bad', 1, 'granted')",
+                [],
+            )
+            .unwrap();
+        let bogus = dir.path().join("bogus.db");
+        std::fs::write(&bogus, "not a sqlite database").unwrap();
+        let db = TccDb::with_paths(db.user_db_path.clone(), bogus, DbTarget::Default);
+
+        let result = db.list_entries(&ListFilter::default()).unwrap();
+        assert_eq!(result.entries.len(), 1);
+        assert_eq!(result.warnings.len(), 2, "{:?}", result.warnings);
+        assert!(result.warnings[0].starts_with("skipping malformed row"));
+        assert!(result.warnings[1].contains("bogus.db"));
+
+        // The per-source listing keeps the same warnings on the iterator
+        let filter = ListFilter::default();
+        let mut iter = db.list_iter(&filter).unwrap();
+        assert_eq!(iter.by_ref().count(), 1);
+        assert_eq!(iter.take_warnings(), result.warnings);
     }
 
     #[test]
-    fn auth_value_limited() {
-        assert_eq!(auth_value_display(3), "limited");
+    fn automation_grant_without_a_target_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(SELF_TEST_SCHEMA)
+            .unwrap();
+        let db = TccDb::with_paths(path, dir.path().join("system_TCC.db"), DbTarget::User);
+
+        db.grant_counted("AppleEvents", "com.foo", Some("com.apple.finder"))
+            .unwrap();
+        db.grant("Camera", "com.foo").unwrap();
+        let untargeted = |warnings: Vec<String>| {
+            warnings
+                .into_iter()
+                .filter(|w| w.contains("scoped to a target app"))
+                .count()
+        };
+        assert_eq!(untargeted(db.take_warnings()), 0);
+
+        db.grant("AppleEvents", "com.foo").unwrap();
+        assert_eq!(untargeted(db.take_warnings()), 1);
     }
 
     #[test]
-    fn auth_value_unknown_values() {
-        assert_eq!(auth_value_display(1), "unknown(1)");
-        assert_eq!(auth_value_display(99), "unknown(99)");
-        assert_eq!(auth_value_display(-1), "unknown(-1)");
-    }
+    fn automation_entries_are_keyed_and_listed_by_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(SELF_TEST_SCHEMA)
+            .unwrap();
+        let db = TccDb::with_paths(path, dir.path().join("system_TCC.db"), DbTarget::User);
+
+        for target in ["com.apple.systemevents", "com.apple.finder"] {
+            let (message, changed) = db
+                .grant_counted("AppleEvents", "com.foo", Some(target))
+                .unwrap();
+            assert!(message.ends_with(&format!("to control '{}'", target)));
+            assert_eq!(changed, 1);
+        }
+        db.grant("Camera", "com.foo").unwrap();
 
-    // ── DB open authorization hint mapping ───────────────────────────
+        let mut targets: Vec<Option<String>> = db
+            .list(Some("com.foo"), None)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.indirect_object)
+            .collect();
+        targets.sort();
+        // Camera's UNUSED placeholder reads as no target
+        assert_eq!(
+            targets,
+            [
+                None,
+                Some("com.apple.finder".to_string()),
+                Some("com.apple.systemevents".to_string())
+            ]
+        );
+        assert!(
+            db.read_access_row("AppleEvents", "com.foo", Some("com.apple.finder"))
+                .unwrap()
+                .is_some()
+        );
 
-    #[test]
-    fn db_open_auth_denied_on_user_tcc_db_includes_fda_hint() {
-        let err = TccError::DbOpen {
-            path: PathBuf::from("/Users/test/Library/Application Support/com.apple.TCC/TCC.db"),
-            source: "opening database: authorization denied".to_string(),
-        };
+        let (_, deleted) = db
+            .revoke_capturing("AppleEvents", "com.foo", Some("com.apple.finder"))
+            .unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert!(matches!(
+            db.revoke_capturing("AppleEvents", "com.foo", Some("com.apple.finder")),
+            Err(TccError::NotFound { .. })
+        ));
+        let left = db.list(None, Some("Apple Events")).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(
+            left[0].indirect_object.as_deref(),
+            Some("com.apple.systemevents")
+        );
 
-        let rendered = err.to_string();
-        assert!(rendered.contains("Failed to open"));
-        assert!(rendered.contains("Full Disk Access"));
-        assert!(rendered.contains("Terminal, iTerm, Ghostty, VS Code"));
-        assert!(rendered.contains("fully quit and reopen"));
-        assert!(rendered.contains("`sudo` does not bypass TCC"));
+        // A schema without the column cannot hold a target
+        let (_dir, old) = make_temp_tcc_db();
+        assert!(matches!(
+            old.grant_counted("AppleEvents", "com.foo", Some("com.apple.finder")),
+            Err(TccError::SchemaInvalid(_))
+        ));
     }
 
     #[test]
-    fn db_open_auth_denied_on_system_tcc_db_includes_fda_hint() {
-        let err = TccError::DbOpen {
-            path: PathBuf::from("/Library/Application Support/com.apple.TCC/TCC.db"),
-            source: "Open authorization denied".to_string(),
+    fn boot_uuid_filter_keeps_current_session_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        for (client, boot) in [("com.app.now", "BOOT-B"), ("com.app.old", "BOOT-A")] {
+            conn.execute(
+                "INSERT INTO access (service, client, client_type, auth_value, boot_uuid) \
+                 VALUES ('kTCCServiceCamera', ?1, 0, 2, ?2)",
+                rusqlite::params![client, boot],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value) \
+             VALUES ('kTCCServiceCamera', 'com.app.unused', 0, 2)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        let db = TccDb::with_paths(path, dir.path().join("none.db"), DbTarget::User);
+
+        let filter = ListFilter {
+            boot_uuid: Some("BOOT-B"),
+            ..ListFilter::default()
         };
+        let entries = db.list_filtered(&filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].client, "com.app.now");
+        assert_eq!(entries[0].boot_uuid.as_deref(), Some("BOOT-B"));
 
-        let rendered = err.to_string();
-        assert!(rendered.contains("Full Disk Access"));
+        // Schemas without the column are left unfiltered
+        let (_dir, legacy) = make_temp_tcc_db();
+        legacy.grant("Camera", "com.example.app").unwrap();
+        let entries = legacy.list_filtered(&filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].boot_uuid, None);
     }
 
     #[test]
-    fn db_open_auth_denied_on_non_tcc_path_does_not_include_hint() {
-        let err = TccError::DbOpen {
-            path: PathBuf::from("/tmp/not-tcc.db"),
-            source: "opening database: authorization denied".to_string(),
-        };
+    fn writes_report_every_row_sharing_service_and_client() {
+        let (dir, db) = make_temp_tcc_db();
+        let conn = Connection::open(dir.path().join("TCC.db")).unwrap();
+        for client_type in [0, 1] {
+            conn.execute(
+                "INSERT INTO access (service, client, client_type, auth_value) \
+                 VALUES ('kTCCServiceCamera', 'com.example.app', ?1, 2)",
+                [client_type],
+            )
+            .unwrap();
+        }
+        drop(conn);
 
-        let rendered = err.to_string();
-        assert!(!rendered.contains("Full Disk Access"));
-        assert!(!rendered.contains("`sudo` does not bypass TCC"));
+        let message = db.disable("Camera", "com.example.app").unwrap();
+        assert!(message.ends_with("(2 rows: this pair has one per client type or target)"));
+        assert!(
+            db.list(None, None)
+                .unwrap()
+                .iter()
+                .all(|e| e.auth_value == 0)
+        );
+        let message = db.enable("Camera", "com.example.app").unwrap();
+        assert!(message.contains("(2 rows"), "{}", message);
+
+        let (message, deleted) = db
+            .revoke_capturing("Camera", "com.example.app", None)
+            .unwrap();
+        assert_eq!(deleted.len(), 2);
+        assert!(message.contains("(2 rows"), "{}", message);
+
+        // A single row keeps the plain message
+        db.grant("Camera", "com.example.app").unwrap();
+        let message = db.revoke("Camera", "com.example.app").unwrap();
+        assert!(!message.contains("rows"), "{}", message);
     }
 
     #[test]
-    fn db_open_non_auth_error_on_tcc_path_does_not_include_hint() {
-        let err = TccError::DbOpen {
-            path: PathBuf::from("/Library/Application Support/com.apple.TCC/TCC.db"),
-            source: "unable to open database file".to_string(),
+    fn revoke_and_reset_report_the_deleted_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        for client in ["com.app.a", "com.app.b", "com.app.c"] {
+            conn.execute(
+                "INSERT INTO access (service, client, client_type, auth_value, csreq) \
+                 VALUES ('kTCCServiceCamera', ?1, 0, 2, X'FADE0C00')",
+                [client],
+            )
+            .unwrap();
+        }
+        drop(conn);
+        let db = TccDb::with_paths(path, dir.path().join("none.db"), DbTarget::User);
+        let column = |row: &RawRow, name: &str| {
+            row.columns
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.clone())
+                .unwrap()
         };
 
-        let rendered = err.to_string();
-        assert!(!rendered.contains("Full Disk Access"));
-    }
-
-    // ── Compact path display ──────────────────────────────────────────
+        let (_, deleted) = db.revoke_capturing("Camera", "com.app.a", None).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].source, SourceLabel::User);
+        assert_eq!(
+            column(&deleted[0], "client"),
+            rusqlite::types::Value::Text("com.app.a".to_string())
+        );
+        assert_eq!(
+            column(&deleted[0], "csreq"),
+            rusqlite::types::Value::Blob(vec![0xFA, 0xDE, 0x0C, 0x00])
+        );
+        // Every column of the table is captured
+        assert_eq!(deleted[0].columns.len(), 17);
 
-    #[test]
-    fn compact_client_extracts_binary_name_from_path() {
-        assert_eq!(compact_client("/usr/local/bin/my-tool"), "my-tool");
+        let outcome = db
+            .reset_except("Camera", &["com.app.c".to_string()])
+            .unwrap();
+        assert_eq!(outcome.deleted, 1);
+        assert_eq!(outcome.deleted_rows.len(), 1);
         assert_eq!(
-            compact_client("/Applications/Safari.app/Contents/MacOS/Safari"),
-            "Safari"
+            column(&outcome.deleted_rows[0], "client"),
+            rusqlite::types::Value::Text("com.app.b".to_string())
         );
-    }
 
-    #[test]
-    fn compact_client_returns_bundle_id_unchanged() {
-        assert_eq!(compact_client("com.apple.Terminal"), "com.apple.Terminal");
-        assert_eq!(compact_client("org.mozilla.firefox"), "org.mozilla.firefox");
+        let outcome = db.reset("Camera", None).unwrap();
+        assert_eq!(outcome.deleted_rows.len(), 1);
+        assert!(db.list(None, None).unwrap().is_empty());
     }
 
     #[test]
-    fn compact_client_root_path() {
-        // Edge case: root path "/"
-        assert_eq!(compact_client("/"), "/");
-    }
-
-    // ── Client/service filtering (partial match) ──────────────────────
+    fn locked_user_db_reports_db_locked() {
+        let (dir, mut db) = make_temp_tcc_db();
+        db.busy_timeout = Duration::from_millis(20);
+        let holder = Connection::open(dir.path().join("TCC.db")).unwrap();
+
+        // A pending write (RESERVED lock) fails the write itself
+        holder.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let err = db.grant("Camera", "com.example.app").unwrap_err();
+        assert!(
+            matches!(&err, TccError::DbLocked(p) if p.ends_with("TCC.db")),
+            "{:?}",
+            err
+        );
+        assert!(err.to_string().contains("tccd"));
+        holder.execute_batch("ROLLBACK").unwrap();
 
-    #[test]
-    fn client_filter_partial_match() {
-        let entries = vec![
-            make_entry("kTCCServiceCamera", "com.apple.Terminal", 2),
-            make_entry("kTCCServiceMicrophone", "com.google.Chrome", 0),
-            make_entry("kTCCServiceCamera", "com.apple.Safari", 2),
-        ];
+        // An exclusive lock already blocks the schema check
+        holder.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        let err = db.revoke("Camera", "com.example.app").unwrap_err();
+        assert!(matches!(err, TccError::DbLocked(_)), "{:?}", err);
+        holder.execute_batch("ROLLBACK").unwrap();
 
-        let filtered = filter_entries(entries, Some("apple"), None);
-        assert_eq!(filtered.len(), 2);
-        assert!(filtered.iter().all(|e| e.client.contains("apple")));
+        db.grant("Camera", "com.example.app").unwrap();
     }
 
     #[test]
-    fn service_filter_partial_match_display_name() {
-        let entries = vec![
-            make_entry("kTCCServiceCamera", "com.app.a", 2),
-            make_entry("kTCCServiceMicrophone", "com.app.b", 0),
-            make_entry("kTCCServiceScreenCapture", "com.app.c", 2),
-        ];
+    fn lock_retries_wait_for_the_lock_to_clear() {
+        let (dir, mut db) = make_temp_tcc_db();
+        db.busy_timeout = Duration::from_millis(20);
+        db.set_lock_retries(2);
+        let holder = Connection::open(dir.path().join("TCC.db")).unwrap();
+        holder.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            holder.execute_batch("ROLLBACK").unwrap();
+        });
 
-        // Matches "Camera" display name
-        let filtered = filter_entries(entries, None, Some("Camer"));
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].service_raw, "kTCCServiceCamera");
+        db.grant("Camera", "com.example.app").unwrap();
+        release.join().unwrap();
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].auth_value, 2);
     }
 
     #[test]
-    fn service_filter_partial_match_raw_key() {
-        let entries = vec![
-            make_entry("kTCCServiceCamera", "com.app.a", 2),
-            make_entry("kTCCServiceMicrophone", "com.app.b", 0),
-        ];
+    fn grant_merges_csreq_from_another_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value, csreq) \
+             VALUES ('kTCCServiceCamera', 'com.app.signed', 1, 2, X'FADE0C00')",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        let mut db = TccDb::with_paths(path.clone(), dir.path().join("none.db"), DbTarget::User);
+        let csreq_of = |service: &str, client: &str| -> Option<Vec<u8>> {
+            Connection::open(&path)
+                .unwrap()
+                .query_row(
+                    "SELECT csreq FROM access WHERE service = ?1 AND client = ?2",
+                    rusqlite::params![service, client],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
 
-        // Matches raw key
-        let filtered = filter_entries(entries, None, Some("kTCCServiceMicro"));
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].service_raw, "kTCCServiceMicrophone");
-    }
+        // Off by default
+        db.grant("Microphone", "com.app.signed").unwrap();
+        assert_eq!(csreq_of("kTCCServiceMicrophone", "com.app.signed"), None);
 
-    #[test]
-    fn filter_case_insensitive() {
-        let entries = vec![make_entry("kTCCServiceCamera", "com.Apple.Terminal", 2)];
+        db.set_merge_csreq(true);
+        let message = db.grant("Microphone", "com.app.signed").unwrap();
+        assert!(
+            message.contains("reusing the csreq stored for Camera"),
+            "{}",
+            message
+        );
+        assert_eq!(
+            csreq_of("kTCCServiceMicrophone", "com.app.signed"),
+            Some(vec![0xFA, 0xDE, 0x0C, 0x00])
+        );
 
-        let filtered = filter_entries(entries, Some("APPLE"), None);
-        assert_eq!(filtered.len(), 1);
+        // No sibling csreq: a plain grant
+        let message = db.grant("Camera", "com.app.unsigned").unwrap();
+        assert!(!message.contains("csreq"));
+        assert_eq!(csreq_of("kTCCServiceCamera", "com.app.unsigned"), None);
     }
 
     #[test]
-    fn filter_no_match_returns_empty() {
-        let entries = vec![make_entry("kTCCServiceCamera", "com.apple.Terminal", 2)];
+    fn extended_schema_reads_pid_last_reminded_and_csreq() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("TCC.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value, pid, last_reminded, csreq) \
+             VALUES ('kTCCServiceCamera', 'com.app.reminded', 0, 2, 4242, 1704067200, X'fade0c00')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value) \
+             VALUES ('kTCCServiceCamera', 'com.app.quiet', 0, 2)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        let db = TccDb::with_paths(path, dir.path().join("none.db"), DbTarget::User);
 
-        let filtered = filter_entries(entries, Some("nonexistent"), None);
-        assert!(filtered.is_empty());
-    }
+        let entries = db.list(None, None).unwrap();
+        let quiet = &entries[0];
+        assert_eq!(quiet.client, "com.app.quiet");
+        assert_eq!(quiet.pid, None);
+        assert_eq!(quiet.last_reminded.as_deref(), Some("N/A"));
+        assert_eq!(quiet.csreq, None);
+        let reminded = &entries[1];
+        assert_eq!(reminded.pid, Some(4242));
+        assert_eq!(
+            reminded.csreq.as_deref(),
+            Some(&[0xfa, 0xde, 0x0c, 0x00][..])
+        );
+        assert_eq!(
+            reminded.last_reminded,
+            Some(TccDb::format_timestamp(1_704_067_200))
+        );
 
-    // ── SERVICE_MAP sanity ────────────────────────────────────────────
+        // Schemas without the columns leave them all unset
+        let (_dir, legacy) = make_temp_tcc_db();
+        legacy.grant("Camera", "com.example.app").unwrap();
+        let entries = legacy.list(None, None).unwrap();
+        assert_eq!(entries[0].pid, None);
+        assert_eq!(entries[0].last_reminded, None);
+        assert_eq!(entries[0].csreq, None);
+    }
 
     #[test]
-    fn service_map_contains_expected_entries() {
-        assert!(SERVICE_MAP.contains_key("kTCCServiceAccessibility"));
-        assert!(SERVICE_MAP.contains_key("kTCCServiceCamera"));
-        assert!(SERVICE_MAP.contains_key("kTCCServiceMicrophone"));
-        assert!(SERVICE_MAP.contains_key("kTCCServiceScreenCapture"));
-        assert!(SERVICE_MAP.len() > 20);
-    }
+    fn list_iter_yields_filtered_entries_per_source() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
 
-    // ── Format timestamp ──────────────────────────────────────────────
+        assert_eq!(db.list_iter(&ListFilter::default()).unwrap().count(), 3);
+        let filter = ListFilter {
+            client: Some("example.a"),
+            ..ListFilter::default()
+        };
+        let mut services = 0;
+        for entry in db.list_iter(&filter).unwrap() {
+            assert_eq!(entry.client, "com.example.a");
+            services += 1;
+        }
+        assert_eq!(services, 2);
+    }
 
     #[test]
-    fn format_timestamp_zero_returns_na() {
-        assert_eq!(TccDb::format_timestamp(0), "N/A");
+    fn entries_checksum_ignores_order_but_not_content() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Microphone", "com.example.b").unwrap();
+
+        let mut entries = db.list(None, None).unwrap();
+        let checksum = entries_checksum(&entries);
+        assert_eq!(checksum.len(), 40);
+        entries.reverse();
+        assert_eq!(entries_checksum(&entries), checksum);
+
+        db.disable("Camera", "com.example.a").unwrap();
+        assert_ne!(entries_checksum(&db.list(None, None).unwrap()), checksum);
+
+        // Fields beyond the status count too
+        let mut entries = db.list(None, None).unwrap();
+        let checksum = entries_checksum(&entries);
+        entries[0].client_type = 0;
+        assert_ne!(entries_checksum(&entries), checksum);
+        entries[0].client_type = 1;
+        entries[0].indirect_object = Some("com.example.target".to_string());
+        assert_ne!(entries_checksum(&entries), checksum);
+        entries[0].indirect_object = None;
+        entries[0].csreq = Some(vec![0xfa, 0xde]);
+        assert_ne!(entries_checksum(&entries), checksum);
     }
 
     #[test]
-    fn format_timestamp_large_unix_value() {
-        // A recent Unix timestamp should produce a valid date
-        let result = TccDb::format_timestamp(1_700_000_000);
-        assert!(result.contains("2023"), "Expected 2023 in: {}", result);
+    fn diff_access_rows_shows_an_auth_value_flip() {
+        use rusqlite::types::Value;
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+        let before = db
+            .read_access_row("Camera", "com.example.app", None)
+            .unwrap();
+        db.disable("Camera", "com.example.app").unwrap();
+        let after = db
+            .read_access_row("Camera", "com.example.app", None)
+            .unwrap();
+
+        let changes = diff_access_rows(before.as_ref(), after.as_ref());
+        let auth = changes.iter().find(|c| c.column == "auth_value").unwrap();
+        assert_eq!(auth.before, Some(Value::Integer(2)));
+        assert_eq!(auth.after, Some(Value::Integer(0)));
+        assert!(changes.iter().all(|c| c.column != "client_type"));
+
+        // A removed row lists every column, with nothing after
+        let gone = diff_access_rows(after.as_ref(), None);
+        assert_eq!(gone.len(), 6);
+        assert!(gone.iter().all(|c| c.before.is_some() && c.after.is_none()));
+        assert!(diff_access_rows(None, None).is_empty());
     }
 
     #[test]
-    fn format_timestamp_coredata_value() {
-        // CoreData timestamp (seconds since 2001-01-01) — small value
-        // 700_000_000 + 978_307_200 = 1_678_307_200 → 2023
-        let result = TccDb::format_timestamp(700_000_000);
-        assert!(
-            result.contains("2023") || result.contains("2024"),
-            "Got: {}",
-            result
-        );
-    }
+    fn toggle_flips_between_granted_and_denied() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
 
-    // ── Helpers ───────────────────────────────────────────────────────
+        let (message, before, after) = db.toggle("Camera", "com.example.app").unwrap();
+        assert_eq!((before, after), (2, 0));
+        assert!(message.contains("granted → denied"), "{}", message);
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries[0].auth_value, 0);
+        assert_ne!(entries[0].last_modified_ts, 0);
 
-    fn make_entry(service_raw: &str, client: &str, auth_value: i32) -> TccEntry {
-        TccEntry {
-            service_raw: service_raw.to_string(),
-            service_display: TccDb::service_display_name(service_raw),
-            client: client.to_string(),
-            auth_value,
-            last_modified: "2024-01-01 00:00:00".to_string(),
-            is_system: false,
+        assert_eq!(db.toggle("Camera", "com.example.app").unwrap().2, 2);
+        // Limited goes to granted too
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        conn.execute("UPDATE access SET auth_value = 3", [])
+            .unwrap();
+        assert_eq!(db.toggle("Camera", "com.example.app").unwrap().2, 2);
+
+        // Unknown (1) and anything past limited have no opposite
+        for auth_value in [1, 4, 5] {
+            conn.execute("UPDATE access SET auth_value = ?1", [auth_value])
+                .unwrap();
+            match db.toggle("Camera", "com.example.app").unwrap_err() {
+                TccError::NotToggleable { auth_value: v, .. } => assert_eq!(v, auth_value),
+                e => panic!("expected NotToggleable, got {}", e),
+            }
+            assert_eq!(db.list(None, None).unwrap()[0].auth_value, auth_value);
         }
-    }
 
-    /// Applies the same filtering logic as TccDb::list
-    fn filter_entries(
-        mut entries: Vec<TccEntry>,
-        client_filter: Option<&str>,
-        service_filter: Option<&str>,
-    ) -> Vec<TccEntry> {
-        if let Some(cf) = client_filter {
-            let cf_lower = cf.to_lowercase();
-            entries.retain(|e| e.client.to_lowercase().contains(&cf_lower));
+        match db.toggle("Camera", "com.example.missing").unwrap_err() {
+            TccError::NotFound { service, .. } => assert!(service.contains("tcc grant")),
+            e => panic!("expected NotFound, got {}", e),
         }
-        if let Some(sf) = service_filter {
-            let sf_lower = sf.to_lowercase();
-            entries.retain(|e| {
-                e.service_display.to_lowercase().contains(&sf_lower)
-                    || e.service_raw.to_lowercase().contains(&sf_lower)
-            });
+    }
+
+    #[test]
+    fn set_writes_any_auth_value_to_an_existing_entry() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.app").unwrap();
+
+        let message = db.set("Camera", "com.example.app", 3).unwrap();
+        assert!(message.contains("to 3 (limited)"), "{}", message);
+        let entries = db.list(None, None).unwrap();
+        assert_eq!(entries[0].auth_value, 3);
+        assert_ne!(entries[0].last_modified_ts, 0);
+
+        let message = db.set("Camera", "com.example.app", 3).unwrap();
+        assert!(message.contains("nothing changed"), "{}", message);
+        db.set("Camera", "com.example.app", 7).unwrap();
+        assert_eq!(db.list(None, None).unwrap()[0].auth_value, 7);
+
+        match db.set("Camera", "com.example.missing", 1).unwrap_err() {
+            TccError::NotFound { service, .. } => assert!(service.contains("tcc grant")),
+            e => panic!("expected NotFound, got {}", e),
         }
-        entries
     }
 
-    // ── Resolve service name ──────────────────────────────────────────
+    #[test]
+    fn schema_is_probed_once_per_path() {
+        let (dir, mut db) = make_temp_tcc_db();
+        db.set_schema_policy(SchemaPolicy::Warn);
+        let probes = || SCHEMA_PROBES.with(|n| n.get());
+        let start = probes();
 
-    fn make_test_db() -> TccDb {
-        TccDb::with_paths(
-            PathBuf::from("/nonexistent/user.db"),
-            PathBuf::from("/nonexistent/system.db"),
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Microphone", "com.example.b").unwrap();
+        db.list(None, None).unwrap();
+        db.list_iter(&ListFilter::default()).unwrap().count();
+        db.schema_digests();
+        db.revoke("Camera", "com.example.a").unwrap();
+        assert_eq!(probes() - start, 1);
+
+        // Another handle starts with an empty cache
+        let other = TccDb::with_paths(
+            db.user_db_path.clone(),
+            dir.path().join("system_TCC.db"),
             DbTarget::User,
-        )
+        );
+        other.list(None, None).unwrap();
+        assert_eq!(probes() - start, 2);
     }
 
     #[test]
-    fn resolve_exact_key() {
-        let db = make_test_db();
-        assert_eq!(
-            db.resolve_service_name("kTCCServiceCamera").unwrap(),
-            "kTCCServiceCamera"
-        );
+    fn list_measured_counts_rows_before_filtering() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.b").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
+
+        let filter = ListFilter {
+            client: Some("example.b"),
+            ..ListFilter::default()
+        };
+        let MeasuredList {
+            entries,
+            failures,
+            counts,
+            ..
+        } = db.list_measured(&filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(failures.is_empty());
+        let user = counts
+            .iter()
+            .find(|c| c.source == SourceLabel::User)
+            .unwrap();
+        assert_eq!(user.rows, 3);
+        assert_eq!(counts.iter().map(|c| c.rows).sum::<usize>(), 3);
     }
 
     #[test]
-    fn resolve_display_name() {
-        let db = make_test_db();
+    fn family_counts_spread_entries_across_families() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Microphone", "com.example.a").unwrap();
+        db.grant("Desktop Folder", "com.example.a").unwrap();
+        db.grant("Accessibility", "com.example.b").unwrap();
+        db.grant("Contacts", "com.example.b").unwrap();
+        db.grant("Location", "com.example.b").unwrap();
+        db.grant("Reminders", "com.example.c").unwrap();
+
+        let counts = db.family_counts().unwrap();
         assert_eq!(
-            db.resolve_service_name("Camera").unwrap(),
-            "kTCCServiceCamera"
+            counts,
+            vec![
+                (ServiceFamily::Devices, 2),
+                (ServiceFamily::Files, 1),
+                (ServiceFamily::Automation, 1),
+                (ServiceFamily::PersonalData, 3),
+                (ServiceFamily::Other, 0),
+            ]
         );
     }
 
     #[test]
-    fn resolve_case_insensitive() {
-        let db = make_test_db();
+    fn reset_family_deletes_only_that_family() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Desktop Folder", "com.example.a").unwrap();
+        db.grant("Desktop Folder", "com.example.b").unwrap();
+        db.grant("Downloads Folder", "com.example.a").unwrap();
+        db.grant("Camera", "com.example.a").unwrap();
+
+        let outcome = db.reset_family(ServiceFamily::Files).unwrap();
+        assert_eq!(outcome.deleted, 3);
+        assert_eq!(outcome.dbs, 1);
+        let counts: Vec<(&str, usize)> = outcome
+            .per_service
+            .iter()
+            .map(|(s, n)| (s.key(), *n))
+            .collect();
         assert_eq!(
-            db.resolve_service_name("camera").unwrap(),
-            "kTCCServiceCamera"
+            counts,
+            vec![
+                ("kTCCServiceSystemPolicyDesktopFolder", 2),
+                ("kTCCServiceSystemPolicyDownloadsFolder", 1),
+            ]
         );
+
+        let left = db.list_filtered(&ListFilter::default()).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].service_raw, "kTCCServiceCamera");
     }
 
     #[test]
-    fn resolve_ambiguous_errors() {
-        let db = make_test_db();
-        // "Photo" matches both "Photos" and "Photos (Add Only)"
-        let err = db.resolve_service_name("Photo").unwrap_err();
-        assert!(
-            matches!(err, TccError::AmbiguousService { .. }),
-            "Expected AmbiguousService, got: {}",
-            err
+    fn newer_than_keeps_rows_the_other_db_has_not_caught_up_with() {
+        let (dir, db) = make_temp_tcc_db();
+        let other_path = dir.path().join("other.db");
+        std::fs::copy(dir.path().join("TCC.db"), &other_path).unwrap();
+        let rows = |path: &Path, rows: &[(&str, i64)]| {
+            let conn = Connection::open(path).unwrap();
+            for (client, ts) in rows {
+                conn.execute(
+                    "INSERT INTO access (service, client, client_type, auth_value, last_modified) \
+                     VALUES ('kTCCServiceCamera', ?1, 0, 2, ?2)",
+                    rusqlite::params![client, ts],
+                )
+                .unwrap();
+            }
+        };
+        rows(
+            &dir.path().join("TCC.db"),
+            &[
+                ("newer", 1_800_000_000),
+                ("older", 1_700_000_000),
+                ("equal", 1_750_000_000),
+                ("local-only", 1_750_000_000),
+            ],
         );
+        rows(
+            &other_path,
+            &[
+                ("newer", 1_700_000_000),
+                ("older", 1_800_000_000),
+                ("equal", 1_750_000_000),
+            ],
+        );
+
+        let mut entries = db.list_filtered(&ListFilter::default()).unwrap();
+        let other = TccDb::read_other_db(&other_path, false).unwrap();
+        let other_ts = retain_newer_than(&mut entries, &other);
+        let kept: Vec<(&str, Option<i64>)> = entries
+            .iter()
+            .map(|e| e.client.as_str())
+            .zip(other_ts)
+            .collect();
+        assert_eq!(
+            kept,
+            vec![("local-only", None), ("newer", Some(1_700_000_000))]
+        );
+
+        let missing = dir.path().join("nope.db");
+        assert!(matches!(
+            TccDb::read_other_db(&missing, false),
+            Err(TccError::DbMissing(_))
+        ));
+        assert!(TccDb::read_other_db(&missing, true).unwrap().is_empty());
     }
 
     #[test]
-    fn resolve_unknown_errors() {
-        let db = make_test_db();
-        let err = db.resolve_service_name("NonexistentService").unwrap_err();
-        assert!(matches!(err, TccError::UnknownService(_)));
+    fn read_order_keeps_insertion_order() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Microphone", "com.example.b").unwrap();
+        db.grant("Camera", "com.example.c").unwrap();
+        db.grant("Camera", "com.example.a").unwrap();
+
+        let clients = |filter: &ListFilter| -> Vec<String> {
+            db.list_filtered(filter)
+                .unwrap()
+                .into_iter()
+                .map(|e| format!("{} {}", e.service_display, e.client))
+                .collect()
+        };
+        assert_eq!(
+            clients(&ListFilter {
+                read_order: true,
+                ..ListFilter::default()
+            }),
+            vec![
+                "Microphone com.example.b",
+                "Camera com.example.c",
+                "Camera com.example.a"
+            ]
+        );
+        assert_eq!(
+            clients(&ListFilter::default()),
+            vec![
+                "Camera com.example.a",
+                "Camera com.example.c",
+                "Microphone com.example.b"
+            ]
+        );
     }
 
     #[test]
-    fn resolve_short_name_via_prefix() {
-        let db = make_test_db();
+    fn sort_keys_order_entries_and_break_ties_by_service_then_client() {
+        let (_dir, db) = make_temp_tcc_db();
+        db.grant("Microphone", "com.example.b").unwrap();
+        db.grant("Camera", "com.example.c").unwrap();
+        db.grant("Camera", "com.example.a").unwrap();
+        let conn = Connection::open(&db.user_db_path).unwrap();
+        conn.execute_batch(
+            "UPDATE access SET last_modified = 0 WHERE client = 'com.example.a';
+             UPDATE access SET last_modified = 900000000, auth_value = 0
+                 WHERE client = 'com.example.b';
+             UPDATE access SET last_modified = 800000000 WHERE client = 'com.example.c';",
+        )
+        .unwrap();
+        drop(conn);
+
+        let clients = |sort: SortKey, reverse: bool| -> Vec<String> {
+            db.list_filtered(&ListFilter {
+                sort,
+                reverse,
+                ..ListFilter::default()
+            })
+            .unwrap()
+            .into_iter()
+            .map(|e| e.client)
+            .collect()
+        };
+        let abc = ["com.example.a", "com.example.b", "com.example.c"];
+        assert_eq!(clients(SortKey::Client, false), abc);
         assert_eq!(
-            db.resolve_service_name("BluetoothAlways").unwrap(),
-            "kTCCServiceBluetoothAlways"
+            clients(SortKey::Client, true),
+            ["com.example.c", "com.example.b", "com.example.a"]
+        );
+        // Ties (both granted) fall back to service, then client
+        assert_eq!(
+            clients(SortKey::Status, false),
+            ["com.example.b", "com.example.a", "com.example.c"]
+        );
+        // No modification time sorts as oldest, not by its "N/A" label
+        assert_eq!(
+            clients(SortKey::Modified, false),
+            ["com.example.a", "com.example.c", "com.example.b"]
+        );
+        assert_eq!(
+            clients(SortKey::Modified, true),
+            ["com.example.b", "com.example.c", "com.example.a"]
+        );
+        assert_eq!(
+            clients(SortKey::Source, false),
+            clients(SortKey::Service, false)
+        );
+        assert_eq!(
+            clients(SortKey::Service, false),
+            ["com.example.a", "com.example.c", "com.example.b"]
         );
     }
 
-    // ── Write operation tests (temp DB) ───────────────────────────────
+    #[test]
+    fn export_sqlite_writes_a_standalone_copy() {
+        let (dir, db) = make_temp_tcc_db();
+        db.grant("Camera", "com.example.a").unwrap();
+        db.grant("Microphone", "com.example.b").unwrap();
 
-    fn make_temp_tcc_db() -> (tempfile::TempDir, TccDb) {
-        let dir = tempfile::tempdir().expect("failed to create temp dir");
-        let db_path = dir.path().join("TCC.db");
+        let out = dir.path().join("export.db");
+        let outcome = db.export_sqlite(&out, None).unwrap();
+        assert_eq!(outcome.rows, 2);
+        // The checksum is `list --checksum`'s, over the rows written
+        assert_eq!(
+            outcome.checksum,
+            entries_checksum(&db.list(None, None).unwrap())
+        );
 
-        let conn = Connection::open(&db_path).expect("failed to create temp db");
-        conn.execute_batch(
-            "CREATE TABLE access (
-                service TEXT NOT NULL,
-                client TEXT NOT NULL,
-                client_type INTEGER NOT NULL,
-                auth_value INTEGER NOT NULL DEFAULT 0,
-                auth_reason INTEGER NOT NULL DEFAULT 0,
-                auth_version INTEGER NOT NULL DEFAULT 1,
-                flags INTEGER NOT NULL DEFAULT 0,
-                last_modified INTEGER DEFAULT 0,
-                PRIMARY KEY (service, client, client_type)
-            );",
-        )
-        .expect("failed to create table");
+        let conn = Connection::open(&out).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT source, service, client, auth_value FROM access ORDER BY client")
+            .unwrap();
+        let rows: Vec<(String, String, String, i32)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "user".to_string(),
+                    "kTCCServiceCamera".to_string(),
+                    "com.example.a".to_string(),
+                    2
+                ),
+                (
+                    "user".to_string(),
+                    "kTCCServiceMicrophone".to_string(),
+                    "com.example.b".to_string(),
+                    2
+                ),
+            ]
+        );
+        let tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 1);
+        drop(stmt);
         drop(conn);
 
-        let db = TccDb::with_paths(db_path, dir.path().join("system_TCC.db"), DbTarget::User);
-
-        (dir, db)
+        // Never overwrites an existing file
+        assert!(db.export_sqlite(&out, None).is_err());
+        assert!(out.exists());
     }
 
     #[test]
-    fn grant_inserts_entry() {
-        let (_dir, db) = make_temp_tcc_db();
-        let result = db.grant("Camera", "com.example.app");
-        assert!(result.is_ok(), "grant failed: {:?}", result.err());
-        assert!(result.unwrap().contains("Granted"));
+    fn export_sqlite_skips_an_unreadable_db_with_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+        Connection::open(&user)
+            .unwrap()
+            .execute_batch(SELF_TEST_SCHEMA)
+            .unwrap();
+        let system = dir.path().join("system.db");
+        std::fs::write(&system, "not a database").unwrap();
+        let db = TccDb::with_paths(user, system.clone(), DbTarget::Default);
+        db.grant("Camera", "com.example.app").unwrap();
 
-        let entries = db.list(None, None).unwrap();
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].service_raw, "kTCCServiceCamera");
-        assert_eq!(entries[0].client, "com.example.app");
-        assert_eq!(entries[0].auth_value, 2);
+        let out = dir.path().join("export.db");
+        assert_eq!(db.export_sqlite(&out, None).unwrap().rows, 1);
+        let warnings = db.take_warnings();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains(&system.display().to_string())),
+            "{:?}",
+            warnings
+        );
+        assert_eq!(std::fs::read(&system).unwrap(), b"not a database");
     }
 
     #[test]
-    fn grant_sets_client_type_for_path() {
-        let (_dir, db) = make_temp_tcc_db();
-        db.grant("Camera", "/usr/bin/test").unwrap();
+    fn export_sqlite_redacts_clients_targets_and_csreq() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+        let conn = Connection::open(&user).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        let db = TccDb::with_paths(user, dir.path().join("system.db"), DbTarget::User);
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value, csreq, \
+             indirect_object_identifier) VALUES \
+             ('kTCCServiceAppleEvents', 'com.secret.app', 0, 2, x'fade', 'com.secret.target'), \
+             ('kTCCServiceCamera', 'com.secret.app', 0, 2, NULL, 'UNUSED')",
+            [],
+        )
+        .unwrap();
 
-        let conn = Connection::open(&db.user_db_path).unwrap();
-        let client_type: i32 = conn
-            .query_row(
-                "SELECT client_type FROM access WHERE client = '/usr/bin/test'",
-                [],
-                |row| row.get(0),
+        let redactor = Redactor::with_salt("salt");
+        let out = dir.path().join("export.db");
+        let outcome = db.export_sqlite(&out, Some(&redactor)).unwrap();
+        assert_eq!(outcome.rows, 2);
+        // Over the real clients, like `list --checksum --redact`
+        assert_eq!(
+            outcome.checksum,
+            entries_checksum(&db.list(None, None).unwrap())
+        );
+
+        let conn = Connection::open(&out).unwrap();
+        let rows: Vec<(String, String, Option<Vec<u8>>)> = conn
+            .prepare(
+                "SELECT client, indirect_object_identifier, csreq FROM access ORDER BY service",
             )
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
             .unwrap();
-        assert_eq!(client_type, 0, "Path client should have client_type 0");
+        let client = redactor.redact("com.secret.app");
+        assert_eq!(
+            rows,
+            vec![
+                (client.clone(), redactor.redact("com.secret.target"), None),
+                (client, "UNUSED".to_string(), None),
+            ]
+        );
     }
 
     #[test]
-    fn grant_sets_client_type_for_bundle_id() {
-        let (_dir, db) = make_temp_tcc_db();
-        db.grant("Camera", "com.example.app").unwrap();
-
-        let conn = Connection::open(&db.user_db_path).unwrap();
-        let client_type: i32 = conn
-            .query_row(
-                "SELECT client_type FROM access WHERE client = 'com.example.app'",
-                [],
-                |row| row.get(0),
+    fn only_modified_drops_rows_without_a_timestamp() {
+        let (dir, db) = make_temp_tcc_db();
+        let conn = Connection::open(dir.path().join("TCC.db")).unwrap();
+        for (client, ts) in [("seeded", 0), ("set", 700_000_000), ("also-seeded", 0)] {
+            conn.execute(
+                "INSERT INTO access (service, client, client_type, auth_value, last_modified) \
+                 VALUES ('kTCCServiceCamera', ?1, 0, 2, ?2)",
+                rusqlite::params![client, ts],
             )
             .unwrap();
-        assert_eq!(client_type, 1, "Bundle ID should have client_type 1");
+        }
+
+        assert_eq!(db.list_filtered(&ListFilter::default()).unwrap().len(), 3);
+        let filter = ListFilter {
+            only_modified: true,
+            ..ListFilter::default()
+        };
+        let shown = db.list_filtered(&filter).unwrap();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].client, "set");
     }
 
     #[test]
-    fn revoke_removes_entry() {
+    fn verify_schema_pins_the_exact_digest() {
         let (_dir, db) = make_temp_tcc_db();
-        db.grant("Camera", "com.example.app").unwrap();
+        let digests = db.schema_digests();
+        assert_eq!(digests.len(), 1);
+        // SHA1 prefix of make_temp_tcc_db's CREATE TABLE statement
+        assert_eq!(digests[0].digest.as_deref(), Some(TEMP_DB_DIGEST));
+
+        assert!(db.verify_schema(TEMP_DB_DIGEST).is_ok());
+        assert!(db.verify_schema(&TEMP_DB_DIGEST.to_uppercase()).is_ok());
+        match db.verify_schema("34abf99d20").unwrap_err() {
+            TccError::SchemaMismatch {
+                expected, found, ..
+            } => {
+                assert_eq!(expected, "34abf99d20");
+                assert_eq!(found.as_deref(), Some(TEMP_DB_DIGEST));
+            }
+            e => panic!("expected SchemaMismatch, got {}", e),
+        }
+    }
 
-        let result = db.revoke("Camera", "com.example.app");
-        assert!(result.is_ok());
+    #[test]
+    fn digest_version_mismatch_flags_a_copied_db() {
+        // A Sonoma-range digest on Monterey
+        let note = digest_version_mismatch("34abf99d20", "12.7.4").unwrap();
+        assert!(note.contains("Sonoma or later"), "{}", note);
+        assert!(note.contains("12.7.4"), "{}", note);
+        assert!(digest_version_mismatch("34abf99d20", "14.5").is_none());
+        assert!(digest_version_mismatch("34abf99d20", "26.2").is_none());
+        // High Sierra's minor still decides against the Mojave range
+        assert!(digest_version_mismatch("ecc443615f", "10.13.6").is_some());
+        assert!(digest_version_mismatch("ecc443615f", "10.15.7").is_none());
+        assert!(digest_version_mismatch("0000000000", "12.0").is_none());
+        assert!(digest_version_mismatch("34abf99d20", "unknown").is_none());
+        for digest in KNOWN_DIGESTS {
+            assert!(
+                DIGEST_RELEASES.iter().any(|(d, ..)| d == digest),
+                "{}",
+                digest
+            );
+        }
+    }
 
-        let entries = db.list(None, None).unwrap();
-        assert!(entries.is_empty());
+    #[test]
+    fn auth_filter_keeps_one_status() {
+        let (dir, db) = make_temp_tcc_db();
+        let conn = Connection::open(dir.path().join("TCC.db")).unwrap();
+        for (client, auth) in [("a", 0), ("b", 2), ("c", 3), ("d", 1), ("e", 0)] {
+            conn.execute(
+                "INSERT INTO access (service, client, client_type, auth_value) \
+                 VALUES ('kTCCServiceCamera', ?1, 0, ?2)",
+                rusqlite::params![client, auth],
+            )
+            .unwrap();
+        }
+
+        let clients = |auth| {
+            let filter = ListFilter {
+                auth: Some(auth),
+                ..ListFilter::default()
+            };
+            let shown = db.list_filtered(&filter).unwrap();
+            shown.into_iter().map(|e| e.client).collect::<Vec<_>>()
+        };
+        assert_eq!(clients(AuthStatus::Denied), vec!["a", "e"]);
+        assert_eq!(clients(AuthStatus::Granted), vec!["b"]);
+        assert_eq!(clients(AuthStatus::Limited), vec!["c"]);
+        assert_eq!(clients(AuthStatus::Unknown), vec!["d"]);
+        // Composes with the other filters
+        let filter = ListFilter {
+            auth: Some(AuthStatus::Denied),
+            client: Some("e"),
+            ..ListFilter::default()
+        };
+        assert_eq!(db.list_filtered(&filter).unwrap().len(), 1);
     }
 
     #[test]
-    fn revoke_nonexistent_returns_not_found() {
-        let (_dir, db) = make_temp_tcc_db();
-        let result = db.revoke("Camera", "com.nonexistent.app");
-        assert!(result.is_err());
-        assert!(matches!(result.unwrap_err(), TccError::NotFound { .. }));
+    fn every_known_service_has_a_family() {
+        for key in SERVICE_MAP.keys() {
+            assert_ne!(ServiceFamily::of(key), ServiceFamily::Other, "{}", key);
+        }
     }
 
     #[test]
-    fn enable_sets_auth_value_to_granted() {
+    fn hide_tcc_internal_drops_only_apple_tcc_clients() {
         let (_dir, db) = make_temp_tcc_db();
         db.grant("Camera", "com.example.app").unwrap();
-        db.disable("Camera", "com.example.app").unwrap();
+        db.grant("Camera", "com.apple.tccd").unwrap();
+        db.grant("Accessibility", "com.apple.systempreferences")
+            .unwrap();
+        db.grant(
+            "Camera",
+            "/System/Library/PrivateFrameworks/TCC.framework/Support/tccd",
+        )
+        .unwrap();
 
-        let entries = db.list(None, None).unwrap();
-        assert_eq!(entries[0].auth_value, 0);
+        let all = db.list_filtered(&ListFilter::default()).unwrap();
+        assert_eq!(all.len(), 4);
 
-        db.enable("Camera", "com.example.app").unwrap();
-        let entries = db.list(None, None).unwrap();
-        assert_eq!(entries[0].auth_value, 2);
+        let filter = ListFilter {
+            hide_tcc_internal: true,
+            ..ListFilter::default()
+        };
+        let shown = db.list_filtered(&filter).unwrap();
+        let clients: Vec<_> = shown.iter().map(|e| e.client.as_str()).collect();
+        assert_eq!(clients, vec!["com.example.app"]);
+        // Exact matches only: a lookalike bundle ID stays visible
+        assert!(!is_tcc_internal("com.apple.tccd.helper"));
     }
 
-    #[test]
-    fn disable_sets_auth_value_to_denied() {
-        let (_dir, db) = make_temp_tcc_db();
-        db.grant("Camera", "com.example.app").unwrap();
+    // ── Symlinked DB paths ────────────────────────────────────────────
 
-        db.disable("Camera", "com.example.app").unwrap();
-        let entries = db.list(None, None).unwrap();
-        assert_eq!(entries[0].auth_value, 0);
+    /// A home whose `Library/Application Support` is a symlink to another directory
+    fn make_symlinked_home() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let real_support = dir.path().join("volume/Application Support");
+        std::fs::create_dir_all(real_support.join("com.apple.TCC")).unwrap();
+        let home = dir.path().join("home");
+        std::fs::create_dir_all(home.join("Library")).unwrap();
+        std::os::unix::fs::symlink(&real_support, home.join("Library/Application Support"))
+            .unwrap();
+
+        let real_db = real_support.join("com.apple.TCC/TCC.db");
+        let conn = Connection::open(&real_db).unwrap();
+        conn.execute_batch(SELF_TEST_SCHEMA).unwrap();
+        (dir, home, real_db)
     }
 
     #[test]
-    fn enable_nonexistent_returns_not_found() {
-        let (_dir, db) = make_temp_tcc_db();
-        let result = db.enable("Camera", "com.nonexistent.app");
-        assert!(result.is_err());
-        assert!(matches!(result.unwrap_err(), TccError::NotFound { .. }));
+    fn symlinked_support_dir_is_followed() {
+        let (_dir, home, _) = make_symlinked_home();
+        let db = TccDb::with_home(DbTarget::User, &home);
+        db.grant("Camera", "com.example.app").unwrap();
+        assert_eq!(db.list(None, None).unwrap().len(), 1);
     }
 
     #[test]
-    fn reset_specific_client() {
-        let (_dir, db) = make_temp_tcc_db();
-        db.grant("Camera", "com.example.a").unwrap();
-        db.grant("Camera", "com.example.b").unwrap();
+    fn resolve_symlinks_uses_real_path() {
+        let (_dir, home, real_db) = make_symlinked_home();
+        let mut db = TccDb::with_home(DbTarget::User, &home);
+        db.resolve_symlinks();
+        assert_eq!(db.user_db_path, std::fs::canonicalize(real_db).unwrap());
+    }
 
-        db.reset("Camera", Some("com.example.a")).unwrap();
-        let entries = db.list(None, None).unwrap();
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].client, "com.example.b");
+    #[test]
+    fn info_reports_resolved_path() {
+        let (_dir, home, real_db) = make_symlinked_home();
+        let db = TccDb::with_home(DbTarget::User, &home);
+        let real = std::fs::canonicalize(real_db).unwrap();
+        let expected = format!("  Resolved path: {}", real.display());
+        assert!(db.info().lines.contains(&expected));
     }
 
     #[test]
-    fn reset_all_entries_for_service() {
-        let (_dir, db) = make_temp_tcc_db();
-        db.grant("Camera", "com.example.a").unwrap();
-        db.grant("Camera", "com.example.b").unwrap();
-        db.grant("Microphone", "com.example.a").unwrap();
+    fn broken_symlink_is_an_error_not_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("TCC.db");
+        std::os::unix::fs::symlink(dir.path().join("gone/TCC.db"), &link).unwrap();
+
+        let err = TccDb::read_db(&link, false, &SchemaCache::default()).unwrap_err();
+        assert!(matches!(err, TccError::DbOpen { .. }));
+        assert!(err.to_string().contains("broken symlink"));
+
+        let db = TccDb::with_paths(link, dir.path().join("system.db"), DbTarget::User);
+        let err = db.grant("Camera", "com.example.app").unwrap_err();
+        assert!(err.to_string().contains("broken symlink"));
+        assert!(!dir.path().join("gone").exists());
+    }
 
-        let result = db.reset("Camera", None).unwrap();
-        assert!(result.contains("2 deleted"));
+    #[test]
+    fn broken_symlink_in_a_parent_directory_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("Library");
+        std::fs::create_dir(&library).unwrap();
+        let support = library.join("Application Support");
+        std::os::unix::fs::symlink(dir.path().join("gone"), &support).unwrap();
+
+        let mut db = TccDb::with_home(DbTarget::User, dir.path());
+        db.set_require_user_db(true);
+        let err = db.list_entries(&ListFilter::default()).unwrap_err();
+        assert!(matches!(err, TccError::DbOpen { .. }), "got {}", err);
+        assert!(
+            err.to_string()
+                .contains(&format!("{} is a broken symlink", support.display())),
+            "got {}",
+            err
+        );
 
-        let entries = db.list(None, None).unwrap();
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].service_raw, "kTCCServiceMicrophone");
+        // Also with --allow-missing-db: the link is reported, not read as empty
+        db.set_require_user_db(false);
+        let err = db.list_entries(&ListFilter::default()).unwrap_err();
+        assert!(err.to_string().contains("broken symlink"), "got {}", err);
+        assert!(
+            db.info()
+                .lines
+                .iter()
+                .any(|l| l.contains("Broken symlink:"))
+        );
     }
 
     #[test]
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::process::Command;
 
 /// Helper: run the `tccutil-rs` binary with given args, returning (stdout, stderr, success).
@@ -13,6 +14,70 @@ fn run_tcc(args: &[&str]) -> (String, String, bool) {
     (stdout, stderr, output.status.success())
 }
 
+/// Helper: create a user TCC.db under `home` containing the given (service, client, auth_value) rows.
+fn make_home_db(home: &Path, rows: &[(&str, &str, i32)]) {
+    let db_dir = home.join("Library/Application Support/com.apple.TCC");
+    std::fs::create_dir_all(&db_dir).expect("failed to create TCC dir");
+    let conn = rusqlite::Connection::open(db_dir.join("TCC.db")).expect("failed to create db");
+    conn.execute_batch(
+        "CREATE TABLE access (
+            service TEXT NOT NULL,
+            client TEXT NOT NULL,
+            client_type INTEGER NOT NULL,
+            auth_value INTEGER NOT NULL DEFAULT 0,
+            auth_reason INTEGER NOT NULL DEFAULT 0,
+            auth_version INTEGER NOT NULL DEFAULT 1,
+            flags INTEGER NOT NULL DEFAULT 0,
+            last_modified INTEGER DEFAULT 0,
+            PRIMARY KEY (service, client, client_type)
+        );",
+    )
+    .expect("failed to create table");
+    for (service, client, auth_value) in rows {
+        conn.execute(
+            "INSERT INTO access (service, client, client_type, auth_value) VALUES (?1, ?2, 1, ?3)",
+            rusqlite::params![service, client, auth_value],
+        )
+        .expect("failed to insert row");
+    }
+}
+
+/// Helper: like `run_tcc`, also returning the exit code.
+fn run_tcc_code(args: &[&str]) -> (String, i32) {
+    let bin = env!("CARGO_BIN_EXE_tccutil-rs");
+    let output = Command::new(bin)
+        .args(args)
+        .output()
+        .expect("failed to execute tccutil-rs binary");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    (stdout, output.status.code().unwrap_or(-1))
+}
+
+/// Helper: like `run_tcc`, feeding `input` on stdin.
+fn run_tcc_stdin(args: &[&str], input: impl AsRef<[u8]>) -> (String, String, bool) {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let bin = env!("CARGO_BIN_EXE_tccutil-rs");
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to execute tccutil-rs binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_ref())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    (stdout, stderr, output.status.success())
+}
+
 fn assert_basic_json_shape(stdout: &str) {
     let trimmed = stdout.trim();
     assert!(
@@ -93,8 +158,232 @@ fn list_with_service_filter_runs() {
     );
 }
 
+#[test]
+fn list_ascii_output_has_no_non_ascii_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.app", 2),
+            ("kTCCServiceMicrophone", "com.foo.app", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(success);
+    assert!(
+        stdout.contains('\u{2033}'),
+        "default output uses the ditto mark"
+    );
+
+    let (stdout, stderr, success) = run_tcc(&["--user", "--home", home, "--ascii", "list"]);
+    assert!(success, "list --ascii failed: {}", stderr);
+    assert!(stdout.is_ascii(), "non-ASCII output: {}", stdout);
+    assert!(stdout.contains("com.foo.app"));
+    assert!(stdout.contains("---"));
+}
+
+#[test]
+fn list_since_returns_only_recent_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    // Rows from make_home_db have no modification time
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.old", 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "--json", "list"]);
+    assert!(success);
+    let marker = stdout
+        .split("\"generated_at\":")
+        .nth(1)
+        .and_then(|rest| rest.split(',').next())
+        .expect("list JSON should carry generated_at");
+    let generated_at: i64 = marker.parse().unwrap();
+
+    let (_stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "grant",
+        "Microphone",
+        "com.foo.new",
+    ]);
+    assert!(success);
+
+    let since = generated_at.to_string();
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user", "--home", home, "--json", "list", "--since", &since,
+    ]);
+    assert!(success);
+    assert!(stdout.contains("\"count\":1"), "got: {}", stdout);
+    assert!(stdout.contains("com.foo.new"));
+    assert!(!stdout.contains("com.foo.old"));
+
+    // An hour-long age window ending a day from now holds only the new grant
+    let until = (generated_at + 86_400).to_string();
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user", "--home", home, "--json", "list", "--since", "1h", "--until", &until,
+    ]);
+    assert!(success);
+    assert!(stdout.contains("\"count\":1"), "got: {}", stdout);
+    assert!(stdout.contains("com.foo.new"));
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "list",
+        "--since",
+        "2030-01-01",
+        "--until",
+        "2020-01-01",
+    ]);
+    assert!(!success);
+    assert!(
+        stdout.contains("\"kind\":\"ParseError\""),
+        "got: {}",
+        stdout
+    );
+}
+
 // ── tccutil-rs info ─────────────────────────────────────────────────
 
+#[test]
+fn list_with_home_but_no_db_errors_unless_allowed() {
+    let dir = tempfile::tempdir().unwrap();
+    let home = dir.path().join("no-such-user");
+    let home = home.to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "--json", "list"]);
+    assert!(
+        !success,
+        "a missing DB under an explicit --home should fail"
+    );
+    assert!(stdout.contains("\"kind\":\"DbMissing\""), "got: {}", stdout);
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--allow-missing-db",
+        "--json",
+        "list",
+    ]);
+    assert!(success);
+    assert!(stdout.contains("\"count\":0"), "got: {}", stdout);
+}
+
+#[test]
+fn reset_confirm_each_refuses_without_terminal() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    // Test stdin is not a TTY, so nothing may be deleted
+    let (_stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "reset",
+        "Camera",
+        "--confirm-each",
+    ]);
+    assert!(!success);
+    assert!(stderr.contains("interactive terminal"), "got: {}", stderr);
+
+    let (stdout, _stderr, _success) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(stdout.contains("com.foo.app"));
+}
+
+#[test]
+fn reset_interactive_declined_leaves_db_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.a", 2),
+            ("kTCCServiceCamera", "com.foo.b", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+    let args = ["--user", "--home", home, "reset", "Camera", "--interactive"];
+
+    let (stdout, stderr, success) = run_tcc_stdin(&args, "n\n");
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("com.foo.a") && stdout.contains("com.foo.b"));
+    assert!(stdout.contains("Delete these 2 entries? [y/N]"));
+    assert!(stdout.contains("Aborted"));
+    let (stdout, _, _) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(stdout.contains("2 entries total"), "got: {}", stdout);
+
+    let (stdout, _, success) = run_tcc_stdin(&args, "y\n");
+    assert!(success);
+    assert!(stdout.contains("2 deleted"), "got: {}", stdout);
+    let (stdout, _, _) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(stdout.contains("No entries found"), "got: {}", stdout);
+}
+
+#[test]
+fn reset_summary_only_prints_just_the_counts() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.a", 2),
+            ("kTCCServiceCamera", "com.foo.b", 0),
+            ("kTCCServiceMicrophone", "com.foo.a", 2),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--assume-schema",
+        "known",
+        "reset",
+        "Camera",
+        "--summary-only",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    assert_eq!(stdout.trim(), "Deleted 2 entries across 1 DB");
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--assume-schema",
+        "known",
+        "revoke",
+        "Microphone",
+        "com.foo.a",
+        "--summary-only",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    assert_eq!(stdout.trim(), "Deleted 1 entry across 1 DB");
+}
+
+#[test]
+fn list_only_reports_every_unresolvable_service() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[]);
+    let home = dir.path().to_str().unwrap();
+
+    let (_stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "list",
+        "--only",
+        "Camera,Bogus,Photo",
+    ]);
+    assert!(!success);
+    assert!(stderr.contains("Bogus"), "got: {}", stderr);
+    assert!(stderr.contains("Photo"), "got: {}", stderr);
+}
+
 #[test]
 fn info_shows_macos_version_and_db_paths() {
     let (stdout, _stderr, success) = run_tcc(&["info"]);
@@ -110,6 +399,242 @@ fn info_shows_macos_version_and_db_paths() {
     assert!(stdout.contains("SIP status:"), "should show SIP status");
 }
 
+// ── tccutil-rs paths ────────────────────────────────────────────────
+
+#[test]
+fn paths_prints_user_and_system_db_paths() {
+    let (stdout, _stderr, success) = run_tcc(&["paths"]);
+    assert!(success, "tccutil-rs paths should exit 0");
+    assert_eq!(
+        stdout.lines().count(),
+        2,
+        "expected two paths, got: {}",
+        stdout
+    );
+    assert!(stdout.contains("/Library/Application Support/com.apple.TCC/TCC.db"));
+}
+
+#[test]
+fn paths_home_changes_user_db_path() {
+    let (stdout, _stderr, success) = run_tcc(&["--user", "paths", "--home", "/tmp/x"]);
+    assert!(success, "tccutil-rs paths --home should exit 0");
+    assert_eq!(
+        stdout.trim(),
+        "/tmp/x/Library/Application Support/com.apple.TCC/TCC.db"
+    );
+}
+
+// ── tccutil-rs self-test ────────────────────────────────────────────
+
+#[test]
+fn self_test_passes_against_throwaway_db() {
+    let (stdout, _stderr, success) = run_tcc(&["self-test"]);
+    assert!(
+        success,
+        "tccutil-rs self-test should exit 0, got: {}",
+        stdout
+    );
+    for step in ["grant", "disable", "enable", "revoke", "reset"] {
+        assert!(stdout.contains(step), "should report {}", step);
+    }
+    assert!(stdout.contains("Self-test passed"));
+}
+
+// ── Write commands in JSON mode ─────────────────────────────────────
+
+#[test]
+fn grant_json_includes_resulting_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "grant",
+        "Camera",
+        "/usr/bin/foo",
+    ]);
+    assert!(success, "grant should succeed, got: {}", stdout);
+    assert!(stdout.contains("\"ok\":true"));
+    assert!(stdout.contains(
+        "\"message\":\"Granted Camera [kTCCServiceCamera] access for '/usr/bin/foo'\",\"service_raw\":\"kTCCServiceCamera\""
+    ));
+    assert!(stdout.contains("\"entry\":{\"service_raw\":\"kTCCServiceCamera\""));
+    assert!(stdout.contains("\"client\":\"/usr/bin/foo\""));
+    assert!(stdout.contains("\"client_type\":0"));
+    assert!(stdout.contains("\"auth_value\":2"));
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "revoke",
+        "Camera",
+        "/usr/bin/foo",
+    ]);
+    assert!(success, "revoke should succeed, got: {}", stdout);
+    assert!(stdout.contains("\"entry\":{\"service_raw\":\"kTCCServiceCamera\""));
+}
+
+#[test]
+fn write_commands_report_changed_rows_in_json() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceMicrophone", "com.foo.a", 2),
+            ("kTCCServiceMicrophone", "com.foo.b", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+    let data = |args: &[&str]| -> serde_json::Value {
+        let mut full = vec!["--user", "--home", home, "--json"];
+        full.extend_from_slice(args);
+        let (stdout, stderr, success) = run_tcc(&full);
+        assert!(success, "{:?} failed: {}{}", args, stdout, stderr);
+        serde_json::from_str::<serde_json::Value>(&stdout).unwrap()["data"].clone()
+    };
+
+    assert_eq!(data(&["grant", "Camera", "com.foo.a"])["changed"], 1);
+    assert_eq!(
+        data(&["grant", "Camera", "com.foo.a", "--if-missing"])["changed"],
+        0
+    );
+    assert_eq!(data(&["enable", "Camera", "com.foo.a"])["changed"], 0);
+    assert_eq!(data(&["disable", "Camera", "com.foo.a"])["changed"], 1);
+    assert_eq!(data(&["disable", "Camera", "com.foo.a"])["changed"], 0);
+    assert_eq!(
+        data(&["enable", "Microphone", "--all-clients", "--yes"])["changed"],
+        1
+    );
+    assert_eq!(data(&["revoke", "Camera", "com.foo.a"])["changed"], 1);
+
+    let one = data(&["reset", "Microphone", "com.foo.a"]);
+    assert_eq!(one["changed"], 1);
+    assert!(one.get("total_deleted").is_none());
+    let all = data(&["reset", "Microphone"]);
+    assert_eq!(all["changed"], 1);
+    assert_eq!(all["total_deleted"], 1);
+}
+
+#[test]
+fn grant_reads_client_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[]);
+    let home = dir.path().to_str().unwrap();
+    let client_file = dir.path().join("client.txt");
+    std::fs::write(
+        &client_file,
+        "/Applications/My App.app/Contents/MacOS/My App\n",
+    )
+    .unwrap();
+    let client_file = client_file.to_str().unwrap();
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "grant",
+        "Camera",
+        "--client-file",
+        client_file,
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("'/Applications/My App.app/Contents/MacOS/My App'"));
+
+    let (stdout, _, _) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(stdout.contains("My App"), "got: {}", stdout);
+}
+
+#[test]
+fn grant_if_missing_reports_created_then_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[]);
+    let home = dir.path().to_str().unwrap();
+    let args = [
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "grant",
+        "Camera",
+        "com.foo",
+        "--if-missing",
+    ];
+
+    let (stdout, _stderr, success) = run_tcc(&args);
+    assert!(success, "got: {}", stdout);
+    assert!(
+        stdout.contains("\"outcome\":\"created\""),
+        "got: {}",
+        stdout
+    );
+
+    let (stdout, _stderr, success) = run_tcc(&args);
+    assert!(success, "got: {}", stdout);
+    assert!(
+        stdout.contains("\"outcome\":\"skipped\""),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("\"auth_value\":2"));
+}
+
+// ── tccutil-rs check ────────────────────────────────────────────────
+
+#[test]
+fn check_oneline_reports_status_and_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo", 2),
+            ("kTCCServiceMicrophone", "com.foo", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, code) = run_tcc_code(&[
+        "--user",
+        "--home",
+        home,
+        "check",
+        "Camera",
+        "com.foo",
+        "--oneline",
+    ]);
+    assert_eq!(stdout, "Camera com.foo: granted\n");
+    assert_eq!(code, 0);
+
+    let (stdout, code) = run_tcc_code(&[
+        "--user",
+        "--home",
+        home,
+        "check",
+        "Microphone",
+        "com.foo",
+        "--oneline",
+    ]);
+    assert_eq!(stdout, "Microphone com.foo: denied\n");
+    assert_eq!(code, 2);
+
+    let (stdout, code) = run_tcc_code(&[
+        "--user",
+        "--home",
+        home,
+        "check",
+        "Camera",
+        "com.bar",
+        "--oneline",
+    ]);
+    assert_eq!(stdout, "Camera com.bar: not set\n");
+    assert_eq!(code, 3);
+}
+
 // ── Error cases ──────────────────────────────────────────────────────
 
 #[test]
@@ -169,9 +694,216 @@ fn list_json_mode_returns_valid_json() {
     assert!(stdout.contains("\"command\":\"list\""));
     assert!(stdout.contains("\"data\":{\"count\":"));
     assert!(stdout.contains("\"entries\":["));
+    assert!(stdout.contains("\"diagnostics\":["));
     assert!(stdout.contains("\"error\":null"));
 }
 
+#[test]
+fn list_json_top_level_is_the_envelope_not_an_array() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let home = dir.path().to_str().unwrap();
+    let (stdout, stderr, success) = run_tcc(&["--user", "--home", home, "--json", "list"]);
+    assert!(success, "stderr: {}", stderr);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let envelope = json.as_object().expect("top level should be an object");
+    let keys: Vec<&str> = envelope.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["command", "data", "error", "ok", "warnings"]);
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["command"], "list");
+    assert!(json["error"].is_null());
+
+    let data = json["data"].as_object().unwrap();
+    let keys: Vec<&str> = data.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["count", "diagnostics", "entries", "generated_at"]);
+    let entries = json["data"]["entries"].as_array().unwrap();
+    assert_eq!(json["data"]["count"], entries.len());
+    assert_eq!(entries[0]["service_raw"], "kTCCServiceCamera");
+    assert_eq!(entries[0]["client"], "com.foo.app");
+    assert_eq!(entries[0]["client_type"], 1);
+}
+
+#[test]
+fn list_json_entries_carry_epoch_seconds_next_to_the_formatted_time() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.app", 2),
+            ("kTCCServiceMicrophone", "com.foo.app", 2),
+        ],
+    );
+    // A CoreData timestamp (seconds since 2001) on one row, none on the other
+    let conn = rusqlite::Connection::open(
+        dir.path()
+            .join("Library/Application Support/com.apple.TCC/TCC.db"),
+    )
+    .unwrap();
+    conn.execute(
+        "UPDATE access SET last_modified = 700000000 WHERE service = 'kTCCServiceCamera'",
+        [],
+    )
+    .unwrap();
+    drop(conn);
+    let home = dir.path().to_str().unwrap();
+    let (stdout, stderr, success) = run_tcc(&["--user", "--home", home, "--json", "list"]);
+    assert!(success, "stderr: {}", stderr);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let entries = json["data"]["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["service_raw"], "kTCCServiceCamera");
+    assert_eq!(entries[0]["last_modified_ts"], 1_678_307_200);
+    assert!(
+        entries[0]["last_modified"]
+            .as_str()
+            .unwrap()
+            .starts_with("2023-03-")
+    );
+    assert_eq!(entries[1]["last_modified_ts"], 0);
+    assert_eq!(entries[1]["last_modified"], "N/A");
+}
+
+#[test]
+fn json_stream_in_read_order_streams_redacted_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceMicrophone", "com.secret.b", 2),
+            ("kTCCServiceCamera", "com.secret.a", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "list",
+        "--no-sort",
+        "--redact",
+        "--json-stream",
+        "array",
+    ]);
+    assert!(success, "got: {}", stdout);
+    assert!(
+        stdout.starts_with("[{") && stdout.ends_with("}]\n"),
+        "got: {}",
+        stdout
+    );
+    assert!(!stdout.contains("com.secret"), "got: {}", stdout);
+    // Read order: the Microphone row was inserted first
+    let mic = stdout.find("kTCCServiceMicrophone").unwrap();
+    assert!(mic < stdout.find("kTCCServiceCamera").unwrap());
+}
+
+#[test]
+fn redact_hides_the_client_in_every_output_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let secret = "/Applications/Secret Tool.app/Contents/MacOS/Secret Tool";
+    make_home_db(dir.path(), &[("kTCCServiceCamera", secret, 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    for args in [
+        vec!["list", "--redact"],
+        vec!["list", "--redact", "--wide"],
+        vec!["--json", "list", "--redact", "--truncate-client", "40"],
+        vec![
+            "list",
+            "--redact",
+            "--tabular-json",
+            "--truncate-client",
+            "40",
+        ],
+        vec!["list", "--redact", "--json-stream", "ndjson"],
+        vec!["list", "--redact", "--json-stream", "array", "--no-sort"],
+        vec!["list", "--redact", "--template", "{client}|{service}"],
+        vec!["--csv", "list", "--redact"],
+        vec!["list", "--redact", "--app-centric"],
+        vec!["list", "--redact", "--distinct-clients"],
+    ] {
+        let mut full = vec!["--user", "--home", home];
+        full.extend(&args);
+        let (stdout, stderr, success) = run_tcc(&full);
+        assert!(success, "{:?} failed: {}", args, stderr);
+        assert!(!stdout.is_empty(), "{:?} printed nothing", args);
+        assert!(
+            !stdout.contains("Secret"),
+            "{:?} leaked the client: {}",
+            args,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn machine_output_ignores_terminal_width() {
+    let dir = tempfile::tempdir().unwrap();
+    let long_client =
+        "/Applications/Some Very Long Application Name.app/Contents/MacOS/Helper With A Long Name";
+    make_home_db(
+        dir.path(),
+        &[("kTCCServiceSystemPolicyAllFiles", long_client, 2)],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    for args in [
+        vec!["--json", "list"],
+        vec!["list", "--json-stream", "ndjson"],
+        vec!["list", "--template", "{client}|{service}"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_tccutil-rs"))
+            .args(["--user", "--home", home])
+            .args(&args)
+            .env("COLUMNS", "20")
+            .output()
+            .expect("failed to execute tccutil-rs binary");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{:?} failed", args);
+        assert!(
+            stdout.contains(long_client),
+            "{:?} truncated: {}",
+            args,
+            stdout
+        );
+        assert!(
+            stdout.contains("Full Disk Access"),
+            "{:?}: {}",
+            args,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn doctor_json_has_known_checks_and_statuses() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, _success) = run_tcc(&["--user", "--home", home, "--json", "doctor"]);
+    assert_basic_json_shape(&stdout);
+    assert!(stdout.contains("\"command\":\"doctor\""));
+    for id in ["root", "sip", "fda", "schema", "write_probe"] {
+        assert!(
+            stdout.contains(&format!("\"id\":\"{}\"", id)),
+            "missing {}: {}",
+            id,
+            stdout
+        );
+    }
+    let statuses: Vec<&str> = stdout
+        .split("\"status\":\"")
+        .skip(1)
+        .map(|rest| rest.split('"').next().unwrap())
+        .collect();
+    assert_eq!(statuses.len(), 6, "overall status plus one per check");
+    for status in statuses {
+        assert!(["ok", "warn", "fail"].contains(&status), "got {}", status);
+    }
+}
+
 #[test]
 fn grant_json_mode_failure_has_error_shape() {
     let (stdout, stderr, success) = run_tcc(&[
@@ -193,3 +925,578 @@ fn grant_json_mode_failure_has_error_shape() {
     assert!(stdout.contains("\"error\":{\"kind\":"));
     assert!(stdout.contains("\"message\":\""));
 }
+
+#[test]
+fn get_returns_the_exact_entry_or_a_not_found_error() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "get",
+        "Camera",
+        "com.foo.app",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["command"], "get");
+    assert_eq!(json["data"]["service_raw"], "kTCCServiceCamera");
+    assert_eq!(json["data"]["client"], "com.foo.app");
+    assert_eq!(json["data"]["status"], "granted");
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user", "--home", home, "--json", "get", "Camera", "com.foo",
+    ]);
+    assert!(!success, "a prefix of the client must not match");
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert!(json["data"].is_null());
+    assert_eq!(json["error"]["kind"], "NotFound");
+
+    let (stdout, stderr, success) =
+        run_tcc(&["--user", "--home", home, "get", "Camera", "com.foo.app"]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("com.foo.app"));
+    let (_stdout, code) = run_tcc_code(&["--user", "--home", home, "get", "Camera", "com.foo"]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn ambiguous_service_json_error_lists_candidates() {
+    let (stdout, _, success) = run_tcc(&["grant", "Photo", "com.example.app", "--json"]);
+    assert!(!success, "an ambiguous service should fail");
+
+    assert_basic_json_shape(&stdout);
+    assert!(stdout.contains("\"kind\":\"AmbiguousService\""));
+    assert!(stdout.contains(
+        "\"candidates\":[{\"service_raw\":\"kTCCServicePhotos\",\"display\":\"Photos\"},\
+         {\"service_raw\":\"kTCCServicePhotosAdd\",\"display\":\"Photos (Add Only)\"}]"
+    ));
+}
+
+#[test]
+fn verify_schema_gates_on_the_hashed_digest() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "--hash-schema"]);
+    assert!(success, "--hash-schema should not need a subcommand");
+    let digest = stdout.split_whitespace().nth(1).unwrap().to_string();
+    assert_eq!(digest.len(), 10, "got: {}", stdout);
+
+    let (stdout, _stderr, success) =
+        run_tcc(&["--user", "--home", home, "--verify-schema", &digest, "list"]);
+    assert!(success);
+    assert!(stdout.contains("com.foo.app"), "got: {}", stdout);
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--verify-schema",
+        "0000000000",
+        "list",
+    ]);
+    assert!(!success);
+    assert!(stderr.contains("mismatch"), "got: {}", stderr);
+    assert!(stderr.contains(&digest), "got: {}", stderr);
+    assert!(!stdout.contains("com.foo.app"));
+}
+
+#[test]
+fn verify_schema_alone_prints_expected_and_actual() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, _success) = run_tcc(&["--user", "--home", home, "--hash-schema"]);
+    let digest = stdout.split_whitespace().nth(1).unwrap().to_string();
+
+    let (stdout, code) = run_tcc_code(&["--user", "--home", home, "--verify-schema", &digest]);
+    assert_eq!(code, 0, "got: {}", stdout);
+    assert!(
+        stdout.contains(&format!("expected  {}", digest)),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!("{}  ok", digest)),
+        "got: {}",
+        stdout
+    );
+
+    let (stdout, code) = run_tcc_code(&["--user", "--home", home, "--verify-schema", "0000000000"]);
+    assert_eq!(code, 1, "got: {}", stdout);
+    assert!(stdout.contains("expected  0000000000"), "got: {}", stdout);
+    assert!(
+        stdout.contains(&format!("{}  mismatch", digest)),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn list_footer_can_be_dropped_or_reformatted() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.app", 2),
+            ("kTCCServiceMicrophone", "com.foo.app", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(success);
+    assert!(stdout.contains("2 entries total"), "got: {}", stdout);
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "list", "--no-footer"]);
+    assert!(success);
+    assert!(stdout.contains("com.foo.app"));
+    assert!(!stdout.contains("entries total"), "got: {}", stdout);
+    assert!(
+        !stdout.ends_with("\n\n"),
+        "no blank separator without a footer"
+    );
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "list",
+        "--footer-format",
+        "{granted} of {count} granted",
+    ]);
+    assert!(success);
+    assert!(stdout.ends_with("1 of 2 granted\n"), "got: {}", stdout);
+}
+
+#[test]
+fn list_distinct_clients_prints_each_client_once() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.app", 2),
+            ("kTCCServiceMicrophone", "com.foo.app", 0),
+            ("kTCCServicePhotos", "com.foo.app", 2),
+            ("kTCCServiceCamera", "/usr/local/bin/tool", 2),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, _stderr, success) =
+        run_tcc(&["--user", "--home", home, "list", "--distinct-clients"]);
+    assert!(success);
+    assert_eq!(stdout, "/usr/local/bin/tool\ncom.foo.app\n");
+
+    let (stdout, _stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "list",
+        "--distinct-clients",
+        "--compact",
+    ]);
+    assert!(success);
+    assert!(
+        stdout.contains("\"count\":2,\"clients\":[\"com.foo.app\",\"tool\"]"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn checksum_is_stable_for_an_unchanged_db() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.app", 2),
+            ("kTCCServiceMicrophone", "com.bar.app", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+    let checksum = |args: &[&str]| {
+        let mut full = vec!["--user", "--home", home, "--json"];
+        full.extend_from_slice(args);
+        let (stdout, stderr, success) = run_tcc(&full);
+        assert!(success, "stderr: {}", stderr);
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        json["data"]["checksum"].as_str().unwrap().to_string()
+    };
+
+    let first = checksum(&["list", "--checksum"]);
+    assert_eq!(first.len(), 40);
+    assert_eq!(checksum(&["list", "--checksum"]), first);
+    // Read order and redaction do not change it
+    assert_eq!(
+        checksum(&["list", "--checksum", "--no-sort", "--redact"]),
+        first
+    );
+    let out = dir.path().join("export.db");
+    assert_eq!(
+        checksum(&["export", out.to_str().unwrap(), "--checksum"]),
+        first
+    );
+
+    let (stdout, _stderr, success) = run_tcc(&["--user", "--home", home, "list", "--checksum"]);
+    assert!(success);
+    assert!(
+        stdout.ends_with(&format!("Checksum: {}\n", first)),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn verbose_diff_shows_what_grant_and_revoke_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 0)]);
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "grant",
+        "Camera",
+        "com.foo.app",
+        "--force",
+        "--verbose-diff",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("  auth_value: 0 → 2\n"), "got: {}", stdout);
+    assert!(!stdout.contains("client_type"), "got: {}", stdout);
+
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "revoke",
+        "Camera",
+        "com.foo.app",
+        "--verbose-diff",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let diff = json["data"]["diff"].as_array().unwrap();
+    let auth = diff.iter().find(|c| c["column"] == "auth_value").unwrap();
+    assert_eq!(auth["before"], 2);
+    assert!(auth["after"].is_null());
+}
+
+#[test]
+fn json_envelope_carries_warnings_for_malformed_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let db_path = dir
+        .path()
+        .join("Library/Application Support/com.apple.TCC/TCC.db");
+    rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .execute(
+            "INSERT INTO access (service, client, client_type, auth_value) \
+             VALUES ('kTCCServiceCamera', 'com.bad.app', 1, 'not a number')",
+            [],
+        )
+        .unwrap();
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, stderr, success) = run_tcc(&["--user", "--home", home, "--json", "list"]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(!stderr.contains("malformed"), "stderr: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["data"]["count"], 1);
+    let warnings = json["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1, "got: {}", stdout);
+    assert!(
+        warnings[0]
+            .as_str()
+            .unwrap()
+            .starts_with("skipping malformed row in "),
+        "got: {}",
+        stdout
+    );
+
+    // Without --json the warning still goes to stderr
+    let (_stdout, stderr, success) = run_tcc(&["--user", "--home", home, "list"]);
+    assert!(success);
+    assert!(
+        stderr.contains("Warning: skipping malformed row"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn csv_lists_one_row_per_entry_and_summarizes_other_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "/Applications/A, \"B\".app", 2),
+            ("kTCCServiceMicrophone", "com.bar.app", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+
+    let (stdout, stderr, success) = run_tcc(&["--csv", "--user", "--home", home, "list"]);
+    assert!(success, "stderr: {}", stderr);
+    let lines: Vec<&str> = stdout.split("\r\n").collect();
+    assert_eq!(
+        lines[0],
+        "service,service_raw,client,status,auth_value,source,last_modified"
+    );
+    assert!(lines[1..3].contains(
+        &"Camera,kTCCServiceCamera,\"/Applications/A, \"\"B\"\".app\",granted,2,user,N/A"
+    ));
+    assert!(
+        lines[1..3].contains(&"Microphone,kTCCServiceMicrophone,com.bar.app,denied,0,user,N/A")
+    );
+    assert_eq!(lines[3], "");
+
+    let (stdout, _, success) = run_tcc(&[
+        "--csv", "--user", "--home", home, "revoke", "Camera", "com.nope",
+    ]);
+    assert!(!success);
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("command,ok,message,warnings"));
+    assert!(lines.next().unwrap().starts_with("revoke,false,"));
+
+    let (stdout, _, success) = run_tcc(&[
+        "--csv",
+        "--user",
+        "--home",
+        home,
+        "grant",
+        "Camera",
+        "com.new.app",
+    ]);
+    assert!(success);
+    assert!(stdout.lines().nth(1).unwrap().starts_with("grant,true,"));
+
+    // The row counts the warnings; their text goes to stderr
+    let (stdout, stderr, success) = run_tcc(&[
+        "--csv",
+        "--user",
+        "--home",
+        home,
+        "grant",
+        "AppleEvents",
+        "com.new.app",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    let warnings = stderr.matches("Warning: ").count();
+    assert!(warnings > 0, "stderr: {}", stderr);
+    assert!(
+        stdout
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(&format!(",{}", warnings))
+    );
+
+    // Commands without a summary message have no CSV form
+    let (_, code) = run_tcc_code(&["--csv", "--user", "--home", home, "info"]);
+    assert_eq!(code, 2);
+
+    // An unreadable DB has no row to carry it, so it is reported on stderr
+    let garbage = dir.path().join("garbage.db");
+    std::fs::write(&garbage, "not a database").unwrap();
+    let (stdout, stderr, _) = run_tcc(&["--csv", "--db", garbage.to_str().unwrap(), "list"]);
+    assert_eq!(stdout.lines().count(), 1, "header only: {}", stdout);
+    assert!(stderr.contains("not a database"), "stderr: {}", stderr);
+
+    let (_, code) = run_tcc_code(&["--csv", "--json", "list"]);
+    assert_ne!(code, 0);
+}
+
+#[test]
+fn dump_is_a_bare_array_of_raw_rows_with_or_without_json() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let home = dir.path().to_str().unwrap();
+
+    let (plain, stderr, success) = run_tcc(&["--user", "--home", home, "dump"]);
+    assert!(success, "stderr: {}", stderr);
+    let (json, _, _) = run_tcc(&["--user", "--home", home, "--json", "dump"]);
+    assert_eq!(plain, json);
+
+    let rows: serde_json::Value = serde_json::from_str(&plain).unwrap();
+    let rows = rows.as_array().expect("dump should be a bare array");
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["source"], "user");
+    let row = &rows[0]["row"];
+    assert_eq!(row["service"], "kTCCServiceCamera");
+    assert_eq!(row["client_type"], 1);
+    assert_eq!(row["auth_value"], 2);
+    assert_eq!(row["last_modified"], 0);
+}
+
+#[test]
+fn restore_puts_back_what_dump_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(
+        dir.path(),
+        &[
+            ("kTCCServiceCamera", "com.foo.app", 2),
+            ("kTCCServiceMicrophone", "com.bar.app", 0),
+        ],
+    );
+    let home = dir.path().to_str().unwrap();
+    let (dumped, stderr, success) = run_tcc(&["--user", "--home", home, "dump"]);
+    assert!(success, "stderr: {}", stderr);
+    let file = dir.path().join("backup.json");
+    std::fs::write(&file, &dumped).unwrap();
+    let file = file.to_str().unwrap();
+
+    let (_, _, success) = run_tcc(&["--user", "--home", home, "revoke", "Camera", "com.foo.app"]);
+    assert!(success);
+    let (stdout, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "restore",
+        file,
+        "--dry-run",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["data"]["dry_run"], true);
+    assert_eq!(json["data"]["restored"], 2);
+    assert_eq!(json["data"]["skipped"], 0);
+    let (stdout, _, _) = run_tcc(&["--user", "--home", home, "dump"]);
+    assert_ne!(stdout, dumped, "a dry run must not write");
+
+    let (stdout, stderr, success) = run_tcc(&["--user", "--home", home, "restore", file]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("Restored 2 rows, skipped 0"), "{}", stdout);
+    let (stdout, _, _) = run_tcc(&["--user", "--home", home, "dump"]);
+    let rows: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+
+    // A row that does not fit is skipped with its reason
+    let extra = dumped.trim_end().trim_end_matches(']').to_string()
+        + r#",{"source":"user","row":{"service":"kTCCServiceNope","client":"x","client_type":1,"auth_value":2}}]"#;
+    std::fs::write(file, extra).unwrap();
+    let (stdout, stderr, success) = run_tcc(&["--user", "--home", home, "restore", file]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("skipped 1"), "{}", stdout);
+    assert!(
+        stderr.contains("skipping dump row 3: unknown service key 'kTCCServiceNope'"),
+        "stderr: {}",
+        stderr
+    );
+
+    std::fs::write(file, "not json").unwrap();
+    let (stdout, _, success) = run_tcc(&["--user", "--home", home, "--json", "restore", file]);
+    assert!(!success);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["error"]["kind"], "DumpInvalid");
+}
+
+#[test]
+fn grant_refuses_to_overwrite_a_denied_entry_without_force() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 0)]);
+    let home = dir.path().to_str().unwrap();
+    let status = || {
+        let (stdout, _, _) = run_tcc(&["--user", "--home", home, "--json", "list"]);
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        json["data"]["entries"][0]["status"].clone()
+    };
+
+    let (stdout, _, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "--json",
+        "grant",
+        "Camera",
+        "com.foo.app",
+    ]);
+    assert!(!success);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["error"]["kind"], "EntryExists");
+    assert_eq!(status(), "denied");
+
+    let (_, stderr, success) = run_tcc(&[
+        "--user",
+        "--home",
+        home,
+        "grant",
+        "Camera",
+        "com.foo.app",
+        "--force",
+    ]);
+    assert!(success, "stderr: {}", stderr);
+    assert_eq!(status(), "granted");
+    // Re-granting a granted entry needs no --force
+    let (_, stderr, success) =
+        run_tcc(&["--user", "--home", home, "grant", "Camera", "com.foo.app"]);
+    assert!(success, "stderr: {}", stderr);
+}
+
+#[test]
+fn db_flag_reads_and_writes_one_explicit_file() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let db = dir
+        .path()
+        .join("Library/Application Support/com.apple.TCC/TCC.db");
+    let db = db.to_str().unwrap();
+
+    // A system DB service is written to the file too
+    let (_, stderr, success) = run_tcc(&["--db", db, "grant", "Accessibility", "com.bar.app"]);
+    assert!(success, "stderr: {}", stderr);
+
+    let (stdout, stderr, success) = run_tcc(&["--db", db, "--json", "list"]);
+    assert!(success, "stderr: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let entries = json["data"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e["source"] == "user"));
+
+    let (stdout, _, _) = run_tcc(&["--db", db, "paths"]);
+    assert_eq!(stdout.trim(), db);
+
+    let missing = dir.path().join("missing.db");
+    let (_, code) = run_tcc_code(&["--db", missing.to_str().unwrap(), "list"]);
+    assert_eq!(code, 1, "a mistyped --db path is reported");
+}
+
+#[test]
+fn db_dash_reads_a_db_piped_on_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    make_home_db(dir.path(), &[("kTCCServiceCamera", "com.foo.app", 2)]);
+    let bytes = std::fs::read(
+        dir.path()
+            .join("Library/Application Support/com.apple.TCC/TCC.db"),
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) = run_tcc_stdin(&["--db", "-", "--json", "list"], &bytes);
+    assert!(success, "stderr: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let entries = json["data"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["client"], "com.foo.app");
+
+    let (_, stderr, success) =
+        run_tcc_stdin(&["--db", "-", "grant", "Camera", "com.bar.app"], &bytes);
+    assert!(!success, "the stdin copy is read-only");
+    assert!(stderr.contains("read-only"), "stderr: {}", stderr);
+
+    let (_, stderr, success) = run_tcc_stdin(&["--db", "-", "list"], "");
+    assert!(!success, "empty stdin is reported");
+    assert!(stderr.contains("stdin was empty"), "stderr: {}", stderr);
+}